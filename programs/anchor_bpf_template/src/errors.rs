@@ -0,0 +1,112 @@
+//! Program errors.
+//!
+//! Clients branch on the numeric codes, which are `6000 + discriminant`. Discriminants
+//! are fixed: variants are never renumbered or removed, new ones are appended.
+
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ClobError {
+    #[msg("Math operation overflowed")]
+    MathOverflow = 0,
+    #[msg("Lot sizes and tick size must be non-zero")]
+    InvalidLotSize = 1,
+    #[msg("Fee is above 100%")]
+    InvalidFee = 2,
+    #[msg("Price must be non-zero")]
+    InvalidPrice = 3,
+    #[msg("Price is not a multiple of the tick size")]
+    InvalidTick = 4,
+    #[msg("Quantity must be non-zero")]
+    InvalidQuantity = 5,
+    #[msg("Expiry timestamp is in the past")]
+    InvalidExpiry = 6,
+    #[msg("Post-only order would cross the book")]
+    WouldCrossPostOnly = 7,
+    #[msg("Book side is full and the order is not better priced than its worst")]
+    BookFull = 8,
+    #[msg("Event queue is full")]
+    EventQueueFull = 9,
+    #[msg("Open orders account has no free order slots")]
+    TooManyOpenOrders = 10,
+    #[msg("Resting orders need an open orders account")]
+    OpenOrdersRequired = 11,
+    #[msg("Order not found")]
+    OrderNotFound = 12,
+    #[msg("Signer is not allowed to perform this action")]
+    Unauthorized = 13,
+    #[msg("Market does not accept new orders")]
+    MarketNotActive = 14,
+    #[msg("Market is paused")]
+    MarketPaused = 15,
+    #[msg("Fewer base lots filled than the requested minimum")]
+    SlippageExceeded = 16,
+    #[msg("Account does not belong to this market")]
+    WrongMarket = 17,
+    #[msg("Token account does not hold enough funds")]
+    InsufficientFunds = 18,
+    #[msg("Token account has the wrong mint")]
+    WrongMint = 19,
+    #[msg(
+        "Vault must hold the market's mint, be owned by the market authority and have no delegate"
+    )]
+    InvalidVault = 20,
+    #[msg("Oracle account is missing, malformed or not the market's oracle")]
+    InvalidOracle = 21,
+    #[msg("Oracle price is stale or not trading")]
+    StaleOracle = 22,
+    #[msg("Limit price is outside the oracle price band")]
+    PriceOutsideBand = 23,
+    #[msg("Price band must be positive when an oracle is set")]
+    InvalidPriceBand = 24,
+    #[msg("Pegged orders need a market oracle, and pegged bids a peg limit")]
+    InvalidPeg = 25,
+    #[msg("Market has no midpoint book, or it was not passed")]
+    MidpointBookRequired = 26,
+    #[msg("Account is already set up")]
+    AlreadyInitialized = 27,
+    #[msg("Trigger account has no free slots")]
+    TooManyTriggers = 28,
+    #[msg("No trigger order is ready to execute")]
+    NoTriggersReady = 29,
+    #[msg("Order groups need a non-zero id and at least two lit or trigger orders")]
+    InvalidGroup = 30,
+    #[msg("Iceberg orders must rest and show fewer base lots than their size")]
+    InvalidIceberg = 31,
+    #[msg("TWAP orders need a slice size within their size and a non-zero interval")]
+    InvalidTwap = 32,
+    #[msg("No TWAP slice is due")]
+    TwapNotDue = 33,
+    #[msg("A circuit breaker needs a non-zero window and cool-down")]
+    InvalidCircuitBreaker = 34,
+    #[msg("Only fixed-price limit and post-only orders can be placed in auctions and batches")]
+    InvalidAuctionOrder = 35,
+    #[msg("Market is not in an auction")]
+    NotInAuction = 36,
+    #[msg("The market is not in batch mode or the batch is still collecting orders")]
+    BatchNotDue = 37,
+    #[msg("Top-of-queue share must be at most 10000 basis points")]
+    InvalidMatchingMode = 38,
+    #[msg("Tick bands need ascending minimum prices, each a multiple of a non-zero tick size")]
+    InvalidTickSchedule = 39,
+    #[msg("Order is below the market's minimum size or value")]
+    OrderTooSmall = 40,
+    #[msg("Bond forfeit share must be at most 10000 basis points")]
+    InvalidOrderBond = 41,
+    #[msg("Heartbeats are disabled or the last one is within its interval")]
+    HeartbeatNotMissed = 42,
+    #[msg("The OpenOrders sequence number is not the expected one")]
+    SequenceMismatch = 43,
+    #[msg("Too many orders placed in this slot")]
+    OrderRateLimitExceeded = 44,
+    #[msg("Order would take the open notional over its limit")]
+    OpenNotionalLimitExceeded = 45,
+    #[msg("Order would take the net base position over its limit")]
+    PositionLimitExceeded = 46,
+    #[msg("The same account was passed more than once")]
+    DuplicateAccount = 47,
+    #[msg("An auction can only be ended by uncross_auction")]
+    AuctionNotUncrossed = 48,
+    #[msg("The delegate cannot be changed while risk limits are set")]
+    RiskLimitsSet = 49,
+}
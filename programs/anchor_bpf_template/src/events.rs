@@ -0,0 +1,238 @@
+//! Events emitted through `emit!` so indexers can follow the program from transaction
+//! logs alone.
+//!
+//! Schemas are stable: fields are only ever appended, never reordered or removed.
+
+use anchor_lang::prelude::*;
+
+use crate::state::{OrderType, Side, TriggerCondition, TriggerSource};
+
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarketCreated {
+    pub market: Pubkey,
+    pub admin: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub event_queue: Pubkey,
+    pub base_lot_size: u64,
+    pub quote_lot_size: u64,
+    pub tick_size: u64,
+    pub taker_fee_bps: u16,
+}
+
+/// An order went through matching. Emitted after its `OrderFilled` events.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderPlaced {
+    pub market: Pubkey,
+    /// `OpenOrders` account, or the signer's wallet for swaps.
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub client_order_id: u64,
+    pub side: Side,
+    pub order_type: OrderType,
+    pub price: u64,
+    pub max_base_lots: u64,
+    pub base_lots_filled: u64,
+    pub quote_lots_filled: u64,
+    /// Native quote units.
+    pub taker_fee: u64,
+    /// Base lots left resting on the book.
+    pub posted_base_lots: u64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderFilled {
+    pub market: Pubkey,
+    pub maker: Pubkey,
+    pub maker_order_id: u64,
+    pub maker_client_order_id: u64,
+    /// Whether the maker order left the book with this fill.
+    pub maker_out: bool,
+    pub taker: Pubkey,
+    pub taker_order_id: u64,
+    pub taker_side: Side,
+    pub price: u64,
+    pub quantity: u64,
+    /// Whether the trade uncrossed an auction or a batch, where both orders were resting.
+    /// It is then reported once, the bid as the maker and the ask as the taker.
+    pub auction: bool,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderCancelled {
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub client_order_id: u64,
+    pub side: Side,
+    pub price: u64,
+    /// Base lots removed from the book.
+    pub quantity: u64,
+}
+
+/// A resting order was found past its expiry during matching and removed.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderExpired {
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub client_order_id: u64,
+    pub side: Side,
+    pub price: u64,
+    pub quantity: u64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FundsSettled {
+    pub market: Pubkey,
+    pub open_orders: Pubkey,
+    pub owner: Pubkey,
+    /// Native base units withdrawn.
+    pub base_amount: u64,
+    /// Native quote units withdrawn.
+    pub quote_amount: u64,
+    /// Lamports of refunded order bonds paid out.
+    pub bond_lamports: u64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeesSwept {
+    pub market: Pubkey,
+    pub destination: Pubkey,
+    /// Native quote units.
+    pub amount: u64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TriggerPlaced {
+    pub market: Pubkey,
+    pub open_orders: Pubkey,
+    pub trigger_id: u64,
+    pub client_order_id: u64,
+    pub side: Side,
+    pub price: u64,
+    pub max_base_lots: u64,
+    pub source: TriggerSource,
+    pub condition: TriggerCondition,
+    pub trigger_price: u64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TriggerCancelled {
+    pub market: Pubkey,
+    pub open_orders: Pubkey,
+    pub trigger_id: u64,
+    pub client_order_id: u64,
+}
+
+/// A trigger order fired and was placed as `order_id`, whose `OrderPlaced` follows.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TriggerExecuted {
+    pub market: Pubkey,
+    pub open_orders: Pubkey,
+    pub trigger_id: u64,
+    pub client_order_id: u64,
+    pub order_id: u64,
+    /// Paid the execution reward.
+    pub executor: Pubkey,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TwapPlaced {
+    pub market: Pubkey,
+    pub open_orders: Pubkey,
+    pub twap_order: Pubkey,
+    pub client_order_id: u64,
+    pub side: Side,
+    pub price: u64,
+    pub total_base_lots: u64,
+    pub slice_base_lots: u64,
+    pub interval_seconds: u32,
+}
+
+/// A TWAP slice was placed as `order_id`, whose `OrderPlaced` precedes this.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TwapSliceExecuted {
+    pub market: Pubkey,
+    pub open_orders: Pubkey,
+    pub twap_order: Pubkey,
+    pub order_id: u64,
+    pub base_lots_filled: u64,
+    pub remaining_base_lots: u64,
+    /// Paid the slice reward.
+    pub executor: Pubkey,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TwapCancelled {
+    pub market: Pubkey,
+    pub open_orders: Pubkey,
+    pub twap_order: Pubkey,
+    pub client_order_id: u64,
+    /// Base lots never sliced.
+    pub remaining_base_lots: u64,
+}
+
+/// A fill moved the price past the circuit breaker, the market is cancel-only until
+/// `halted_until`.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CircuitBreakerTripped {
+    pub market: Pubkey,
+    pub reference_price: u64,
+    pub price: u64,
+    pub halted_until: i64,
+}
+
+/// An auction ended, `base_lots` trading at `price` through `OrderFilled` events emitted
+/// before this one, one per trade. `price` is 0 when no orders crossed.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuctionUncrossed {
+    pub market: Pubkey,
+    pub price: u64,
+    pub base_lots: u64,
+}
+
+/// A batch closed, `base_lots` trading at `price` through `OrderFilled` events emitted
+/// before this one, one per trade. `price` is 0 when no orders crossed.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchCleared {
+    pub market: Pubkey,
+    pub batch_start_slot: u64,
+    pub price: u64,
+    pub base_lots: u64,
+}
+
+/// A resting order was removed from a full book side to make room for a better priced
+/// one, `evicted_by`. Its funds are returned through the event queue.
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrderEvicted {
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub client_order_id: u64,
+    pub side: Side,
+    pub price: u64,
+    pub quantity: u64,
+    pub evicted_by: u64,
+}
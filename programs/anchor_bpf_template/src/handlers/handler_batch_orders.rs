@@ -0,0 +1,180 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+use anchor_spl::token::{Token, TokenAccount};
+
+use crate::errors::ClobError;
+use crate::matching::{post_bond, settle_taker, OrderParams, Orderbook};
+use crate::state::*;
+use crate::utils;
+
+/// Cancels `cancel_order_ids`, then places `orders`, atomically. Orders that already left
+/// the book are skipped rather than failing the whole batch, and so are the orders after
+/// one that trips the circuit breaker.
+pub(crate) fn process(
+    ctx: Context<BatchOrders>,
+    cancel_order_ids: Vec<u64>,
+    orders: Vec<OrderParams>,
+    expected_sequence: Option<u64>,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+    let open_orders_key = ctx.accounts.open_orders.key();
+    let remaining_accounts = ctx.remaining_accounts;
+    let accounts = ctx.accounts;
+
+    require!(
+        accounts.market.status != MarketStatus::Paused,
+        ClobError::MarketPaused
+    );
+    require!(
+        orders.is_empty() || accounts.market.accepts_orders(now),
+        ClobError::MarketNotActive
+    );
+    require!(
+        accounts
+            .open_orders
+            .is_owner_or_delegate(accounts.signer.key),
+        ClobError::Unauthorized
+    );
+    accounts.open_orders.bump_sequence(expected_sequence)?;
+    let exposure = accounts.open_orders.exposure();
+
+    let oracle_price = if orders.is_empty() {
+        None
+    } else {
+        accounts.market.oracle_price(remaining_accounts, now)?
+    };
+    let mut midpoint_book = accounts.market.load_midpoint_book(remaining_accounts)?;
+    let mut book = Orderbook {
+        bids: &mut accounts.bids,
+        asks: &mut accounts.asks,
+        oracle_price,
+        midpoint: midpoint_book.as_deref_mut(),
+    };
+    for order_id in cancel_order_ids {
+        let side = match accounts.open_orders.find_order(order_id) {
+            Some(order) => order.side,
+            None => continue,
+        };
+        if !book.contains(side, order_id) {
+            continue;
+        }
+        book.cancel_order(
+            &accounts.market,
+            &mut accounts.open_orders,
+            open_orders_key,
+            order_id,
+        )?;
+    }
+
+    let mut base_deposit: u64 = 0;
+    let mut quote_deposit: u64 = 0;
+    let mut bonds: u64 = 0;
+    for params in orders {
+        // A fill that tripped the circuit breaker leaves the market cancel-only.
+        if !accounts.market.accepts_orders(now) {
+            break;
+        }
+        accounts.open_orders.count_order(clock.slot)?;
+        params.validate(&accounts.market, now)?;
+        accounts
+            .market
+            .check_price_band(oracle_price, params.price)?;
+        let outcome = book.new_order(
+            &mut accounts.market,
+            &mut accounts.event_queue,
+            open_orders_key,
+            Some(&mut accounts.open_orders),
+            &params,
+            now,
+        )?;
+        let deposit = settle_taker(
+            &accounts.market,
+            &mut accounts.open_orders,
+            &params,
+            &outcome,
+        )?;
+        bonds = bonds
+            .checked_add(post_bond(
+                &accounts.market,
+                &mut accounts.open_orders,
+                &outcome,
+            ))
+            .ok_or_else(|| error!(ClobError::MathOverflow))?;
+        let total = match params.side {
+            Side::Bid => &mut quote_deposit,
+            Side::Ask => &mut base_deposit,
+        };
+        *total = total
+            .checked_add(deposit)
+            .ok_or_else(|| error!(ClobError::MathOverflow))?;
+    }
+    accounts.open_orders.check_risk_limits(&exposure)?;
+
+    if let Some(midpoint_book) = &midpoint_book {
+        midpoint_book.exit(&crate::ID)?;
+    }
+    let token_program = accounts.token_program.to_account_info();
+    let signer = accounts.signer.to_account_info();
+    utils::deposit(
+        &token_program,
+        &accounts.user_base_account,
+        &accounts.base_vault.to_account_info(),
+        &signer,
+        base_deposit,
+    )?;
+    utils::deposit(
+        &token_program,
+        &accounts.user_quote_account,
+        &accounts.quote_vault.to_account_info(),
+        &signer,
+        quote_deposit,
+    )?;
+    utils::deposit_lamports(
+        &accounts.system_program.to_account_info(),
+        &signer,
+        &accounts.market.to_account_info(),
+        bonds,
+    )
+}
+
+#[derive(Accounts)]
+pub struct BatchOrders<'info> {
+    /// The `OpenOrders` owner or its delegate, pays the order bonds.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(mut, has_one = market @ ClobError::WrongMarket)]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+
+    #[account(mut,
+        has_one = bids @ ClobError::WrongMarket,
+        has_one = asks @ ClobError::WrongMarket,
+        has_one = event_queue @ ClobError::WrongMarket,
+        has_one = base_vault @ ClobError::WrongMarket,
+        has_one = quote_vault @ ClobError::WrongMarket,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(mut)]
+    pub bids: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub asks: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub event_queue: Box<Account<'info, EventQueue>>,
+
+    #[account(mut)]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = user_base_account.mint == market.base_mint @ ClobError::WrongMint)]
+    pub user_base_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut,
+        constraint = user_quote_account.mint == market.quote_mint @ ClobError::WrongMint,
+    )]
+    pub user_quote_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
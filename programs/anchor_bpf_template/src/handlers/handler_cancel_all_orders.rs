@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::matching::Orderbook;
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<CancelAllOrders>) -> Result<()> {
+    let open_orders_key = ctx.accounts.open_orders.key();
+    let remaining_accounts = ctx.remaining_accounts;
+    let accounts = ctx.accounts;
+
+    require!(
+        accounts.market.status != MarketStatus::Paused,
+        ClobError::MarketPaused
+    );
+    require!(
+        accounts
+            .open_orders
+            .is_owner_or_delegate(accounts.signer.key),
+        ClobError::Unauthorized
+    );
+
+    let mut midpoint_book = accounts.market.load_midpoint_book(remaining_accounts)?;
+    let mut book = Orderbook {
+        bids: &mut accounts.bids,
+        asks: &mut accounts.asks,
+        oracle_price: None,
+        midpoint: midpoint_book.as_deref_mut(),
+    };
+    book.cancel_all_orders(&accounts.market, &mut accounts.open_orders, open_orders_key)?;
+    if let Some(midpoint_book) = &midpoint_book {
+        midpoint_book.exit(&crate::ID)?;
+    }
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CancelAllOrders<'info> {
+    /// The `OpenOrders` owner or its delegate.
+    pub signer: Signer<'info>,
+
+    #[account(mut, has_one = market @ ClobError::WrongMarket)]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+
+    #[account(has_one = bids @ ClobError::WrongMarket, has_one = asks @ ClobError::WrongMarket)]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(mut)]
+    pub bids: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub asks: Box<Account<'info, BookSide>>,
+}
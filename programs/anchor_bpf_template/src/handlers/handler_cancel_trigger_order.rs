@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::events::TriggerCancelled;
+use crate::state::*;
+use crate::utils;
+
+pub(crate) fn process(ctx: Context<CancelTriggerOrder>, trigger_id: u64) -> Result<()> {
+    let accounts = ctx.accounts;
+    let trigger = accounts
+        .trigger_orders
+        .remove(trigger_id)
+        .ok_or_else(|| error!(ClobError::OrderNotFound))?;
+    accounts
+        .open_orders
+        .unlock_native(trigger.side, trigger.locked)?;
+    utils::pay_lamports(
+        &accounts.trigger_orders.to_account_info(),
+        &accounts.owner.to_account_info(),
+        TriggerOrders::EXECUTION_REWARD,
+    )?;
+
+    emit!(TriggerCancelled {
+        market: accounts.open_orders.market,
+        open_orders: accounts.open_orders.key(),
+        trigger_id,
+        client_order_id: trigger.client_order_id,
+    });
+    Ok(())
+}
+
+/// Allowed whatever the market status, the released funds are settled as usual.
+#[derive(Accounts)]
+pub struct CancelTriggerOrder<'info> {
+    /// Refunded the execution reward.
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut, has_one = owner @ ClobError::Unauthorized)]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+
+    #[account(mut, has_one = open_orders @ ClobError::WrongMarket)]
+    pub trigger_orders: Box<Account<'info, TriggerOrders>>,
+}
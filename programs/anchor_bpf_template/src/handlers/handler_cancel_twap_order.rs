@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::events::TwapCancelled;
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<CancelTwapOrder>) -> Result<()> {
+    let accounts = ctx.accounts;
+    let twap = &accounts.twap_order;
+    accounts.open_orders.unlock_native(twap.side, twap.locked)?;
+
+    emit!(TwapCancelled {
+        market: twap.market,
+        open_orders: twap.open_orders,
+        twap_order: twap.key(),
+        client_order_id: twap.client_order_id,
+        remaining_base_lots: twap.remaining_base_lots,
+    });
+    Ok(())
+}
+
+/// Also closes a completed TWAP order. Allowed whatever the market status, the released
+/// funds are settled as usual and the rent and unused slice rewards go back to the owner.
+#[derive(Accounts)]
+pub struct CancelTwapOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut, has_one = owner @ ClobError::Unauthorized)]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+
+    #[account(mut,
+        close = owner,
+        has_one = open_orders @ ClobError::WrongMarket,
+    )]
+    pub twap_order: Box<Account<'info, TwapOrder>>,
+}
@@ -0,0 +1,83 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::events::BatchCleared;
+use crate::matching::Orderbook;
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<ClearBatch>) -> Result<()> {
+    let clock = Clock::get()?;
+    let remaining_accounts = ctx.remaining_accounts;
+    let accounts = ctx.accounts;
+
+    require!(
+        accounts.market.status_at(clock.unix_timestamp) == MarketStatus::Active,
+        ClobError::MarketNotActive
+    );
+    let batch_start_slot = accounts.market.batch_start_slot;
+    require!(
+        accounts.market.batch_slots > 0
+            && clock.slot >= batch_start_slot.saturating_add(accounts.market.batch_slots),
+        ClobError::BatchNotDue
+    );
+    let (price, base_lots) = uncross(
+        &mut accounts.market,
+        &mut accounts.bids,
+        &mut accounts.asks,
+        &mut accounts.event_queue,
+        remaining_accounts,
+        clock.unix_timestamp,
+    )?;
+    accounts.market.batch_start_slot = clock.slot;
+
+    emit!(BatchCleared {
+        market: accounts.market.key(),
+        batch_start_slot,
+        price,
+        base_lots,
+    });
+    Ok(())
+}
+
+/// Uncrosses the resting orders at a single price, see [`Orderbook::uncross`].
+pub(crate) fn uncross(
+    market: &mut Market,
+    bids: &mut BookSide,
+    asks: &mut BookSide,
+    event_queue: &mut EventQueue,
+    remaining_accounts: &[AccountInfo],
+    now: i64,
+) -> Result<(u64, u64)> {
+    let mut midpoint_book = market.load_midpoint_book(remaining_accounts)?;
+    let mut book = Orderbook {
+        bids,
+        asks,
+        // Pegged orders take no part in auctions.
+        oracle_price: None,
+        midpoint: midpoint_book.as_deref_mut(),
+    };
+    let traded = book.uncross(market, event_queue, now)?;
+    if let Some(midpoint_book) = &midpoint_book {
+        midpoint_book.exit(&crate::ID)?;
+    }
+    Ok(traded)
+}
+
+/// Permissionless: anyone may close a batch once its slots have passed.
+#[derive(Accounts)]
+pub struct ClearBatch<'info> {
+    #[account(mut,
+        has_one = bids @ ClobError::WrongMarket,
+        has_one = asks @ ClobError::WrongMarket,
+        has_one = event_queue @ ClobError::WrongMarket,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(mut)]
+    pub bids: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub asks: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub event_queue: Box<Account<'info, EventQueue>>,
+}
@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+use crate::utils;
+
+/// Settles up to `limit` maker events. The makers' `OpenOrders` accounts are passed as
+/// writable remaining accounts, each once; processing stops at the first event whose owner is missing.
+/// The bonds forfeited by expired and evicted orders are paid to `cranker`.
+pub(crate) fn process<'info>(
+    ctx: Context<'_, '_, '_, 'info, ConsumeEvents<'info>>,
+    limit: u16,
+) -> Result<()> {
+    let market_key = ctx.accounts.market.key();
+    let mut makers = ctx
+        .remaining_accounts
+        .iter()
+        .map(|info| {
+            let open_orders = Account::<OpenOrders>::try_from(info)?;
+            require_keys_eq!(open_orders.market, market_key, ClobError::WrongMarket);
+            Ok(open_orders)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    // A second copy would be written back last, undoing the events applied to the first.
+    require!(
+        makers
+            .iter()
+            .enumerate()
+            .all(|(i, maker)| makers[..i].iter().all(|m| m.key() != maker.key())),
+        ClobError::DuplicateAccount
+    );
+
+    let market = &ctx.accounts.market;
+    let event_queue = &mut ctx.accounts.event_queue;
+    let mut consumed = 0;
+    let mut forfeited: u64 = 0;
+    for event in event_queue.events.iter().take(limit as usize) {
+        let owner = event.owner();
+        let maker = match makers.iter_mut().find(|m| m.key() == owner) {
+            Some(maker) => maker,
+            None => break,
+        };
+        match event {
+            Event::Fill(fill) => maker.apply_fill(market, fill)?,
+            Event::Out(out) => {
+                forfeited = forfeited
+                    .checked_add(maker.apply_out(market, out)?)
+                    .ok_or_else(|| error!(ClobError::MathOverflow))?;
+            }
+        }
+        consumed += 1;
+    }
+    event_queue.events.drain(..consumed);
+
+    for maker in makers {
+        maker.exit(ctx.program_id)?;
+    }
+    utils::pay_lamports(
+        &ctx.accounts.market.to_account_info(),
+        &ctx.accounts.cranker.to_account_info(),
+        forfeited,
+    )
+}
+
+#[derive(Accounts)]
+pub struct ConsumeEvents<'info> {
+    #[account(mut, has_one = event_queue @ ClobError::WrongMarket)]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(mut)]
+    pub event_queue: Box<Account<'info, EventQueue>>,
+
+    /// CHECK: any account, receives the forfeited order bonds.
+    #[account(mut)]
+    pub cranker: UncheckedAccount<'info>,
+}
@@ -0,0 +1,174 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::events::{TriggerCancelled, TriggerExecuted};
+use crate::matching::{settle_taker, Orderbook};
+use crate::state::*;
+use crate::utils;
+
+pub(crate) fn process(ctx: Context<ExecuteTriggers>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let open_orders_key = ctx.accounts.open_orders.key();
+    let remaining_accounts = ctx.remaining_accounts;
+    let accounts = ctx.accounts;
+
+    require!(
+        accounts.market.status_at(now) == MarketStatus::Active,
+        ClobError::MarketNotActive
+    );
+    let oracle_price = accounts.market.oracle_price(remaining_accounts, now)?;
+    // Triggers in a group another member of which filled are dropped, fired or not.
+    let open_orders = &accounts.open_orders;
+    let event_queue = &accounts.event_queue;
+    let mut dropped = accounts.trigger_orders.take_where(|t| {
+        t.group_id != 0 && open_orders.is_group_filled(open_orders_key, event_queue, t.group_id)
+    });
+    // Batch mode only takes orders that rest, immediate-or-cancel ones wait for it to end.
+    let batch_mode = accounts.market.collects_orders();
+    let (deferred, triggered): (Vec<_>, Vec<_>) = accounts
+        .trigger_orders
+        .take_triggered(oracle_price, accounts.market.last_trade_price)
+        .into_iter()
+        .partition(|t| batch_mode && t.order_type == OrderType::ImmediateOrCancel);
+    accounts.trigger_orders.restore(deferred);
+    require!(
+        !dropped.is_empty() || !triggered.is_empty(),
+        ClobError::NoTriggersReady
+    );
+
+    let mut midpoint_book = accounts.market.load_midpoint_book(remaining_accounts)?;
+    let mut book = Orderbook {
+        bids: &mut accounts.bids,
+        asks: &mut accounts.asks,
+        oracle_price,
+        midpoint: midpoint_book.as_deref_mut(),
+    };
+    let mut executed = 0;
+    let mut fired_groups = Vec::new();
+    let mut triggered = triggered.into_iter();
+    while let Some(trigger) = triggered.next() {
+        // Once a fill trips the circuit breaker the rest wait for trading to resume.
+        if accounts.market.status_at(now) != MarketStatus::Active {
+            accounts
+                .trigger_orders
+                .restore(std::iter::once(trigger).chain(triggered.by_ref()));
+            break;
+        }
+        if trigger.group_id != 0 && fired_groups.contains(&trigger.group_id) {
+            dropped.push(trigger);
+            continue;
+        }
+        // Fills and other orders since the trigger was placed may have used up the limits.
+        let over_limits = accounts
+            .open_orders
+            .check_order_risk(
+                &accounts.market,
+                trigger.side,
+                trigger.price,
+                trigger.max_base_lots,
+            )
+            .is_err();
+        if over_limits {
+            dropped.push(trigger);
+            continue;
+        }
+        // The order is paid for with the funds locked when the trigger was placed.
+        accounts
+            .open_orders
+            .unlock_native(trigger.side, trigger.locked)?;
+        let params = trigger.to_order_params();
+        let outcome = book.new_order(
+            &mut accounts.market,
+            &mut accounts.event_queue,
+            open_orders_key,
+            Some(&mut accounts.open_orders),
+            &params,
+            now,
+        )?;
+        let deposit = settle_taker(
+            &accounts.market,
+            &mut accounts.open_orders,
+            &params,
+            &outcome,
+        )?;
+        require!(deposit == 0, ClobError::InsufficientFunds);
+        executed += 1;
+
+        if trigger.group_id != 0 {
+            fired_groups.push(trigger.group_id);
+            book.cancel_group_orders(
+                &accounts.market,
+                &mut accounts.open_orders,
+                open_orders_key,
+                trigger.group_id,
+            )?;
+            dropped.extend(
+                accounts
+                    .trigger_orders
+                    .take_where(|t| t.group_id == trigger.group_id),
+            );
+        }
+        emit!(TriggerExecuted {
+            market: accounts.market.key(),
+            open_orders: open_orders_key,
+            trigger_id: trigger.trigger_id,
+            client_order_id: trigger.client_order_id,
+            order_id: outcome.order_id,
+            executor: accounts.executor.key(),
+        });
+    }
+    if let Some(midpoint_book) = &midpoint_book {
+        midpoint_book.exit(&crate::ID)?;
+    }
+
+    for trigger in &dropped {
+        accounts
+            .open_orders
+            .unlock_native(trigger.side, trigger.locked)?;
+        emit!(TriggerCancelled {
+            market: accounts.market.key(),
+            open_orders: open_orders_key,
+            trigger_id: trigger.trigger_id,
+            client_order_id: trigger.client_order_id,
+        });
+    }
+
+    // Dropping a trigger earns its reward like executing one.
+    let reward = TriggerOrders::EXECUTION_REWARD
+        .checked_mul((executed + dropped.len()) as u64)
+        .ok_or_else(|| error!(ClobError::MathOverflow))?;
+    utils::pay_lamports(
+        &accounts.trigger_orders.to_account_info(),
+        &accounts.executor.to_account_info(),
+        reward,
+    )
+}
+
+/// Permissionless: anyone may execute the trigger orders that have fired, or drop those
+/// whose group filled, and collect their rewards.
+#[derive(Accounts)]
+pub struct ExecuteTriggers<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(mut, has_one = market @ ClobError::WrongMarket)]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+
+    #[account(mut, has_one = open_orders @ ClobError::WrongMarket)]
+    pub trigger_orders: Box<Account<'info, TriggerOrders>>,
+
+    #[account(mut,
+        has_one = bids @ ClobError::WrongMarket,
+        has_one = asks @ ClobError::WrongMarket,
+        has_one = event_queue @ ClobError::WrongMarket,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(mut)]
+    pub bids: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub asks: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub event_queue: Box<Account<'info, EventQueue>>,
+}
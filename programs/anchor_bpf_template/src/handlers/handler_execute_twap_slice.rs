@@ -0,0 +1,128 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::events::TwapSliceExecuted;
+use crate::matching::{settle_taker, OrderOutcome, Orderbook};
+use crate::state::*;
+use crate::utils;
+
+pub(crate) fn process(ctx: Context<ExecuteTwapSlice>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let open_orders_key = ctx.accounts.open_orders.key();
+    let remaining_accounts = ctx.remaining_accounts;
+    let accounts = ctx.accounts;
+
+    require!(
+        accounts.market.status_at(now) == MarketStatus::Active,
+        ClobError::MarketNotActive
+    );
+    let twap = &mut accounts.twap_order;
+    require!(
+        twap.remaining_base_lots > 0 && now >= twap.next_slice_timestamp,
+        ClobError::TwapNotDue
+    );
+
+    // The slice is paid for with the funds locked for the whole order, what it does not
+    // spend is locked again for the slices after it.
+    accounts.open_orders.unlock_native(twap.side, twap.locked)?;
+    let params = twap.slice_params();
+    // A slice over the risk limits, or in batch mode where immediate-or-cancel orders
+    // cannot be placed, is skipped as if nothing had matched it.
+    let skip = accounts.market.collects_orders()
+        || accounts
+            .open_orders
+            .check_order_risk(
+                &accounts.market,
+                params.side,
+                params.price,
+                params.max_base_lots,
+            )
+            .is_err();
+    let outcome = if skip {
+        OrderOutcome::default()
+    } else {
+        let oracle_price = accounts.market.oracle_price(remaining_accounts, now)?;
+        let mut midpoint_book = accounts.market.load_midpoint_book(remaining_accounts)?;
+        let mut book = Orderbook {
+            bids: &mut accounts.bids,
+            asks: &mut accounts.asks,
+            oracle_price,
+            midpoint: midpoint_book.as_deref_mut(),
+        };
+        let outcome = book.new_order(
+            &mut accounts.market,
+            &mut accounts.event_queue,
+            open_orders_key,
+            Some(&mut accounts.open_orders),
+            &params,
+            now,
+        )?;
+        if let Some(midpoint_book) = &midpoint_book {
+            midpoint_book.exit(&crate::ID)?;
+        }
+        let deposit = settle_taker(
+            &accounts.market,
+            &mut accounts.open_orders,
+            &params,
+            &outcome,
+        )?;
+        require!(deposit == 0, ClobError::InsufficientFunds);
+        outcome
+    };
+
+    // The unfilled part of a slice is not retried.
+    twap.remaining_base_lots -= params.max_base_lots;
+    twap.base_lots_filled = twap
+        .base_lots_filled
+        .checked_add(outcome.base_lots_filled)
+        .ok_or_else(|| error!(ClobError::MathOverflow))?;
+    twap.next_slice_timestamp = now
+        .checked_add(twap.interval_seconds as i64)
+        .ok_or_else(|| error!(ClobError::MathOverflow))?;
+    twap.locked = twap.required_funds(&accounts.market)?;
+    let deposit = accounts.open_orders.lock_native(twap.side, twap.locked)?;
+    require!(deposit == 0, ClobError::InsufficientFunds);
+
+    emit!(TwapSliceExecuted {
+        market: accounts.market.key(),
+        open_orders: open_orders_key,
+        twap_order: twap.key(),
+        order_id: outcome.order_id,
+        base_lots_filled: outcome.base_lots_filled,
+        remaining_base_lots: twap.remaining_base_lots,
+        executor: accounts.executor.key(),
+    });
+    utils::pay_lamports(
+        &accounts.twap_order.to_account_info(),
+        &accounts.executor.to_account_info(),
+        TwapOrder::SLICE_REWARD,
+    )
+}
+
+/// Permissionless: anyone may execute a due TWAP slice and collect its reward.
+#[derive(Accounts)]
+pub struct ExecuteTwapSlice<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    #[account(mut, has_one = market @ ClobError::WrongMarket)]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+
+    #[account(mut, has_one = open_orders @ ClobError::WrongMarket)]
+    pub twap_order: Box<Account<'info, TwapOrder>>,
+
+    #[account(mut,
+        has_one = bids @ ClobError::WrongMarket,
+        has_one = asks @ ClobError::WrongMarket,
+        has_one = event_queue @ ClobError::WrongMarket,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(mut)]
+    pub bids: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub asks: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub event_queue: Box<Account<'info, EventQueue>>,
+}
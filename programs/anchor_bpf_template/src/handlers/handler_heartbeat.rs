@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<Heartbeat>) -> Result<()> {
+    let open_orders = &mut ctx.accounts.open_orders;
+    require!(
+        open_orders.is_owner_or_delegate(ctx.accounts.signer.key),
+        ClobError::Unauthorized
+    );
+    open_orders.last_heartbeat = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct Heartbeat<'info> {
+    /// The `OpenOrders` owner or its delegate.
+    pub signer: Signer<'info>,
+
+    #[account(mut)]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<InitInternalOracle>, price: i64, expo: i32) -> Result<()> {
+    let oracle = &mut ctx.accounts.oracle;
+    oracle.authority = ctx.accounts.authority.key();
+    oracle.price = price;
+    oracle.expo = expo;
+    oracle.last_update_timestamp = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+/// `oracle` is created zeroed and owned by the program by the caller, in the same
+/// transaction.
+#[derive(Accounts)]
+pub struct InitInternalOracle<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(zero)]
+    pub oracle: Box<Account<'info, InternalOracle>>,
+}
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<InitMidpointBook>) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    require_keys_eq!(
+        market.midpoint_book,
+        Pubkey::default(),
+        ClobError::AlreadyInitialized
+    );
+    market.midpoint_book = ctx.accounts.midpoint_book.key();
+    ctx.accounts.midpoint_book.market = market.key();
+    Ok(())
+}
+
+/// `midpoint_book` is created zeroed and owned by the program by the caller, in the same
+/// transaction.
+#[derive(Accounts)]
+pub struct InitMidpointBook<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, has_one = admin @ ClobError::Unauthorized)]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(zero)]
+    pub midpoint_book: Box<Account<'info, MidpointBook>>,
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<InitOpenOrders>) -> Result<()> {
+    let open_orders = &mut ctx.accounts.open_orders;
+    open_orders.market = ctx.accounts.market.key();
+    open_orders.owner = ctx.accounts.owner.key();
+    Ok(())
+}
+
+/// `open_orders` is created zeroed and owned by the program by the caller, in the same
+/// transaction.
+#[derive(Accounts)]
+pub struct InitOpenOrders<'info> {
+    pub owner: Signer<'info>,
+
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(zero)]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+}
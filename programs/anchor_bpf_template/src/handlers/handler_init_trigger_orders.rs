@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<InitTriggerOrders>) -> Result<()> {
+    let trigger_orders = &mut ctx.accounts.trigger_orders;
+    trigger_orders.market = ctx.accounts.open_orders.market;
+    trigger_orders.open_orders = ctx.accounts.open_orders.key();
+    Ok(())
+}
+
+/// `trigger_orders` is created zeroed and owned by the program by the caller, in the same
+/// transaction.
+#[derive(Accounts)]
+pub struct InitTriggerOrders<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(has_one = owner @ ClobError::Unauthorized)]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+
+    #[account(zero)]
+    pub trigger_orders: Box<Account<'info, TriggerOrders>>,
+}
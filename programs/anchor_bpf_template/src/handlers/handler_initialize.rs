@@ -1,9 +1,107 @@
 use anchor_lang::prelude::*;
 use anchor_lang::Accounts;
+use anchor_spl::token::{Mint, TokenAccount};
 
-pub fn process(_ctx: Context<Initialize>) -> Result<()> {
+use crate::errors::ClobError;
+use crate::events::MarketCreated;
+use crate::pda::MARKET_AUTHORITY_SEED;
+use crate::state::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct InitializeArgs {
+    pub base_lot_size: u64,
+    pub quote_lot_size: u64,
+    pub tick_size: u64,
+    pub taker_fee_bps: u16,
+}
+
+pub(crate) fn process(ctx: Context<Initialize>, args: InitializeArgs) -> Result<()> {
+    require!(
+        args.base_lot_size > 0 && args.quote_lot_size > 0 && args.tick_size > 0,
+        ClobError::InvalidLotSize
+    );
+    require!(args.taker_fee_bps <= 10_000, ClobError::InvalidFee);
+
+    let market_key = ctx.accounts.market.key();
+    let market = &mut ctx.accounts.market;
+    market.admin = ctx.accounts.admin.key();
+    market.base_mint = ctx.accounts.base_mint.key();
+    market.quote_mint = ctx.accounts.quote_mint.key();
+    market.base_vault = ctx.accounts.base_vault.key();
+    market.quote_vault = ctx.accounts.quote_vault.key();
+    market.bids = ctx.accounts.bids.key();
+    market.asks = ctx.accounts.asks.key();
+    market.event_queue = ctx.accounts.event_queue.key();
+    market.base_decimals = ctx.accounts.base_mint.decimals;
+    market.quote_decimals = ctx.accounts.quote_mint.decimals;
+    market.base_lot_size = args.base_lot_size;
+    market.quote_lot_size = args.quote_lot_size;
+    market.tick_size = args.tick_size;
+    market.taker_fee_bps = args.taker_fee_bps;
+    market.status = MarketStatus::Active;
+    market.authority_bump = *ctx.bumps.get("market_authority").unwrap();
+
+    ctx.accounts.bids.market = market_key;
+    ctx.accounts.bids.side = Side::Bid;
+    ctx.accounts.asks.market = market_key;
+    ctx.accounts.asks.side = Side::Ask;
+    ctx.accounts.event_queue.market = market_key;
+
+    let market = &ctx.accounts.market;
+    emit!(MarketCreated {
+        market: market_key,
+        admin: market.admin,
+        base_mint: market.base_mint,
+        quote_mint: market.quote_mint,
+        base_vault: market.base_vault,
+        quote_vault: market.quote_vault,
+        bids: market.bids,
+        asks: market.asks,
+        event_queue: market.event_queue,
+        base_lot_size: market.base_lot_size,
+        quote_lot_size: market.quote_lot_size,
+        tick_size: market.tick_size,
+        taker_fee_bps: market.taker_fee_bps,
+    });
     Ok(())
 }
 
+/// The market, books and event queue are created zeroed and owned by the program by the
+/// caller, in the same transaction. The vaults are empty token accounts owned by the
+/// market authority PDA.
 #[derive(Accounts)]
-pub struct Initialize {}
+pub struct Initialize<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(zero)]
+    pub market: Box<Account<'info, Market>>,
+    #[account(zero)]
+    pub bids: Box<Account<'info, BookSide>>,
+    #[account(zero)]
+    pub asks: Box<Account<'info, BookSide>>,
+    #[account(zero)]
+    pub event_queue: Box<Account<'info, EventQueue>>,
+
+    /// CHECK: PDA, only used as the vaults' owner.
+    #[account(seeds = [MARKET_AUTHORITY_SEED, market.key().as_ref()], bump)]
+    pub market_authority: UncheckedAccount<'info>,
+
+    pub base_mint: Box<Account<'info, Mint>>,
+    #[account(constraint = quote_mint.key() != base_mint.key() @ ClobError::WrongMint)]
+    pub quote_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        constraint = base_vault.mint == base_mint.key() @ ClobError::InvalidVault,
+        constraint = base_vault.owner == market_authority.key() @ ClobError::InvalidVault,
+        constraint = base_vault.delegate.is_none() @ ClobError::InvalidVault,
+        constraint = base_vault.close_authority.is_none() @ ClobError::InvalidVault,
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        constraint = quote_vault.mint == quote_mint.key() @ ClobError::InvalidVault,
+        constraint = quote_vault.owner == market_authority.key() @ ClobError::InvalidVault,
+        constraint = quote_vault.delegate.is_none() @ ClobError::InvalidVault,
+        constraint = quote_vault.close_authority.is_none() @ ClobError::InvalidVault,
+    )]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+}
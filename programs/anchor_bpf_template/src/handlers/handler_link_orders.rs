@@ -0,0 +1,90 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+
+pub(crate) fn process(
+    ctx: Context<LinkOrders>,
+    group_id: u64,
+    order_ids: Vec<u64>,
+    trigger_ids: Vec<u64>,
+) -> Result<()> {
+    let open_orders_key = ctx.accounts.open_orders.key();
+    let remaining_accounts = ctx.remaining_accounts;
+    let accounts = ctx.accounts;
+
+    require!(
+        accounts
+            .open_orders
+            .is_owner_or_delegate(accounts.signer.key),
+        ClobError::Unauthorized
+    );
+    require!(
+        group_id != 0 && order_ids.len() + trigger_ids.len() >= 2,
+        ClobError::InvalidGroup
+    );
+
+    for order_id in order_ids {
+        let open_order = accounts
+            .open_orders
+            .find_order_mut(order_id)
+            .ok_or_else(|| error!(ClobError::OrderNotFound))?;
+        let book = match open_order.side {
+            Side::Bid => &mut accounts.bids,
+            Side::Ask => &mut accounts.asks,
+        };
+        // Midpoint orders are not found here and cannot be grouped.
+        let at = book
+            .find(order_id)
+            .filter(|&at| book.order(at).owner == open_orders_key)
+            .ok_or_else(|| error!(ClobError::OrderNotFound))?;
+        book.order_mut(at).group_id = group_id;
+        open_order.group_id = group_id;
+    }
+
+    if !trigger_ids.is_empty() {
+        let info = remaining_accounts
+            .first()
+            .ok_or_else(|| error!(ClobError::OrderNotFound))?;
+        let mut trigger_orders = Account::<TriggerOrders>::try_from(info)?;
+        require_keys_eq!(
+            trigger_orders.open_orders,
+            open_orders_key,
+            ClobError::WrongMarket
+        );
+        for trigger_id in trigger_ids {
+            trigger_orders
+                .find_mut(trigger_id)
+                .ok_or_else(|| error!(ClobError::OrderNotFound))?
+                .group_id = group_id;
+        }
+        trigger_orders.exit(&crate::ID)?;
+    }
+
+    // A reused id starts a new group.
+    accounts
+        .open_orders
+        .filled_groups
+        .retain(|&g| g != group_id);
+    Ok(())
+}
+
+/// The `TriggerOrders` account of `open_orders` is the first remaining account when
+/// trigger orders are linked.
+#[derive(Accounts)]
+pub struct LinkOrders<'info> {
+    /// The `OpenOrders` owner or its delegate.
+    pub signer: Signer<'info>,
+
+    #[account(mut, has_one = market @ ClobError::WrongMarket)]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+
+    #[account(has_one = bids @ ClobError::WrongMarket, has_one = asks @ ClobError::WrongMarket)]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(mut)]
+    pub bids: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub asks: Box<Account<'info, BookSide>>,
+}
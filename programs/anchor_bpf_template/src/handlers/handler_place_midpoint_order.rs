@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ClobError;
+use crate::matching::{post_bond, settle_taker, MidpointOrderParams, OrderSummary, Orderbook};
+use crate::state::*;
+use crate::utils;
+
+use super::PlaceOrder;
+
+/// Places a hidden order at the midpoint, which needs the market's midpoint book.
+pub(crate) fn process(
+    ctx: Context<PlaceOrder>,
+    params: MidpointOrderParams,
+    expected_sequence: Option<u64>,
+) -> Result<OrderSummary> {
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+    let remaining_accounts = ctx.remaining_accounts;
+    let open_orders_key = ctx.accounts.open_orders.key();
+    let accounts = ctx.accounts;
+
+    require!(
+        accounts.market.status_at(now) == MarketStatus::Active,
+        ClobError::MarketNotActive
+    );
+    require!(
+        accounts
+            .open_orders
+            .is_owner_or_delegate(accounts.signer.key),
+        ClobError::Unauthorized
+    );
+    accounts.open_orders.bump_sequence(expected_sequence)?;
+    let exposure = accounts.open_orders.exposure();
+    accounts.open_orders.count_order(clock.slot)?;
+    let vault = match params.side {
+        Side::Bid => accounts.market.quote_vault,
+        Side::Ask => accounts.market.base_vault,
+    };
+    require_keys_eq!(accounts.market_vault.key(), vault, ClobError::WrongMarket);
+    params.validate(&accounts.market, now)?;
+
+    let mut midpoint_book = accounts
+        .market
+        .load_midpoint_book(remaining_accounts)?
+        .ok_or_else(|| error!(ClobError::MidpointBookRequired))?;
+    let mut book = Orderbook {
+        bids: &mut accounts.bids,
+        asks: &mut accounts.asks,
+        oracle_price: accounts.market.oracle_price(remaining_accounts, now)?,
+        midpoint: Some(&mut midpoint_book),
+    };
+    let outcome = book.new_midpoint_order(
+        &mut accounts.market,
+        &mut accounts.event_queue,
+        open_orders_key,
+        &mut accounts.open_orders,
+        &params,
+        now,
+    )?;
+    midpoint_book.exit(&crate::ID)?;
+    let deposit = settle_taker(
+        &accounts.market,
+        &mut accounts.open_orders,
+        &params.to_order_params(),
+        &outcome,
+    )?;
+    let bond = post_bond(&accounts.market, &mut accounts.open_orders, &outcome);
+    accounts.open_orders.check_risk_limits(&exposure)?;
+
+    utils::deposit(
+        &accounts.token_program.to_account_info(),
+        &accounts.token_deposit_account,
+        &accounts.market_vault.to_account_info(),
+        &accounts.signer.to_account_info(),
+        deposit,
+    )?;
+    utils::deposit_lamports(
+        &accounts.system_program.to_account_info(),
+        &accounts.signer.to_account_info(),
+        &accounts.market.to_account_info(),
+        bond,
+    )?;
+    outcome.summary(&accounts.market)
+}
@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+use anchor_spl::token::{Token, TokenAccount};
+
+use crate::errors::ClobError;
+use crate::matching::{post_bond, settle_taker, OrderParams, OrderSummary, Orderbook};
+use crate::state::*;
+use crate::utils;
+
+pub(crate) fn process(
+    ctx: Context<PlaceOrder>,
+    params: OrderParams,
+    expected_sequence: Option<u64>,
+) -> Result<OrderSummary> {
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+    let open_orders_key = ctx.accounts.open_orders.key();
+    let remaining_accounts = ctx.remaining_accounts;
+    let accounts = ctx.accounts;
+
+    require!(
+        accounts.market.accepts_orders(now),
+        ClobError::MarketNotActive
+    );
+    require!(
+        accounts
+            .open_orders
+            .is_owner_or_delegate(accounts.signer.key),
+        ClobError::Unauthorized
+    );
+    accounts.open_orders.bump_sequence(expected_sequence)?;
+    let exposure = accounts.open_orders.exposure();
+    accounts.open_orders.count_order(clock.slot)?;
+    let vault = match params.side {
+        Side::Bid => accounts.market.quote_vault,
+        Side::Ask => accounts.market.base_vault,
+    };
+    require_keys_eq!(accounts.market_vault.key(), vault, ClobError::WrongMarket);
+    params.validate(&accounts.market, now)?;
+    let oracle_price = accounts.market.oracle_price(remaining_accounts, now)?;
+    accounts
+        .market
+        .check_price_band(oracle_price, params.price)?;
+
+    let mut midpoint_book = accounts.market.load_midpoint_book(remaining_accounts)?;
+    let mut book = Orderbook {
+        bids: &mut accounts.bids,
+        asks: &mut accounts.asks,
+        oracle_price,
+        midpoint: midpoint_book.as_deref_mut(),
+    };
+    let outcome = book.new_order(
+        &mut accounts.market,
+        &mut accounts.event_queue,
+        open_orders_key,
+        Some(&mut accounts.open_orders),
+        &params,
+        now,
+    )?;
+    let deposit = settle_taker(
+        &accounts.market,
+        &mut accounts.open_orders,
+        &params,
+        &outcome,
+    )?;
+    let bond = post_bond(&accounts.market, &mut accounts.open_orders, &outcome);
+    accounts.open_orders.check_risk_limits(&exposure)?;
+
+    if let Some(midpoint_book) = &midpoint_book {
+        midpoint_book.exit(&crate::ID)?;
+    }
+    utils::deposit(
+        &accounts.token_program.to_account_info(),
+        &accounts.token_deposit_account,
+        &accounts.market_vault.to_account_info(),
+        &accounts.signer.to_account_info(),
+        deposit,
+    )?;
+    utils::deposit_lamports(
+        &accounts.system_program.to_account_info(),
+        &accounts.signer.to_account_info(),
+        &accounts.market.to_account_info(),
+        bond,
+    )?;
+    outcome.summary(&accounts.market)
+}
+
+#[derive(Accounts)]
+pub struct PlaceOrder<'info> {
+    /// The `OpenOrders` owner or its delegate, pays the order bond.
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(mut, has_one = market @ ClobError::WrongMarket)]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+
+    #[account(mut,
+        has_one = bids @ ClobError::WrongMarket,
+        has_one = asks @ ClobError::WrongMarket,
+        has_one = event_queue @ ClobError::WrongMarket,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(mut)]
+    pub bids: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub asks: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub event_queue: Box<Account<'info, EventQueue>>,
+
+    /// Pays for the order: quote tokens for bids, base tokens for asks.
+    #[account(mut)]
+    pub token_deposit_account: Box<Account<'info, TokenAccount>>,
+    /// The market's quote vault for bids, base vault for asks.
+    #[account(mut)]
+    pub market_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,106 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ClobError;
+use crate::matching::{post_bond, settle_taker, OrderSummary, Orderbook, PeggedOrderParams};
+use crate::state::*;
+use crate::utils;
+
+use super::PlaceOrder;
+
+/// Places an order priced off the market's oracle.
+/// What does not match rests as a pegged order, re-priced every time the book is traversed.
+pub(crate) fn process(
+    ctx: Context<PlaceOrder>,
+    params: PeggedOrderParams,
+    expected_sequence: Option<u64>,
+) -> Result<OrderSummary> {
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+    let remaining_accounts = ctx.remaining_accounts;
+    let open_orders_key = ctx.accounts.open_orders.key();
+    let accounts = ctx.accounts;
+
+    require!(
+        accounts.market.status_at(now) == MarketStatus::Active,
+        ClobError::MarketNotActive
+    );
+    require!(
+        accounts
+            .open_orders
+            .is_owner_or_delegate(accounts.signer.key),
+        ClobError::Unauthorized
+    );
+    accounts.open_orders.bump_sequence(expected_sequence)?;
+    let exposure = accounts.open_orders.exposure();
+    accounts.open_orders.count_order(clock.slot)?;
+    let vault = match params.side {
+        Side::Bid => accounts.market.quote_vault,
+        Side::Ask => accounts.market.base_vault,
+    };
+    require_keys_eq!(accounts.market_vault.key(), vault, ClobError::WrongMarket);
+    params.validate(&accounts.market, now)?;
+    // An ask without a limit locks no quote, so the notional cap would not see it.
+    require!(
+        params.peg_limit > 0 || accounts.open_orders.risk_limits.max_open_notional == 0,
+        ClobError::InvalidPeg
+    );
+    let oracle_price = accounts
+        .market
+        .oracle_price(remaining_accounts, now)?
+        .ok_or_else(|| error!(ClobError::InvalidPeg))?;
+    let price = peg_price(
+        params.side,
+        oracle_price,
+        params.price_offset,
+        params.peg_limit,
+        &accounts.market.tick_sizes(),
+    )
+    .ok_or_else(|| error!(ClobError::InvalidPrice))?;
+    accounts
+        .market
+        .check_price_band(Some(oracle_price), price)?;
+
+    let mut midpoint_book = accounts.market.load_midpoint_book(remaining_accounts)?;
+    let mut book = Orderbook {
+        bids: &mut accounts.bids,
+        asks: &mut accounts.asks,
+        oracle_price: Some(oracle_price),
+        midpoint: midpoint_book.as_deref_mut(),
+    };
+    let outcome = book.new_pegged_order(
+        &mut accounts.market,
+        &mut accounts.event_queue,
+        open_orders_key,
+        &mut accounts.open_orders,
+        &params,
+        price,
+        now,
+    )?;
+    // The resting part of a bid locks quote at its peg limit, the most it can pay.
+    let deposit = settle_taker(
+        &accounts.market,
+        &mut accounts.open_orders,
+        &params.to_order_params(params.peg_limit),
+        &outcome,
+    )?;
+    let bond = post_bond(&accounts.market, &mut accounts.open_orders, &outcome);
+    accounts.open_orders.check_risk_limits(&exposure)?;
+
+    if let Some(midpoint_book) = &midpoint_book {
+        midpoint_book.exit(&crate::ID)?;
+    }
+    utils::deposit(
+        &accounts.token_program.to_account_info(),
+        &accounts.token_deposit_account,
+        &accounts.market_vault.to_account_info(),
+        &accounts.signer.to_account_info(),
+        deposit,
+    )?;
+    utils::deposit_lamports(
+        &accounts.system_program.to_account_info(),
+        &accounts.signer.to_account_info(),
+        &accounts.market.to_account_info(),
+        bond,
+    )?;
+    outcome.summary(&accounts.market)
+}
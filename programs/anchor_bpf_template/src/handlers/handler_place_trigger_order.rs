@@ -0,0 +1,118 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, System, Transfer};
+use anchor_lang::Accounts;
+use anchor_spl::token::{Token, TokenAccount};
+
+use crate::errors::ClobError;
+use crate::events::TriggerPlaced;
+use crate::matching::TriggerOrderParams;
+use crate::state::*;
+use crate::utils;
+
+pub(crate) fn process(ctx: Context<PlaceTriggerOrder>, params: TriggerOrderParams) -> Result<()> {
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+    let remaining_accounts = ctx.remaining_accounts;
+    let accounts = ctx.accounts;
+
+    require!(
+        accounts.market.status_at(now) == MarketStatus::Active,
+        ClobError::MarketNotActive
+    );
+    let vault = match params.side {
+        Side::Bid => accounts.market.quote_vault,
+        Side::Ask => accounts.market.base_vault,
+    };
+    require_keys_eq!(accounts.market_vault.key(), vault, ClobError::WrongMarket);
+    params.validate(&accounts.market, now)?;
+    accounts.open_orders.count_order(clock.slot)?;
+    accounts.open_orders.check_order_risk(
+        &accounts.market,
+        params.side,
+        params.price,
+        params.max_base_lots,
+    )?;
+    let oracle_price = accounts.market.oracle_price(remaining_accounts, now)?;
+    accounts
+        .market
+        .check_price_band(oracle_price, params.price)?;
+
+    let locked = params.required_funds(&accounts.market)?;
+    let trigger = TriggerOrder {
+        trigger_id: accounts.market.next_order_id(),
+        client_order_id: params.client_order_id,
+        side: params.side,
+        order_type: params.order_type,
+        price: params.price,
+        max_base_lots: params.max_base_lots,
+        source: params.source,
+        condition: params.condition,
+        trigger_price: params.trigger_price,
+        locked,
+        group_id: 0,
+    };
+    accounts.trigger_orders.insert(trigger.clone())?;
+    let deposit = accounts.open_orders.lock_native(params.side, locked)?;
+
+    utils::deposit(
+        &accounts.token_program.to_account_info(),
+        &accounts.token_deposit_account,
+        &accounts.market_vault.to_account_info(),
+        &accounts.owner.to_account_info(),
+        deposit,
+    )?;
+    system_program::transfer(
+        CpiContext::new(
+            accounts.system_program.to_account_info(),
+            Transfer {
+                from: accounts.owner.to_account_info(),
+                to: accounts.trigger_orders.to_account_info(),
+            },
+        ),
+        TriggerOrders::EXECUTION_REWARD,
+    )?;
+
+    emit!(TriggerPlaced {
+        market: accounts.market.key(),
+        open_orders: accounts.open_orders.key(),
+        trigger_id: trigger.trigger_id,
+        client_order_id: trigger.client_order_id,
+        side: trigger.side,
+        price: trigger.price,
+        max_base_lots: trigger.max_base_lots,
+        source: trigger.source,
+        condition: trigger.condition,
+        trigger_price: trigger.trigger_price,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct PlaceTriggerOrder<'info> {
+    /// Funds the order and the execution reward, so only the `OpenOrders` owner may place
+    /// trigger orders.
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = owner @ ClobError::Unauthorized,
+        has_one = market @ ClobError::WrongMarket,
+    )]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+
+    #[account(mut, has_one = open_orders @ ClobError::WrongMarket)]
+    pub trigger_orders: Box<Account<'info, TriggerOrders>>,
+
+    #[account(mut)]
+    pub market: Box<Account<'info, Market>>,
+
+    /// Pays for the order: quote tokens for bids, base tokens for asks.
+    #[account(mut)]
+    pub token_deposit_account: Box<Account<'info, TokenAccount>>,
+    /// The market's quote vault for bids, base vault for asks.
+    #[account(mut)]
+    pub market_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
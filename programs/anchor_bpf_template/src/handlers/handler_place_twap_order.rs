@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, System, Transfer};
+use anchor_lang::Accounts;
+use anchor_spl::token::{Token, TokenAccount};
+
+use crate::errors::ClobError;
+use crate::events::TwapPlaced;
+use crate::matching::TwapOrderParams;
+use crate::state::*;
+use crate::utils;
+
+pub(crate) fn process(ctx: Context<PlaceTwapOrder>, params: TwapOrderParams) -> Result<()> {
+    let clock = Clock::get()?;
+    let now = clock.unix_timestamp;
+    let remaining_accounts = ctx.remaining_accounts;
+    let accounts = ctx.accounts;
+
+    require!(
+        accounts.market.status_at(now) == MarketStatus::Active,
+        ClobError::MarketNotActive
+    );
+    let vault = match params.side {
+        Side::Bid => accounts.market.quote_vault,
+        Side::Ask => accounts.market.base_vault,
+    };
+    require_keys_eq!(accounts.market_vault.key(), vault, ClobError::WrongMarket);
+    params.validate(&accounts.market, now)?;
+    accounts.open_orders.count_order(clock.slot)?;
+    accounts.open_orders.check_order_risk(
+        &accounts.market,
+        params.side,
+        params.price,
+        params.total_base_lots,
+    )?;
+    let oracle_price = accounts.market.oracle_price(remaining_accounts, now)?;
+    accounts
+        .market
+        .check_price_band(oracle_price, params.price)?;
+
+    let twap = &mut accounts.twap_order;
+    twap.market = accounts.market.key();
+    twap.open_orders = accounts.open_orders.key();
+    twap.side = params.side;
+    twap.price = params.price;
+    twap.total_base_lots = params.total_base_lots;
+    twap.slice_base_lots = params.slice_base_lots;
+    twap.interval_seconds = params.interval_seconds;
+    twap.client_order_id = params.client_order_id;
+    twap.remaining_base_lots = params.total_base_lots;
+    // The first slice can be executed right away.
+    twap.next_slice_timestamp = now;
+    twap.locked = twap.required_funds(&accounts.market)?;
+    let deposit = accounts.open_orders.lock_native(twap.side, twap.locked)?;
+    let reward = TwapOrder::SLICE_REWARD
+        .checked_mul(twap.slices_left())
+        .ok_or_else(|| error!(ClobError::MathOverflow))?;
+
+    utils::deposit(
+        &accounts.token_program.to_account_info(),
+        &accounts.token_deposit_account,
+        &accounts.market_vault.to_account_info(),
+        &accounts.owner.to_account_info(),
+        deposit,
+    )?;
+    system_program::transfer(
+        CpiContext::new(
+            accounts.system_program.to_account_info(),
+            Transfer {
+                from: accounts.owner.to_account_info(),
+                to: accounts.twap_order.to_account_info(),
+            },
+        ),
+        reward,
+    )?;
+
+    emit!(TwapPlaced {
+        market: accounts.market.key(),
+        open_orders: accounts.open_orders.key(),
+        twap_order: accounts.twap_order.key(),
+        client_order_id: params.client_order_id,
+        side: params.side,
+        price: params.price,
+        total_base_lots: params.total_base_lots,
+        slice_base_lots: params.slice_base_lots,
+        interval_seconds: params.interval_seconds,
+    });
+    Ok(())
+}
+
+/// `twap_order` is created zeroed and owned by the program by the caller, in the same
+/// transaction.
+#[derive(Accounts)]
+pub struct PlaceTwapOrder<'info> {
+    /// Funds the order and the slice rewards, so only the `OpenOrders` owner may place TWAP
+    /// orders.
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = owner @ ClobError::Unauthorized,
+        has_one = market @ ClobError::WrongMarket,
+    )]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+
+    #[account(zero)]
+    pub twap_order: Box<Account<'info, TwapOrder>>,
+
+    #[account(mut)]
+    pub market: Box<Account<'info, Market>>,
+
+    /// Pays for the order: quote tokens for bids, base tokens for asks.
+    #[account(mut)]
+    pub token_deposit_account: Box<Account<'info, TokenAccount>>,
+    /// The market's quote vault for bids, base vault for asks.
+    #[account(mut)]
+    pub market_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::events::BatchCleared;
+use crate::handlers::handler_clear_batch::uncross;
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<SetBatchAuction>, batch_slots: u64) -> Result<()> {
+    let clock = Clock::get()?;
+    let remaining_accounts = ctx.remaining_accounts;
+    let accounts = ctx.accounts;
+
+    // The orders collected so far are cleared before continuous matching resumes.
+    if accounts.market.batch_slots > 0 && batch_slots == 0 {
+        let batch_start_slot = accounts.market.batch_start_slot;
+        let (price, base_lots) = uncross(
+            &mut accounts.market,
+            &mut accounts.bids,
+            &mut accounts.asks,
+            &mut accounts.event_queue,
+            remaining_accounts,
+            clock.unix_timestamp,
+        )?;
+        emit!(BatchCleared {
+            market: accounts.market.key(),
+            batch_start_slot,
+            price,
+            base_lots,
+        });
+    }
+    accounts.market.batch_slots = batch_slots;
+    accounts.market.batch_start_slot = clock.slot;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetBatchAuction<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut,
+        has_one = admin @ ClobError::Unauthorized,
+        has_one = bids @ ClobError::WrongMarket,
+        has_one = asks @ ClobError::WrongMarket,
+        has_one = event_queue @ ClobError::WrongMarket,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(mut)]
+    pub bids: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub asks: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub event_queue: Box<Account<'info, EventQueue>>,
+}
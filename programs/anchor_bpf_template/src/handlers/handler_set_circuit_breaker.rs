@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CircuitBreakerArgs {
+    /// Largest move from the reference price, in basis points of it; 0 disables the
+    /// breaker.
+    pub move_bps: u16,
+    pub window_seconds: u32,
+    pub cooldown_seconds: u32,
+}
+
+pub(crate) fn process(ctx: Context<SetCircuitBreaker>, args: CircuitBreakerArgs) -> Result<()> {
+    require!(
+        args.move_bps == 0 || (args.window_seconds > 0 && args.cooldown_seconds > 0),
+        ClobError::InvalidCircuitBreaker
+    );
+    let market = &mut ctx.accounts.market;
+    market.circuit_breaker_bps = args.move_bps;
+    market.circuit_breaker_window = args.window_seconds;
+    market.circuit_breaker_cooldown = args.cooldown_seconds;
+    market.reference_price = 0;
+    market.reference_timestamp = 0;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetCircuitBreaker<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, has_one = admin @ ClobError::Unauthorized)]
+    pub market: Box<Account<'info, Market>>,
+}
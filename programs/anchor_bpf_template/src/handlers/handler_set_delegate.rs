@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<SetDelegate>, delegate: Pubkey) -> Result<()> {
+    let open_orders = &mut ctx.accounts.open_orders;
+    // Otherwise the owner could pick a delegate that lifts them.
+    require!(
+        open_orders.risk_limits == RiskLimits::default(),
+        ClobError::RiskLimitsSet
+    );
+    open_orders.delegate = delegate;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetDelegate<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut, has_one = owner @ ClobError::Unauthorized)]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+}
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<SetHeartbeat>, interval_seconds: u32) -> Result<()> {
+    let open_orders = &mut ctx.accounts.open_orders;
+    open_orders.heartbeat_interval = interval_seconds;
+    open_orders.last_heartbeat = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetHeartbeat<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut, has_one = owner @ ClobError::Unauthorized)]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+}
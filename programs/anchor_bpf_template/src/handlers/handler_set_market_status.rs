@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<SetMarketStatus>, status: MarketStatus) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    // The orders collected may cross, which continuous matching would never clear.
+    require!(
+        market.status != MarketStatus::Auction || status == MarketStatus::Auction,
+        ClobError::AuctionNotUncrossed
+    );
+    market.status = status;
+    // The admin's call ends any circuit breaker halt.
+    market.halted_until = 0;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetMarketStatus<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, has_one = admin @ ClobError::Unauthorized)]
+    pub market: Box<Account<'info, Market>>,
+}
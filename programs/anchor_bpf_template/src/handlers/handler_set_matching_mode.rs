@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+
+pub(crate) fn process(
+    ctx: Context<SetMatchingMode>,
+    mode: MatchingMode,
+    top_share_bps: u16,
+) -> Result<()> {
+    require!(top_share_bps <= 10_000, ClobError::InvalidMatchingMode);
+    let market = &mut ctx.accounts.market;
+    market.matching_mode = mode;
+    market.pro_rata_top_share_bps = match mode {
+        MatchingMode::Fifo => 0,
+        MatchingMode::ProRata => top_share_bps,
+    };
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetMatchingMode<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, has_one = admin @ ClobError::Unauthorized)]
+    pub market: Box<Account<'info, Market>>,
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct OracleConfigArgs {
+    /// `OracleKind::None` removes the band, `oracle` is then ignored.
+    pub kind: OracleKind,
+    pub price_band_bps: u16,
+    pub max_staleness: u32,
+}
+
+pub(crate) fn process(ctx: Context<SetOracleConfig>, args: OracleConfigArgs) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    if args.kind == OracleKind::None {
+        market.oracle = Pubkey::default();
+        market.oracle_kind = OracleKind::None;
+        market.price_band_bps = 0;
+        market.oracle_max_staleness = 0;
+        return Ok(());
+    }
+
+    require!(args.price_band_bps > 0, ClobError::InvalidPriceBand);
+    let oracle = &ctx.accounts.oracle;
+    OraclePrice::load(args.kind, oracle)?.to_lots(market)?;
+
+    market.oracle = oracle.key();
+    market.oracle_kind = args.kind;
+    market.price_band_bps = args.price_band_bps;
+    market.oracle_max_staleness = args.max_staleness;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetOracleConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, has_one = admin @ ClobError::Unauthorized)]
+    pub market: Box<Account<'info, Market>>,
+
+    /// CHECK: parsed according to `args.kind`, which checks its owner.
+    pub oracle: UncheckedAccount<'info>,
+}
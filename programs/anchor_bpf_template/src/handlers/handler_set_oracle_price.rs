@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<SetOraclePrice>, price: i64, expo: i32) -> Result<()> {
+    let oracle = &mut ctx.accounts.oracle;
+    oracle.price = price;
+    oracle.expo = expo;
+    oracle.last_update_timestamp = Clock::get()?.unix_timestamp;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetOraclePrice<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut, has_one = authority @ ClobError::Unauthorized)]
+    pub oracle: Box<Account<'info, InternalOracle>>,
+}
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+
+/// Applies to orders placed from now on, resting orders keep the bond they posted.
+pub(crate) fn process(ctx: Context<SetOrderBond>, bond: u64, forfeit_bps: u16) -> Result<()> {
+    require!(forfeit_bps <= 10_000, ClobError::InvalidOrderBond);
+    let market = &mut ctx.accounts.market;
+    market.order_bond = bond;
+    market.bond_forfeit_bps = forfeit_bps;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetOrderBond<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, has_one = admin @ ClobError::Unauthorized)]
+    pub market: Box<Account<'info, Market>>,
+}
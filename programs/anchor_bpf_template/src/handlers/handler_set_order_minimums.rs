@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+
+pub(crate) fn process(
+    ctx: Context<SetOrderMinimums>,
+    min_base_lots: u64,
+    min_notional: u64,
+) -> Result<()> {
+    let market = &mut ctx.accounts.market;
+    market.min_base_lots = min_base_lots;
+    market.min_notional = min_notional;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetOrderMinimums<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, has_one = admin @ ClobError::Unauthorized)]
+    pub market: Box<Account<'info, Market>>,
+}
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<SetRiskLimits>, limits: RiskLimits) -> Result<()> {
+    let accounts = ctx.accounts;
+    let open_orders = &mut accounts.open_orders;
+    let signer = accounts.signer.key();
+    require!(
+        signer == accounts.market.admin || open_orders.is_delegate(&signer),
+        ClobError::Unauthorized
+    );
+    open_orders.risk_limits = limits;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetRiskLimits<'info> {
+    /// The market admin or the `OpenOrders` delegate.
+    pub signer: Signer<'info>,
+
+    #[account(mut, has_one = market @ ClobError::WrongMarket)]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+
+    pub market: Box<Account<'info, Market>>,
+}
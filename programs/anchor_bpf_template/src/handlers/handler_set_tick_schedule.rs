@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<SetTickSchedule>, bands: Vec<TickBand>) -> Result<()> {
+    require!(
+        bands.len() <= Market::MAX_TICK_BANDS,
+        ClobError::InvalidTickSchedule
+    );
+    require!(
+        bands
+            .iter()
+            .all(|band| band.tick_size > 0 && band.min_price.is_multiple_of(band.tick_size)),
+        ClobError::InvalidTickSchedule
+    );
+    require!(
+        bands.windows(2).all(|w| w[0].min_price < w[1].min_price),
+        ClobError::InvalidTickSchedule
+    );
+    // Prices rounded up to the tick below a band then stop at its lowest price.
+    let below = std::iter::once(ctx.accounts.market.tick_size)
+        .chain(bands.iter().map(|band| band.tick_size));
+    require!(
+        bands
+            .iter()
+            .zip(below)
+            .all(|(band, tick_size)| band.min_price.is_multiple_of(tick_size)),
+        ClobError::InvalidTickSchedule
+    );
+    // Orders already resting keep their prices.
+    ctx.accounts.market.tick_schedule = bands;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetTickSchedule<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut, has_one = admin @ ClobError::Unauthorized)]
+    pub market: Box<Account<'info, Market>>,
+}
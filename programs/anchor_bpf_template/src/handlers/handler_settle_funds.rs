@@ -0,0 +1,90 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+use anchor_spl::token::{Token, TokenAccount};
+
+use crate::errors::ClobError;
+use crate::events::FundsSettled;
+use crate::pda::MARKET_AUTHORITY_SEED;
+use crate::state::*;
+use crate::utils;
+
+pub(crate) fn process(ctx: Context<SettleFunds>) -> Result<()> {
+    let accounts = ctx.accounts;
+    let base = accounts.open_orders.base_free;
+    let quote = accounts.open_orders.quote_free;
+    let bonds = accounts.open_orders.bond_free;
+    accounts.open_orders.base_free = 0;
+    accounts.open_orders.quote_free = 0;
+    accounts.open_orders.bond_free = 0;
+
+    let market_key = accounts.market.key();
+    let market = &accounts.market;
+    let token_program = accounts.token_program.to_account_info();
+    utils::withdraw(
+        &token_program,
+        &accounts.base_vault.to_account_info(),
+        &accounts.user_base_account.to_account_info(),
+        &accounts.market_authority.to_account_info(),
+        &market.authority_seeds(&market_key),
+        base,
+    )?;
+    utils::withdraw(
+        &token_program,
+        &accounts.quote_vault.to_account_info(),
+        &accounts.user_quote_account.to_account_info(),
+        &accounts.market_authority.to_account_info(),
+        &market.authority_seeds(&market_key),
+        quote,
+    )?;
+    utils::pay_lamports(
+        &accounts.market.to_account_info(),
+        &accounts.owner.to_account_info(),
+        bonds,
+    )?;
+
+    emit!(FundsSettled {
+        market: market_key,
+        open_orders: accounts.open_orders.key(),
+        owner: accounts.owner.key(),
+        base_amount: base,
+        quote_amount: quote,
+        bond_lamports: bonds,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SettleFunds<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = market @ ClobError::WrongMarket,
+        has_one = owner @ ClobError::Unauthorized,
+    )]
+    pub open_orders: Box<Account<'info, OpenOrders>>,
+
+    #[account(mut,
+        has_one = base_vault @ ClobError::WrongMarket,
+        has_one = quote_vault @ ClobError::WrongMarket,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    /// CHECK: PDA, signs for the vaults.
+    #[account(seeds = [MARKET_AUTHORITY_SEED, market.key().as_ref()], bump = market.authority_bump)]
+    pub market_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = user_base_account.mint == market.base_mint @ ClobError::WrongMint)]
+    pub user_base_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut,
+        constraint = user_quote_account.mint == market.quote_mint @ ClobError::WrongMint,
+    )]
+    pub user_quote_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
@@ -0,0 +1,150 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+use anchor_spl::token::{Token, TokenAccount};
+
+use crate::errors::ClobError;
+use crate::matching::{OrderParams, OrderSummary, Orderbook};
+use crate::pda::MARKET_AUTHORITY_SEED;
+use crate::state::*;
+use crate::utils;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SwapArgs {
+    pub side: Side,
+    /// Worst price, in quote lots per base lot, the swap may trade at.
+    pub limit_price: u64,
+    pub max_base_lots: u64,
+    /// The swap fails unless at least this many base lots are filled.
+    pub min_base_lots: u64,
+}
+
+pub(crate) fn process(ctx: Context<Swap>, args: SwapArgs) -> Result<OrderSummary> {
+    let now = Clock::get()?.unix_timestamp;
+    let remaining_accounts = ctx.remaining_accounts;
+    let accounts = ctx.accounts;
+
+    require!(
+        accounts.market.status_at(now) == MarketStatus::Active,
+        ClobError::MarketNotActive
+    );
+    let params = OrderParams {
+        side: args.side,
+        price: args.limit_price,
+        max_base_lots: args.max_base_lots,
+        order_type: OrderType::ImmediateOrCancel,
+        client_order_id: 0,
+        expiry_timestamp: 0,
+    };
+    params.validate(&accounts.market, now)?;
+    let oracle_price = accounts.market.oracle_price(remaining_accounts, now)?;
+    accounts
+        .market
+        .check_price_band(oracle_price, params.price)?;
+
+    let mut midpoint_book = accounts.market.load_midpoint_book(remaining_accounts)?;
+    let mut book = Orderbook {
+        bids: &mut accounts.bids,
+        asks: &mut accounts.asks,
+        oracle_price,
+        midpoint: midpoint_book.as_deref_mut(),
+    };
+    let outcome = book.new_order(
+        &mut accounts.market,
+        &mut accounts.event_queue,
+        accounts.owner.key(),
+        None,
+        &params,
+        now,
+    )?;
+    require!(
+        outcome.base_lots_filled >= args.min_base_lots,
+        ClobError::SlippageExceeded
+    );
+
+    if let Some(midpoint_book) = &midpoint_book {
+        midpoint_book.exit(&crate::ID)?;
+    }
+    let market_key = accounts.market.key();
+    let market = &accounts.market;
+    let base = market.base_lots_to_native(outcome.base_lots_filled)?;
+    let quote = market.quote_lots_to_native(outcome.quote_lots_filled)?;
+    let (pay_from, pay_to, pay_amount, receive_from, receive_to, receive_amount) = match args.side {
+        Side::Bid => (
+            &accounts.user_quote_account,
+            &accounts.quote_vault,
+            quote
+                .checked_add(outcome.taker_fee)
+                .ok_or_else(|| error!(ClobError::MathOverflow))?,
+            &accounts.base_vault,
+            &accounts.user_base_account,
+            base,
+        ),
+        Side::Ask => (
+            &accounts.user_base_account,
+            &accounts.base_vault,
+            base,
+            &accounts.quote_vault,
+            &accounts.user_quote_account,
+            quote
+                .checked_sub(outcome.taker_fee)
+                .ok_or_else(|| error!(ClobError::MathOverflow))?,
+        ),
+    };
+
+    let token_program = accounts.token_program.to_account_info();
+    utils::deposit(
+        &token_program,
+        pay_from,
+        &pay_to.to_account_info(),
+        &accounts.owner.to_account_info(),
+        pay_amount,
+    )?;
+    utils::withdraw(
+        &token_program,
+        &receive_from.to_account_info(),
+        &receive_to.to_account_info(),
+        &accounts.market_authority.to_account_info(),
+        &market.authority_seeds(&market_key),
+        receive_amount,
+    )?;
+    outcome.summary(market)
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mut,
+        has_one = bids @ ClobError::WrongMarket,
+        has_one = asks @ ClobError::WrongMarket,
+        has_one = event_queue @ ClobError::WrongMarket,
+        has_one = base_vault @ ClobError::WrongMarket,
+        has_one = quote_vault @ ClobError::WrongMarket,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    /// CHECK: PDA, signs for the vaults.
+    #[account(seeds = [MARKET_AUTHORITY_SEED, market.key().as_ref()], bump = market.authority_bump)]
+    pub market_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub bids: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub asks: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub event_queue: Box<Account<'info, EventQueue>>,
+
+    #[account(mut)]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = user_base_account.mint == market.base_mint @ ClobError::WrongMint)]
+    pub user_base_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut,
+        constraint = user_quote_account.mint == market.quote_mint @ ClobError::WrongMint,
+    )]
+    pub user_quote_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
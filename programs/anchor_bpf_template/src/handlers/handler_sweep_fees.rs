@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+use anchor_spl::token::{Token, TokenAccount};
+
+use crate::errors::ClobError;
+use crate::events::FeesSwept;
+use crate::pda::MARKET_AUTHORITY_SEED;
+use crate::state::*;
+use crate::utils;
+
+pub(crate) fn process(ctx: Context<SweepFees>) -> Result<()> {
+    let accounts = ctx.accounts;
+    let amount = accounts.market.fees_accrued;
+    accounts.market.fees_accrued = 0;
+
+    let market_key = accounts.market.key();
+    let market = &accounts.market;
+    utils::withdraw(
+        &accounts.token_program.to_account_info(),
+        &accounts.quote_vault.to_account_info(),
+        &accounts.destination.to_account_info(),
+        &accounts.market_authority.to_account_info(),
+        &market.authority_seeds(&market_key),
+        amount,
+    )?;
+
+    emit!(FeesSwept {
+        market: market_key,
+        destination: accounts.destination.key(),
+        amount,
+    });
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SweepFees<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut,
+        has_one = admin @ ClobError::Unauthorized,
+        has_one = quote_vault @ ClobError::WrongMarket,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    /// CHECK: PDA, signs for the vaults.
+    #[account(seeds = [MARKET_AUTHORITY_SEED, market.key().as_ref()], bump = market.authority_bump)]
+    pub market_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, constraint = destination.mint == market.quote_mint @ ClobError::WrongMint)]
+    pub destination: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
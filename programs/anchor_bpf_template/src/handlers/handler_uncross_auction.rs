@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Accounts;
+
+use crate::errors::ClobError;
+use crate::events::AuctionUncrossed;
+use crate::handlers::handler_clear_batch::uncross;
+use crate::state::*;
+
+pub(crate) fn process(ctx: Context<UncrossAuction>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let remaining_accounts = ctx.remaining_accounts;
+    let accounts = ctx.accounts;
+
+    require!(
+        accounts.market.status == MarketStatus::Auction,
+        ClobError::NotInAuction
+    );
+    let (price, base_lots) = uncross(
+        &mut accounts.market,
+        &mut accounts.bids,
+        &mut accounts.asks,
+        &mut accounts.event_queue,
+        remaining_accounts,
+        now,
+    )?;
+    accounts.market.status = MarketStatus::Active;
+
+    emit!(AuctionUncrossed {
+        market: accounts.market.key(),
+        price,
+        base_lots,
+    });
+    Ok(())
+}
+
+/// The admin decides when an auction ends. The makers' fills are settled by
+/// `consume_events`.
+#[derive(Accounts)]
+pub struct UncrossAuction<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(mut,
+        has_one = admin @ ClobError::Unauthorized,
+        has_one = bids @ ClobError::WrongMarket,
+        has_one = asks @ ClobError::WrongMarket,
+        has_one = event_queue @ ClobError::WrongMarket,
+    )]
+    pub market: Box<Account<'info, Market>>,
+
+    #[account(mut)]
+    pub bids: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub asks: Box<Account<'info, BookSide>>,
+    #[account(mut)]
+    pub event_queue: Box<Account<'info, EventQueue>>,
+}
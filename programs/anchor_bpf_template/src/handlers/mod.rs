@@ -1,2 +1,75 @@
+pub mod handler_batch_orders;
+pub mod handler_cancel_all_orders;
+pub mod handler_cancel_order;
+pub mod handler_cancel_stale_orders;
+pub mod handler_cancel_trigger_order;
+pub mod handler_cancel_twap_order;
+pub mod handler_clear_batch;
+pub mod handler_consume_events;
+pub mod handler_execute_triggers;
+pub mod handler_execute_twap_slice;
+pub mod handler_heartbeat;
+pub mod handler_init_internal_oracle;
+pub mod handler_init_midpoint_book;
+pub mod handler_init_open_orders;
+pub mod handler_init_trigger_orders;
 pub mod handler_initialize;
+pub mod handler_link_orders;
+pub mod handler_place_iceberg_order;
+pub mod handler_place_midpoint_order;
+pub mod handler_place_order;
+pub mod handler_place_pegged_order;
+pub mod handler_place_trigger_order;
+pub mod handler_place_twap_order;
+pub mod handler_set_batch_auction;
+pub mod handler_set_circuit_breaker;
+pub mod handler_set_delegate;
+pub mod handler_set_heartbeat;
+pub mod handler_set_market_status;
+pub mod handler_set_matching_mode;
+pub mod handler_set_oracle_config;
+pub mod handler_set_oracle_price;
+pub mod handler_set_order_bond;
+pub mod handler_set_order_minimums;
+pub mod handler_set_risk_limits;
+pub mod handler_set_tick_schedule;
+pub mod handler_settle_funds;
+pub mod handler_swap;
+pub mod handler_sweep_fees;
+pub mod handler_uncross_auction;
+pub use handler_batch_orders::*;
+pub use handler_cancel_all_orders::*;
+pub use handler_cancel_order::*;
+pub use handler_cancel_stale_orders::*;
+pub use handler_cancel_trigger_order::*;
+pub use handler_cancel_twap_order::*;
+pub use handler_clear_batch::*;
+pub use handler_consume_events::*;
+pub use handler_execute_triggers::*;
+pub use handler_execute_twap_slice::*;
+pub use handler_heartbeat::*;
+pub use handler_init_internal_oracle::*;
+pub use handler_init_midpoint_book::*;
+pub use handler_init_open_orders::*;
+pub use handler_init_trigger_orders::*;
 pub use handler_initialize::*;
+pub use handler_link_orders::*;
+pub use handler_place_order::*;
+pub use handler_place_trigger_order::*;
+pub use handler_place_twap_order::*;
+pub use handler_set_batch_auction::*;
+pub use handler_set_circuit_breaker::*;
+pub use handler_set_delegate::*;
+pub use handler_set_heartbeat::*;
+pub use handler_set_market_status::*;
+pub use handler_set_matching_mode::*;
+pub use handler_set_oracle_config::*;
+pub use handler_set_oracle_price::*;
+pub use handler_set_order_bond::*;
+pub use handler_set_order_minimums::*;
+pub use handler_set_risk_limits::*;
+pub use handler_set_tick_schedule::*;
+pub use handler_settle_funds::*;
+pub use handler_swap::*;
+pub use handler_sweep_fees::*;
+pub use handler_uncross_auction::*;
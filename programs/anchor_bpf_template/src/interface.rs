@@ -0,0 +1,383 @@
+//! Account builders for programs calling the order book through CPI.
+//!
+//! Resolve a market's accounts once into [`MarketAccounts`], then build the
+//! `cpi::accounts::*` struct for each call by adding the accounts of the user being
+//! traded for. [`MarketAccounts::matches`] checks a set of accounts the caller was
+//! handed against the market's own state.
+//!
+//! A market's oracle and midpoint book, if it has them, go in
+//! `CpiContext::with_remaining_accounts`, see the crate docs. Instructions reserved for
+//! the market admin have no builders here.
+
+use anchor_lang::prelude::*;
+
+use crate::cpi::accounts::{
+    BatchOrders, CancelAllOrders, CancelOrder, CancelStaleOrders, CancelTriggerOrder,
+    CancelTwapOrder, ClearBatch, ConsumeEvents, ExecuteTriggers, ExecuteTwapSlice, Heartbeat,
+    InitOpenOrders, InitTriggerOrders, LinkOrders, PlaceOrder, PlaceTriggerOrder, PlaceTwapOrder,
+    SetDelegate, SetHeartbeat, SetRiskLimits, SettleFunds, Swap,
+};
+use crate::pda::find_market_authority_address;
+use crate::state::{Market, Side};
+
+#[derive(Clone)]
+pub struct MarketAccounts<'info> {
+    pub market: AccountInfo<'info>,
+    pub bids: AccountInfo<'info>,
+    pub asks: AccountInfo<'info>,
+    pub event_queue: AccountInfo<'info>,
+    pub base_vault: AccountInfo<'info>,
+    pub quote_vault: AccountInfo<'info>,
+    pub market_authority: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+impl<'info> MarketAccounts<'info> {
+    /// Whether these are the accounts recorded in `market`, the deserialised state of
+    /// `self.market`.
+    pub fn matches(&self, market: &Market) -> bool {
+        *self.bids.key == market.bids
+            && *self.asks.key == market.asks
+            && *self.event_queue.key == market.event_queue
+            && *self.base_vault.key == market.base_vault
+            && *self.quote_vault.key == market.quote_vault
+            && *self.market_authority.key == find_market_authority_address(self.market.key).0
+    }
+
+    /// The vault an order on `side` is paid into: quote for bids, base for asks.
+    fn deposit_vault(&self, side: Side) -> AccountInfo<'info> {
+        match side {
+            Side::Bid => self.quote_vault.clone(),
+            Side::Ask => self.base_vault.clone(),
+        }
+    }
+
+    /// `open_orders` must already be allocated, zeroed and owned by this program.
+    pub fn init_open_orders(
+        &self,
+        owner: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+    ) -> InitOpenOrders<'info> {
+        InitOpenOrders {
+            owner,
+            market: self.market.clone(),
+            open_orders,
+        }
+    }
+
+    pub fn set_delegate(
+        &self,
+        owner: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+    ) -> SetDelegate<'info> {
+        SetDelegate { owner, open_orders }
+    }
+
+    pub fn set_heartbeat(
+        &self,
+        owner: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+    ) -> SetHeartbeat<'info> {
+        SetHeartbeat { owner, open_orders }
+    }
+
+    pub fn heartbeat(
+        &self,
+        signer: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+    ) -> Heartbeat<'info> {
+        Heartbeat {
+            signer,
+            open_orders,
+        }
+    }
+
+    /// `signer` is the market admin or the `OpenOrders` delegate.
+    pub fn set_risk_limits(
+        &self,
+        signer: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+    ) -> SetRiskLimits<'info> {
+        SetRiskLimits {
+            signer,
+            open_orders,
+            market: self.market.clone(),
+        }
+    }
+
+    /// `token_deposit_account` holds quote tokens for bids and base tokens for asks. The
+    /// same accounts serve `place_pegged_order`, `place_midpoint_order` and
+    /// `place_iceberg_order`.
+    pub fn place_order(
+        &self,
+        signer: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+        token_deposit_account: AccountInfo<'info>,
+        side: Side,
+    ) -> PlaceOrder<'info> {
+        PlaceOrder {
+            signer,
+            open_orders,
+            market: self.market.clone(),
+            bids: self.bids.clone(),
+            asks: self.asks.clone(),
+            event_queue: self.event_queue.clone(),
+            token_deposit_account,
+            market_vault: self.deposit_vault(side),
+            token_program: self.token_program.clone(),
+            system_program: self.system_program.clone(),
+        }
+    }
+
+    pub fn cancel_order(
+        &self,
+        signer: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+    ) -> CancelOrder<'info> {
+        CancelOrder {
+            signer,
+            open_orders,
+            market: self.market.clone(),
+            bids: self.bids.clone(),
+            asks: self.asks.clone(),
+        }
+    }
+
+    pub fn cancel_all_orders(
+        &self,
+        signer: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+    ) -> CancelAllOrders<'info> {
+        CancelAllOrders {
+            signer,
+            open_orders,
+            market: self.market.clone(),
+            bids: self.bids.clone(),
+            asks: self.asks.clone(),
+        }
+    }
+
+    pub fn cancel_stale_orders(&self, open_orders: AccountInfo<'info>) -> CancelStaleOrders<'info> {
+        CancelStaleOrders {
+            open_orders,
+            market: self.market.clone(),
+            bids: self.bids.clone(),
+            asks: self.asks.clone(),
+        }
+    }
+
+    pub fn link_orders(
+        &self,
+        signer: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+    ) -> LinkOrders<'info> {
+        LinkOrders {
+            signer,
+            open_orders,
+            market: self.market.clone(),
+            bids: self.bids.clone(),
+            asks: self.asks.clone(),
+        }
+    }
+
+    pub fn batch_orders(
+        &self,
+        signer: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+        user_base_account: AccountInfo<'info>,
+        user_quote_account: AccountInfo<'info>,
+    ) -> BatchOrders<'info> {
+        BatchOrders {
+            signer,
+            open_orders,
+            market: self.market.clone(),
+            bids: self.bids.clone(),
+            asks: self.asks.clone(),
+            event_queue: self.event_queue.clone(),
+            base_vault: self.base_vault.clone(),
+            quote_vault: self.quote_vault.clone(),
+            user_base_account,
+            user_quote_account,
+            token_program: self.token_program.clone(),
+            system_program: self.system_program.clone(),
+        }
+    }
+
+    /// `trigger_orders` must already be allocated, zeroed and owned by this program.
+    pub fn init_trigger_orders(
+        &self,
+        owner: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+        trigger_orders: AccountInfo<'info>,
+    ) -> InitTriggerOrders<'info> {
+        InitTriggerOrders {
+            owner,
+            open_orders,
+            trigger_orders,
+        }
+    }
+
+    /// `token_deposit_account` as for [`MarketAccounts::place_order`].
+    pub fn place_trigger_order(
+        &self,
+        owner: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+        trigger_orders: AccountInfo<'info>,
+        token_deposit_account: AccountInfo<'info>,
+        side: Side,
+    ) -> PlaceTriggerOrder<'info> {
+        PlaceTriggerOrder {
+            owner,
+            open_orders,
+            trigger_orders,
+            market: self.market.clone(),
+            token_deposit_account,
+            market_vault: self.deposit_vault(side),
+            token_program: self.token_program.clone(),
+            system_program: self.system_program.clone(),
+        }
+    }
+
+    pub fn cancel_trigger_order(
+        &self,
+        owner: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+        trigger_orders: AccountInfo<'info>,
+    ) -> CancelTriggerOrder<'info> {
+        CancelTriggerOrder {
+            owner,
+            open_orders,
+            trigger_orders,
+        }
+    }
+
+    pub fn execute_triggers(
+        &self,
+        executor: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+        trigger_orders: AccountInfo<'info>,
+    ) -> ExecuteTriggers<'info> {
+        ExecuteTriggers {
+            executor,
+            open_orders,
+            trigger_orders,
+            market: self.market.clone(),
+            bids: self.bids.clone(),
+            asks: self.asks.clone(),
+            event_queue: self.event_queue.clone(),
+        }
+    }
+
+    /// `twap_order` must already be allocated, zeroed and owned by this program.
+    /// `token_deposit_account` as for [`MarketAccounts::place_order`].
+    pub fn place_twap_order(
+        &self,
+        owner: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+        twap_order: AccountInfo<'info>,
+        token_deposit_account: AccountInfo<'info>,
+        side: Side,
+    ) -> PlaceTwapOrder<'info> {
+        PlaceTwapOrder {
+            owner,
+            open_orders,
+            twap_order,
+            market: self.market.clone(),
+            token_deposit_account,
+            market_vault: self.deposit_vault(side),
+            token_program: self.token_program.clone(),
+            system_program: self.system_program.clone(),
+        }
+    }
+
+    pub fn cancel_twap_order(
+        &self,
+        owner: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+        twap_order: AccountInfo<'info>,
+    ) -> CancelTwapOrder<'info> {
+        CancelTwapOrder {
+            owner,
+            open_orders,
+            twap_order,
+        }
+    }
+
+    pub fn execute_twap_slice(
+        &self,
+        executor: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+        twap_order: AccountInfo<'info>,
+    ) -> ExecuteTwapSlice<'info> {
+        ExecuteTwapSlice {
+            executor,
+            open_orders,
+            twap_order,
+            market: self.market.clone(),
+            bids: self.bids.clone(),
+            asks: self.asks.clone(),
+            event_queue: self.event_queue.clone(),
+        }
+    }
+
+    pub fn swap(
+        &self,
+        owner: AccountInfo<'info>,
+        user_base_account: AccountInfo<'info>,
+        user_quote_account: AccountInfo<'info>,
+    ) -> Swap<'info> {
+        Swap {
+            owner,
+            market: self.market.clone(),
+            bids: self.bids.clone(),
+            asks: self.asks.clone(),
+            event_queue: self.event_queue.clone(),
+            base_vault: self.base_vault.clone(),
+            quote_vault: self.quote_vault.clone(),
+            market_authority: self.market_authority.clone(),
+            user_base_account,
+            user_quote_account,
+            token_program: self.token_program.clone(),
+        }
+    }
+
+    pub fn settle_funds(
+        &self,
+        owner: AccountInfo<'info>,
+        open_orders: AccountInfo<'info>,
+        user_base_account: AccountInfo<'info>,
+        user_quote_account: AccountInfo<'info>,
+    ) -> SettleFunds<'info> {
+        SettleFunds {
+            owner,
+            open_orders,
+            market: self.market.clone(),
+            base_vault: self.base_vault.clone(),
+            quote_vault: self.quote_vault.clone(),
+            market_authority: self.market_authority.clone(),
+            user_base_account,
+            user_quote_account,
+            token_program: self.token_program.clone(),
+        }
+    }
+
+    pub fn clear_batch(&self) -> ClearBatch<'info> {
+        ClearBatch {
+            market: self.market.clone(),
+            bids: self.bids.clone(),
+            asks: self.asks.clone(),
+            event_queue: self.event_queue.clone(),
+        }
+    }
+
+    /// Maker `OpenOrders` accounts go in `CpiContext::with_remaining_accounts`. `cranker`
+    /// collects the bonds forfeited by expired and evicted orders.
+    pub fn consume_events(&self, cranker: AccountInfo<'info>) -> ConsumeEvents<'info> {
+        ConsumeEvents {
+            market: self.market.clone(),
+            event_queue: self.event_queue.clone(),
+            cranker,
+        }
+    }
+}
@@ -1,13 +1,350 @@
+//! Central limit order book.
+//!
+//! # Calling from another program
+//!
+//! Enable the `cpi` feature to get Anchor's generated `cpi` module, one function per
+//! instruction below, together with:
+//!
+//! - [`pda`]: seeds and derivation of the market authority PDA that owns the vaults.
+//! - [`interface`]: builders that assemble the `cpi::accounts::*` structs from a market's
+//!   accounts, so a caller only supplies the accounts specific to its own user.
+//!
+//! A program trading on behalf of its users typically makes one of its own PDAs the owner
+//! of an `OpenOrders` account per market and signs as that PDA:
+//!
+//! ```ignore
+//! let accounts = market_accounts.place_order(vault_authority, open_orders, vault_quote, Side::Bid);
+//! let summary = anchor_bpf_template::cpi::place_order(
+//!     CpiContext::new_with_signer(clob_program, accounts, &[vault_authority_seeds]),
+//!     params,
+//!     None,
+//! )?
+//! .get();
+//! ```
+//!
+//! `place_order`, `swap` and `cancel_order` return an [`OrderSummary`] of what was filled,
+//! the fee paid and whether the order rested.
+//!
+//! # Optional market accounts
+//!
+//! A market may have an oracle and a midpoint book, which also holds the hidden reserves
+//! of iceberg orders. They are passed as remaining accounts, in any order: the oracle to
+//! instructions that place orders, the midpoint book, writable, to those and to cancels.
+//!
+//! # Call auctions
+//!
+//! A market set to `MarketStatus::Auction`, at listing or after a halt, takes limit and
+//! post-only orders without matching them. `uncross_auction` then fills the crossing
+//! orders at the single price that trades the most and returns the market to `Active`.
+//!
+//! In batch mode, set with `set_batch_auction`, an `Active` market always collects orders
+//! this way, and anyone may call `clear_batch` to uncross them every `batch_slots` slots.
+//!
+//! # Circuit breaker
+//!
+//! With `set_circuit_breaker`, a fill moving the price too far from the last trade price
+//! at the start of its window stops matching and makes the market cancel-only for a
+//! cool-down. `set_market_status` ends a halt early. The orders a `batch_orders` call
+//! has not placed yet are dropped, and triggers `execute_triggers` has not run yet stay
+//! pending.
+//!
+//! # Trigger orders
+//!
+//! Stop-loss and take-profit orders are kept off the book in a `TriggerOrders` account
+//! with their funds locked. Once the oracle or last trade price crosses a trigger price,
+//! anyone may call `execute_triggers` to place them and collect a lamport reward per order.
+//! In batch mode, immediate-or-cancel trigger orders stay pending until it ends.
+//!
+//! `link_orders` groups resting and trigger orders of one `OpenOrders` account so that a
+//! fill of one cancels the others: resting members during matching, trigger orders the
+//! next time `execute_triggers` runs.
+//!
+//! # TWAP orders
+//!
+//! A `TwapOrder` account holds a funded parent order that anyone may slice with
+//! `execute_twap_slice`: one immediate-or-cancel order of up to the slice size per
+//! interval, for a lamport reward per slice. In batch mode the slices are skipped.
+#![allow(clippy::result_large_err)]
+
 use anchor_lang::prelude::*;
+pub mod errors;
+pub mod events;
 mod handlers;
-use crate::handlers::*;
+#[cfg(feature = "cpi")]
+pub mod interface;
+pub mod matching;
+pub mod math;
+pub mod pda;
+pub mod state;
+mod utils;
+pub use crate::handlers::*;
+pub use crate::matching::{
+    IcebergOrderParams, MidpointOrderParams, OrderParams, OrderSummary, PeggedOrderParams,
+    TriggerOrderParams, TwapOrderParams,
+};
+pub use crate::state::{
+    MarketStatus, MatchingMode, OracleKind, OrderType, RiskLimits, Side, TickBand,
+    TriggerCondition, TriggerSource,
+};
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
 #[program]
 pub mod anchor_bpf_template {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        handlers::handler_initialize::process(ctx)
+    /// Creates a market for the `base_mint`/`quote_mint` pair with its books, event queue
+    /// and vaults.
+    pub fn initialize(ctx: Context<Initialize>, args: InitializeArgs) -> Result<()> {
+        handlers::handler_initialize::process(ctx, args)
+    }
+
+    pub fn init_open_orders(ctx: Context<InitOpenOrders>) -> Result<()> {
+        handlers::handler_init_open_orders::process(ctx)
+    }
+
+    /// Lets `delegate` place and cancel orders, but not withdraw, on the owner's behalf.
+    /// Fails while the account has risk limits set.
+    pub fn set_delegate(ctx: Context<SetDelegate>, delegate: Pubkey) -> Result<()> {
+        handlers::handler_set_delegate::process(ctx, delegate)
+    }
+
+    /// Lets anyone cancel the resting orders once `interval_seconds` pass without a
+    /// heartbeat, 0 to disable. Counts as a heartbeat.
+    pub fn set_heartbeat(ctx: Context<SetHeartbeat>, interval_seconds: u32) -> Result<()> {
+        handlers::handler_set_heartbeat::process(ctx, interval_seconds)
+    }
+
+    /// Signals that the owner's quoting is alive, see `set_heartbeat`.
+    pub fn heartbeat(ctx: Context<Heartbeat>) -> Result<()> {
+        handlers::handler_heartbeat::process(ctx)
+    }
+
+    /// Caps the order rate, open notional and net base position of an `OpenOrders`
+    /// account, see `RiskLimits`. Signed by the market admin or the account's delegate.
+    pub fn set_risk_limits(ctx: Context<SetRiskLimits>, limits: RiskLimits) -> Result<()> {
+        handlers::handler_set_risk_limits::process(ctx, limits)
+    }
+
+    /// Sets the market's status and ends any circuit breaker halt. An auction can only be
+    /// left through `uncross_auction`.
+    pub fn set_market_status(ctx: Context<SetMarketStatus>, status: MarketStatus) -> Result<()> {
+        handlers::handler_set_market_status::process(ctx, status)
+    }
+
+    /// Ends an auction, filling the crossing orders at the price that trades the most, and
+    /// resumes continuous trading. Auctions start with `set_market_status`.
+    pub fn uncross_auction(ctx: Context<UncrossAuction>) -> Result<()> {
+        handlers::handler_uncross_auction::process(ctx)
+    }
+
+    /// Switches the market to batches of `batch_slots` slots cleared at a single price, or
+    /// back to continuous matching with 0, clearing the pending batch.
+    pub fn set_batch_auction(ctx: Context<SetBatchAuction>, batch_slots: u64) -> Result<()> {
+        handlers::handler_set_batch_auction::process(ctx, batch_slots)
+    }
+
+    /// Clears the current batch once its slots have passed and starts the next one.
+    pub fn clear_batch(ctx: Context<ClearBatch>) -> Result<()> {
+        handlers::handler_clear_batch::process(ctx)
+    }
+
+    /// Chooses how takers are split between orders at one price, `top_share_bps` being the
+    /// oldest order's share under `MatchingMode::ProRata`.
+    pub fn set_matching_mode(
+        ctx: Context<SetMatchingMode>,
+        mode: MatchingMode,
+        top_share_bps: u16,
+    ) -> Result<()> {
+        handlers::handler_set_matching_mode::process(ctx, mode, top_share_bps)
+    }
+
+    /// Sets the lamports posted with every resting order and the share of it forfeited to
+    /// the cranker when the order expires or is evicted.
+    pub fn set_order_bond(ctx: Context<SetOrderBond>, bond: u64, forfeit_bps: u16) -> Result<()> {
+        handlers::handler_set_order_bond::process(ctx, bond, forfeit_bps)
+    }
+
+    /// Sets the smallest order, and resting remainder, in base lots and in native quote
+    /// value, 0 for no minimum.
+    pub fn set_order_minimums(
+        ctx: Context<SetOrderMinimums>,
+        min_base_lots: u64,
+        min_notional: u64,
+    ) -> Result<()> {
+        handlers::handler_set_order_minimums::process(ctx, min_base_lots, min_notional)
+    }
+
+    /// Replaces the market's tick sizes by price band, an empty schedule leaving only the
+    /// base tick size. Each band starts at a multiple of its own tick size and of the one
+    /// below it.
+    pub fn set_tick_schedule(ctx: Context<SetTickSchedule>, bands: Vec<TickBand>) -> Result<()> {
+        handlers::handler_set_tick_schedule::process(ctx, bands)
+    }
+
+    /// Halts trading for a cool-down when fills move the price too far within a window,
+    /// or disables that.
+    pub fn set_circuit_breaker(
+        ctx: Context<SetCircuitBreaker>,
+        args: CircuitBreakerArgs,
+    ) -> Result<()> {
+        handlers::handler_set_circuit_breaker::process(ctx, args)
+    }
+
+    /// Adds a book for hidden midpoint orders to the market.
+    pub fn init_midpoint_book(ctx: Context<InitMidpointBook>) -> Result<()> {
+        handlers::handler_init_midpoint_book::process(ctx)
+    }
+
+    /// Bands limit prices around an oracle price, or removes the band.
+    pub fn set_oracle_config(ctx: Context<SetOracleConfig>, args: OracleConfigArgs) -> Result<()> {
+        handlers::handler_set_oracle_config::process(ctx, args)
+    }
+
+    /// Creates an oracle whose price, `price * 10^expo` quote per base token, is pushed by
+    /// `authority`.
+    pub fn init_internal_oracle(
+        ctx: Context<InitInternalOracle>,
+        price: i64,
+        expo: i32,
+    ) -> Result<()> {
+        handlers::handler_init_internal_oracle::process(ctx, price, expo)
+    }
+
+    pub fn set_oracle_price(ctx: Context<SetOraclePrice>, price: i64, expo: i32) -> Result<()> {
+        handlers::handler_set_oracle_price::process(ctx, price, expo)
+    }
+
+    /// Fails unless `expected_sequence`, if given, is the `OpenOrders` sequence number,
+    /// which every call moves on.
+    pub fn place_order(
+        ctx: Context<PlaceOrder>,
+        params: OrderParams,
+        expected_sequence: Option<u64>,
+    ) -> Result<OrderSummary> {
+        handlers::handler_place_order::process(ctx, params, expected_sequence)
+    }
+
+    /// Places an order at the oracle price plus an offset that keeps following the oracle
+    /// while it rests. `expected_sequence` as for `place_order`.
+    pub fn place_pegged_order(
+        ctx: Context<PlaceOrder>,
+        params: PeggedOrderParams,
+        expected_sequence: Option<u64>,
+    ) -> Result<OrderSummary> {
+        handlers::handler_place_pegged_order::process(ctx, params, expected_sequence)
+    }
+
+    /// Places a hidden order that rests at the best bid and offer midpoint and only trades
+    /// with incoming takers, at the mid. `expected_sequence` as for `place_order`.
+    pub fn place_midpoint_order(
+        ctx: Context<PlaceOrder>,
+        params: MidpointOrderParams,
+        expected_sequence: Option<u64>,
+    ) -> Result<OrderSummary> {
+        handlers::handler_place_midpoint_order::process(ctx, params, expected_sequence)
+    }
+
+    /// Places a limit order that shows `params.display_base_lots` at a time, keeping the
+    /// rest hidden in the market's midpoint book. `expected_sequence` as for `place_order`.
+    pub fn place_iceberg_order(
+        ctx: Context<PlaceOrder>,
+        params: IcebergOrderParams,
+        expected_sequence: Option<u64>,
+    ) -> Result<OrderSummary> {
+        handlers::handler_place_iceberg_order::process(ctx, params, expected_sequence)
+    }
+
+    /// Returns the cancelled order's summary, with `base_cancelled` set to what was still open.
+    pub fn cancel_order(ctx: Context<CancelOrder>, order_id: u64) -> Result<OrderSummary> {
+        handlers::handler_cancel_order::process(ctx, order_id)
+    }
+
+    pub fn cancel_all_orders(ctx: Context<CancelAllOrders>) -> Result<()> {
+        handlers::handler_cancel_all_orders::process(ctx)
+    }
+
+    /// Cancels all resting orders of an `OpenOrders` account that missed a heartbeat.
+    pub fn cancel_stale_orders(ctx: Context<CancelStaleOrders>) -> Result<()> {
+        handlers::handler_cancel_stale_orders::process(ctx)
+    }
+
+    /// Links resting and trigger orders of `open_orders` so that a fill of one cancels
+    /// the others.
+    pub fn link_orders(
+        ctx: Context<LinkOrders>,
+        group_id: u64,
+        order_ids: Vec<u64>,
+        trigger_ids: Vec<u64>,
+    ) -> Result<()> {
+        handlers::handler_link_orders::process(ctx, group_id, order_ids, trigger_ids)
+    }
+
+    /// `expected_sequence` as for `place_order`.
+    pub fn batch_orders(
+        ctx: Context<BatchOrders>,
+        cancel_order_ids: Vec<u64>,
+        orders: Vec<OrderParams>,
+        expected_sequence: Option<u64>,
+    ) -> Result<()> {
+        handlers::handler_batch_orders::process(ctx, cancel_order_ids, orders, expected_sequence)
+    }
+
+    /// Immediate-or-cancel trade settled straight to the signer's token accounts.
+    pub fn swap(ctx: Context<Swap>, args: SwapArgs) -> Result<OrderSummary> {
+        handlers::handler_swap::process(ctx, args)
+    }
+
+    /// Adds an account holding the trigger orders of `open_orders`.
+    pub fn init_trigger_orders(ctx: Context<InitTriggerOrders>) -> Result<()> {
+        handlers::handler_init_trigger_orders::process(ctx)
+    }
+
+    /// Locks the funds for an order that is placed once the oracle or last trade price
+    /// crosses `params.trigger_price`, and escrows the reward for executing it.
+    pub fn place_trigger_order(
+        ctx: Context<PlaceTriggerOrder>,
+        params: TriggerOrderParams,
+    ) -> Result<()> {
+        handlers::handler_place_trigger_order::process(ctx, params)
+    }
+
+    pub fn cancel_trigger_order(ctx: Context<CancelTriggerOrder>, trigger_id: u64) -> Result<()> {
+        handlers::handler_cancel_trigger_order::process(ctx, trigger_id)
+    }
+
+    /// Places the trigger orders that have fired and pays the executor their rewards.
+    pub fn execute_triggers(ctx: Context<ExecuteTriggers>) -> Result<()> {
+        handlers::handler_execute_triggers::process(ctx)
+    }
+
+    /// Funds a TWAP order up front, with the rewards for executing its slices.
+    pub fn place_twap_order(ctx: Context<PlaceTwapOrder>, params: TwapOrderParams) -> Result<()> {
+        handlers::handler_place_twap_order::process(ctx, params)
+    }
+
+    /// Releases the funds of the unexecuted slices and closes the TWAP order.
+    pub fn cancel_twap_order(ctx: Context<CancelTwapOrder>) -> Result<()> {
+        handlers::handler_cancel_twap_order::process(ctx)
+    }
+
+    /// Executes the next slice of a TWAP order once its interval has elapsed and pays the
+    /// executor its reward.
+    pub fn execute_twap_slice(ctx: Context<ExecuteTwapSlice>) -> Result<()> {
+        handlers::handler_execute_twap_slice::process(ctx)
+    }
+
+    pub fn consume_events<'info>(
+        ctx: Context<'_, '_, '_, 'info, ConsumeEvents<'info>>,
+        limit: u16,
+    ) -> Result<()> {
+        handlers::handler_consume_events::process(ctx, limit)
+    }
+
+    pub fn settle_funds(ctx: Context<SettleFunds>) -> Result<()> {
+        handlers::handler_settle_funds::process(ctx)
+    }
+
+    pub fn sweep_fees(ctx: Context<SweepFees>) -> Result<()> {
+        handlers::handler_sweep_fees::process(ctx)
     }
 }
@@ -0,0 +1,1405 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ClobError;
+use crate::events::{
+    CircuitBreakerTripped, OrderCancelled, OrderEvicted, OrderExpired, OrderFilled, OrderPlaced,
+};
+use crate::state::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct OrderParams {
+    pub side: Side,
+    /// Limit price in quote lots per base lot.
+    pub price: u64,
+    pub max_base_lots: u64,
+    pub order_type: OrderType,
+    pub client_order_id: u64,
+    /// Unix timestamp after which a resting order expires, 0 for good-till-cancelled.
+    pub expiry_timestamp: i64,
+}
+
+impl OrderParams {
+    pub fn validate(&self, market: &Market, now: i64) -> Result<()> {
+        require!(self.price > 0, ClobError::InvalidPrice);
+        require!(
+            self.price.is_multiple_of(market.tick_size_at(self.price)),
+            ClobError::InvalidTick
+        );
+        require!(self.max_base_lots > 0, ClobError::InvalidQuantity);
+        require!(
+            !market.is_below_minimum(self.price, self.max_base_lots),
+            ClobError::OrderTooSmall
+        );
+        require!(
+            self.expiry_timestamp == 0 || self.expiry_timestamp > now,
+            ClobError::InvalidExpiry
+        );
+        Ok(())
+    }
+}
+
+/// An order priced off the market's oracle, see [`PeggedOrder`].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PeggedOrderParams {
+    pub side: Side,
+    /// Quote lots per base lot added to the oracle price, a multiple of the tick size.
+    pub price_offset: i64,
+    /// Highest price a bid or lowest price an ask trades at, 0 for none on asks. Bids
+    /// need one, their quote is locked at it.
+    pub peg_limit: u64,
+    pub max_base_lots: u64,
+    pub order_type: OrderType,
+    pub client_order_id: u64,
+    /// Unix timestamp after which a resting order expires, 0 for good-till-cancelled.
+    pub expiry_timestamp: i64,
+}
+
+impl PeggedOrderParams {
+    pub fn validate(&self, market: &Market, now: i64) -> Result<()> {
+        require!(
+            self.side == Side::Ask || self.peg_limit > 0,
+            ClobError::InvalidPeg
+        );
+        // Offsets use the market's base tick size, pegged prices are rounded to their band's.
+        require!(
+            self.price_offset
+                .unsigned_abs()
+                .is_multiple_of(market.tick_size)
+                && self
+                    .peg_limit
+                    .is_multiple_of(market.tick_size_at(self.peg_limit)),
+            ClobError::InvalidTick
+        );
+        require!(self.max_base_lots > 0, ClobError::InvalidQuantity);
+        require!(
+            !market.is_below_minimum(self.peg_limit, self.max_base_lots),
+            ClobError::OrderTooSmall
+        );
+        require!(
+            self.expiry_timestamp == 0 || self.expiry_timestamp > now,
+            ClobError::InvalidExpiry
+        );
+        Ok(())
+    }
+
+    /// The equivalent fixed-price order at `price`.
+    pub fn to_order_params(&self, price: u64) -> OrderParams {
+        OrderParams {
+            side: self.side,
+            price,
+            max_base_lots: self.max_base_lots,
+            order_type: self.order_type,
+            client_order_id: self.client_order_id,
+            expiry_timestamp: self.expiry_timestamp,
+        }
+    }
+}
+
+/// A hidden order resting at the midpoint, see [`MidpointBook`].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MidpointOrderParams {
+    pub side: Side,
+    /// Highest mid a bid or lowest mid an ask trades at, 0 for none on asks. Bids need
+    /// one, their quote is locked at it.
+    pub limit_price: u64,
+    pub max_base_lots: u64,
+    pub client_order_id: u64,
+    /// Unix timestamp after which a resting order expires, 0 for good-till-cancelled.
+    pub expiry_timestamp: i64,
+}
+
+impl MidpointOrderParams {
+    pub fn validate(&self, market: &Market, now: i64) -> Result<()> {
+        require!(
+            self.side == Side::Ask || self.limit_price > 0,
+            ClobError::InvalidPeg
+        );
+        require!(
+            self.limit_price
+                .is_multiple_of(market.tick_size_at(self.limit_price)),
+            ClobError::InvalidTick
+        );
+        require!(self.max_base_lots > 0, ClobError::InvalidQuantity);
+        require!(
+            !market.is_below_minimum(self.limit_price, self.max_base_lots),
+            ClobError::OrderTooSmall
+        );
+        require!(
+            self.expiry_timestamp == 0 || self.expiry_timestamp > now,
+            ClobError::InvalidExpiry
+        );
+        Ok(())
+    }
+
+    /// The equivalent limit order, priced at the midpoint limit.
+    pub fn to_order_params(&self) -> OrderParams {
+        OrderParams {
+            side: self.side,
+            price: self.limit_price,
+            max_base_lots: self.max_base_lots,
+            order_type: OrderType::Limit,
+            client_order_id: self.client_order_id,
+            expiry_timestamp: self.expiry_timestamp,
+        }
+    }
+}
+
+/// A limit order that shows part of its size on the book and keeps the rest hidden, see
+/// [`IcebergReserve`].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct IcebergOrderParams {
+    pub side: Side,
+    /// Limit price in quote lots per base lot.
+    pub price: u64,
+    pub max_base_lots: u64,
+    /// Base lots shown at a time, the next part is shown with a new time priority once
+    /// they fill.
+    pub display_base_lots: u64,
+    /// `Limit` or `PostOnly`.
+    pub order_type: OrderType,
+    pub client_order_id: u64,
+    /// Unix timestamp after which a resting order expires, 0 for good-till-cancelled.
+    pub expiry_timestamp: i64,
+}
+
+impl IcebergOrderParams {
+    pub fn validate(&self, market: &Market, now: i64) -> Result<()> {
+        self.to_order_params().validate(market, now)?;
+        require!(
+            self.order_type != OrderType::ImmediateOrCancel
+                && self.display_base_lots > 0
+                && self.display_base_lots < self.max_base_lots,
+            ClobError::InvalidIceberg
+        );
+        Ok(())
+    }
+
+    pub fn to_order_params(&self) -> OrderParams {
+        OrderParams {
+            side: self.side,
+            price: self.price,
+            max_base_lots: self.max_base_lots,
+            order_type: self.order_type,
+            client_order_id: self.client_order_id,
+            expiry_timestamp: self.expiry_timestamp,
+        }
+    }
+}
+
+/// A parent order sliced over time, see [`TwapOrder`].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TwapOrderParams {
+    pub side: Side,
+    /// Limit price of every slice, in quote lots per base lot.
+    pub price: u64,
+    pub total_base_lots: u64,
+    pub slice_base_lots: u64,
+    pub interval_seconds: u32,
+    pub client_order_id: u64,
+}
+
+impl TwapOrderParams {
+    pub fn validate(&self, market: &Market, now: i64) -> Result<()> {
+        OrderParams {
+            side: self.side,
+            price: self.price,
+            max_base_lots: self.total_base_lots,
+            order_type: OrderType::ImmediateOrCancel,
+            client_order_id: self.client_order_id,
+            expiry_timestamp: 0,
+        }
+        .validate(market, now)?;
+        require!(
+            self.slice_base_lots > 0
+                && self.slice_base_lots <= self.total_base_lots
+                && self.interval_seconds > 0,
+            ClobError::InvalidTwap
+        );
+        Ok(())
+    }
+}
+
+impl TwapOrder {
+    /// The immediate-or-cancel order for the next slice.
+    pub fn slice_params(&self) -> OrderParams {
+        OrderParams {
+            side: self.side,
+            price: self.price,
+            max_base_lots: self.slice_base_lots.min(self.remaining_base_lots),
+            order_type: OrderType::ImmediateOrCancel,
+            client_order_id: self.client_order_id,
+            expiry_timestamp: 0,
+        }
+    }
+}
+
+/// An order held off the book until a price crosses `trigger_price`, see [`TriggerOrder`].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TriggerOrderParams {
+    pub side: Side,
+    /// `ImmediateOrCancel` for a market order bounded by `price`, or `Limit`.
+    pub order_type: OrderType,
+    /// Limit price of the order placed when the trigger fires.
+    pub price: u64,
+    pub max_base_lots: u64,
+    pub client_order_id: u64,
+    pub source: TriggerSource,
+    pub condition: TriggerCondition,
+    /// Quote lots per base lot.
+    pub trigger_price: u64,
+}
+
+impl TriggerOrderParams {
+    pub fn validate(&self, market: &Market, now: i64) -> Result<()> {
+        self.to_order_params().validate(market, now)?;
+        require!(self.trigger_price > 0, ClobError::InvalidPrice);
+        require!(
+            self.source == TriggerSource::LastTrade || market.oracle_kind != OracleKind::None,
+            ClobError::InvalidOracle
+        );
+        Ok(())
+    }
+
+    /// The order placed when the trigger fires.
+    pub fn to_order_params(&self) -> OrderParams {
+        OrderParams {
+            side: self.side,
+            price: self.price,
+            max_base_lots: self.max_base_lots,
+            order_type: self.order_type,
+            client_order_id: self.client_order_id,
+            expiry_timestamp: 0,
+        }
+    }
+
+    /// Native units to lock for the order, see [`Market::order_funds`].
+    pub fn required_funds(&self, market: &Market) -> Result<u64> {
+        market.order_funds(self.side, self.price, self.max_base_lots)
+    }
+}
+
+impl TriggerOrder {
+    pub fn to_order_params(&self) -> OrderParams {
+        OrderParams {
+            side: self.side,
+            price: self.price,
+            max_base_lots: self.max_base_lots,
+            order_type: self.order_type,
+            client_order_id: self.client_order_id,
+            expiry_timestamp: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OrderOutcome {
+    pub order_id: u64,
+    pub base_lots_filled: u64,
+    /// Sum of `price * quantity` over all fills.
+    pub quote_lots_filled: u64,
+    /// Taker fee charged on the fills, in native quote units.
+    pub taker_fee: u64,
+    /// Base lots left resting on the book.
+    pub posted_base_lots: u64,
+}
+
+/// Summary of an order, set as return data by `place_order`, `swap` and `cancel_order`
+/// so that CPI callers do not have to diff token balances.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct OrderSummary {
+    pub order_id: u64,
+    /// Native base units filled.
+    pub base_filled: u64,
+    /// Native quote units filled, excluding fees.
+    pub quote_filled: u64,
+    /// Taker fee paid, in native quote units.
+    pub fee_paid: u64,
+    /// Whether part of the order was left resting on the book.
+    pub rested: bool,
+    /// Native base units still open when `cancel_order` removed the order.
+    pub base_cancelled: u64,
+}
+
+impl OrderOutcome {
+    pub fn summary(&self, market: &Market) -> Result<OrderSummary> {
+        Ok(OrderSummary {
+            order_id: self.order_id,
+            base_filled: market.base_lots_to_native(self.base_lots_filled)?,
+            quote_filled: market.quote_lots_to_native(self.quote_lots_filled)?,
+            fee_paid: self.taker_fee,
+            rested: self.posted_base_lots > 0,
+            base_cancelled: 0,
+        })
+    }
+}
+
+pub struct Orderbook<'a> {
+    pub bids: &'a mut BookSide,
+    pub asks: &'a mut BookSide,
+    /// Oracle price in quote lots per base lot, pegged orders do not match without it.
+    pub oracle_price: Option<u64>,
+    /// The market's midpoint book, if it has one.
+    pub midpoint: Option<&'a mut MidpointBook>,
+}
+
+/// Where the unfilled part of an order rests.
+#[derive(Clone, Copy)]
+enum Resting<'p> {
+    Fixed,
+    Pegged(&'p PeggedOrderParams),
+    Midpoint,
+    /// Shows this many base lots at a time, the rest is kept in the midpoint book.
+    Iceberg(u64),
+}
+
+/// The incoming order, as recorded in the events it generates.
+struct Taker {
+    market: Pubkey,
+    key: Pubkey,
+    order_id: u64,
+    side: Side,
+    now: i64,
+    /// Set when uncrossing, where the bid and the ask each fill the other as its maker.
+    auction: bool,
+}
+
+impl Taker {
+    /// Records a fill at `price` on `market`, returns whether it tripped the circuit
+    /// breaker.
+    fn record_trade(&self, market: &mut Market, price: u64) -> bool {
+        let Some(reference_price) = market.record_trade(price, self.now) else {
+            return false;
+        };
+        emit!(CircuitBreakerTripped {
+            market: self.market,
+            reference_price,
+            price,
+            halted_until: market.halted_until,
+        });
+        true
+    }
+
+    /// Fills `quantity` of `maker` at `price`, returns whether the maker's displayed
+    /// quantity is filled in full. `refills` is set for an iceberg maker with more in
+    /// reserve, which stays open.
+    fn fill(
+        &self,
+        event_queue: &mut EventQueue,
+        maker: &mut Order,
+        price: u64,
+        quantity: u64,
+        refills: bool,
+        outcome: &mut OrderOutcome,
+    ) -> Result<bool> {
+        maker.quantity -= quantity;
+        let maker_out = maker.quantity == 0 && !refills;
+        event_queue.push(Event::Fill(FillEvent {
+            maker: maker.owner,
+            maker_order_id: maker.order_id,
+            maker_client_order_id: maker.client_order_id,
+            maker_side: self.side.invert(),
+            maker_out,
+            taker: self.key,
+            taker_order_id: self.order_id,
+            price,
+            quantity,
+            timestamp: self.now,
+        }))?;
+        // An auction trade is reported once, from the fill of the bid.
+        if !self.auction || self.side == Side::Ask {
+            emit!(OrderFilled {
+                market: self.market,
+                maker: maker.owner,
+                maker_order_id: maker.order_id,
+                maker_client_order_id: maker.client_order_id,
+                maker_out,
+                taker: self.key,
+                taker_order_id: self.order_id,
+                taker_side: self.side,
+                price,
+                quantity,
+                auction: self.auction,
+            });
+        }
+
+        outcome.base_lots_filled += quantity;
+        outcome.quote_lots_filled = price
+            .checked_mul(quantity)
+            .and_then(|v| v.checked_add(outcome.quote_lots_filled))
+            .ok_or_else(|| error!(ClobError::MathOverflow))?;
+        Ok(maker.quantity == 0)
+    }
+
+    /// Releases a maker order found expired while matching.
+    fn expire(&self, event_queue: &mut EventQueue, expired: &Order) -> Result<()> {
+        emit!(OrderExpired {
+            market: self.market,
+            owner: expired.owner,
+            order_id: expired.order_id,
+            client_order_id: expired.client_order_id,
+            side: self.side.invert(),
+            price: expired.price,
+            quantity: expired.quantity,
+        });
+        event_queue.push(Event::Out(OutEvent {
+            owner: expired.owner,
+            order_id: expired.order_id,
+            client_order_id: expired.client_order_id,
+            side: self.side.invert(),
+            price: expired.price,
+            quantity: expired.quantity,
+            timestamp: self.now,
+            reason: OutReason::Expired,
+        }))
+    }
+
+    /// Releases an order evicted from the taker's own side to make room for the taker.
+    fn evict(&self, event_queue: &mut EventQueue, evicted: &Order) -> Result<()> {
+        emit!(OrderEvicted {
+            market: self.market,
+            owner: evicted.owner,
+            order_id: evicted.order_id,
+            client_order_id: evicted.client_order_id,
+            side: self.side,
+            price: evicted.price,
+            quantity: evicted.quantity,
+            evicted_by: self.order_id,
+        });
+        event_queue.push(Event::Out(OutEvent {
+            owner: evicted.owner,
+            order_id: evicted.order_id,
+            client_order_id: evicted.client_order_id,
+            side: self.side,
+            price: evicted.price,
+            quantity: evicted.quantity,
+            timestamp: self.now,
+            reason: OutReason::Evicted,
+        }))
+    }
+
+    /// Releases a maker `order` removed from `side` without filling, through the event
+    /// queue.
+    fn cancel_maker(&self, event_queue: &mut EventQueue, side: Side, order: &Order) -> Result<()> {
+        emit!(OrderCancelled {
+            market: self.market,
+            owner: order.owner,
+            order_id: order.order_id,
+            client_order_id: order.client_order_id,
+            side,
+            price: order.price,
+            quantity: order.quantity,
+        });
+        event_queue.push(Event::Out(OutEvent {
+            owner: order.owner,
+            order_id: order.order_id,
+            client_order_id: order.client_order_id,
+            side,
+            price: order.price,
+            quantity: order.quantity,
+            timestamp: self.now,
+            reason: OutReason::Cancelled,
+        }))
+    }
+}
+
+impl<'a> Orderbook<'a> {
+    pub fn side_mut(&mut self, side: Side) -> &mut BookSide {
+        match side {
+            Side::Bid => self.bids,
+            Side::Ask => self.asks,
+        }
+    }
+
+    /// Whether `order_id` is still resting on `side`, lit or in the midpoint book.
+    pub fn contains(&self, side: Side, order_id: u64) -> bool {
+        let book = match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        };
+        book.find(order_id).is_some()
+            || self
+                .midpoint
+                .as_ref()
+                .is_some_and(|m| m.find(side, order_id).is_some())
+    }
+
+    /// Midpoint of the best live bid and offer, rounded in favour of the resting side
+    /// of a taker on `side`. `None` unless both sides are quoted.
+    pub fn midpoint_price(&self, side: Side, tick_sizes: &TickSizes, now: i64) -> Option<u64> {
+        let bid = self
+            .bids
+            .best_live_price(self.oracle_price, tick_sizes, now)?;
+        let ask = self
+            .asks
+            .best_live_price(self.oracle_price, tick_sizes, now)?;
+        let sum = bid as u128 + ask as u128;
+        let mid = match side {
+            Side::Bid => sum.div_ceil(2),
+            Side::Ask => sum / 2,
+        };
+        u64::try_from(mid).ok()
+    }
+
+    /// Matches `params` against the opposite side of the book and, unless the order is
+    /// immediate-or-cancel, rests the remainder on behalf of `open_orders`. Hidden
+    /// midpoint orders go first, at the mid, unless the order is post-only.
+    ///
+    /// Maker settlement is deferred to the event queue. Taker balances are left to the
+    /// caller, which knows whether the taker trades through an `OpenOrders` account or a swap.
+    pub fn new_order(
+        &mut self,
+        market: &mut Market,
+        event_queue: &mut EventQueue,
+        taker: Pubkey,
+        open_orders: Option<&mut OpenOrders>,
+        params: &OrderParams,
+        now: i64,
+    ) -> Result<OrderOutcome> {
+        self.process_order(
+            market,
+            event_queue,
+            taker,
+            open_orders,
+            params,
+            Resting::Fixed,
+            now,
+        )
+    }
+
+    /// Like [`Orderbook::new_order`] at the pegged order's current `price`, resting the
+    /// remainder as a pegged order.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_pegged_order(
+        &mut self,
+        market: &mut Market,
+        event_queue: &mut EventQueue,
+        taker: Pubkey,
+        open_orders: &mut OpenOrders,
+        params: &PeggedOrderParams,
+        price: u64,
+        now: i64,
+    ) -> Result<OrderOutcome> {
+        self.process_order(
+            market,
+            event_queue,
+            taker,
+            Some(open_orders),
+            &params.to_order_params(price),
+            Resting::Pegged(params),
+            now,
+        )
+    }
+
+    /// Matches a midpoint order against the opposite midpoint orders at the current mid,
+    /// then rests the remainder in the midpoint book. It never trades with lit orders.
+    pub fn new_midpoint_order(
+        &mut self,
+        market: &mut Market,
+        event_queue: &mut EventQueue,
+        taker: Pubkey,
+        open_orders: &mut OpenOrders,
+        params: &MidpointOrderParams,
+        now: i64,
+    ) -> Result<OrderOutcome> {
+        self.process_order(
+            market,
+            event_queue,
+            taker,
+            Some(open_orders),
+            &params.to_order_params(),
+            Resting::Midpoint,
+            now,
+        )
+    }
+
+    /// Like [`Orderbook::new_order`], resting the remainder as an iceberg that shows
+    /// `params.display_base_lots` at a time.
+    pub fn new_iceberg_order(
+        &mut self,
+        market: &mut Market,
+        event_queue: &mut EventQueue,
+        taker: Pubkey,
+        open_orders: &mut OpenOrders,
+        params: &IcebergOrderParams,
+        now: i64,
+    ) -> Result<OrderOutcome> {
+        self.process_order(
+            market,
+            event_queue,
+            taker,
+            Some(open_orders),
+            &params.to_order_params(),
+            Resting::Iceberg(params.display_base_lots),
+            now,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn process_order(
+        &mut self,
+        market: &mut Market,
+        event_queue: &mut EventQueue,
+        taker: Pubkey,
+        mut open_orders: Option<&mut OpenOrders>,
+        params: &OrderParams,
+        resting: Resting,
+        now: i64,
+    ) -> Result<OrderOutcome> {
+        let side = params.side;
+        let order_id = market.next_order_id();
+        let taker = Taker {
+            market: self.bids.market,
+            key: taker,
+            order_id,
+            side,
+            now,
+            auction: false,
+        };
+        let mut outcome = OrderOutcome {
+            order_id,
+            ..OrderOutcome::default()
+        };
+        let mut remaining = params.max_base_lots;
+        // Set once a fill trips the circuit breaker, the rest of the order is cancelled.
+        let mut halted = false;
+        // Orders placed during an auction or in batch mode rest without matching.
+        let auction = market.collects_orders();
+        require!(
+            !auction
+                || (params.order_type != OrderType::ImmediateOrCancel
+                    && matches!(resting, Resting::Fixed)),
+            ClobError::InvalidAuctionOrder
+        );
+
+        let oracle_price = self.oracle_price;
+        let tick_sizes = market.tick_sizes();
+        if params.order_type != OrderType::PostOnly && !auction {
+            let mid = self
+                .midpoint_price(side, &tick_sizes, now)
+                .filter(|&mid| midpoint_allows(side, params.price, mid));
+            if let (Some(mid), Some(midpoint)) = (mid, self.midpoint.as_deref_mut()) {
+                let makers = midpoint.side_mut(side.invert());
+                let mut index = 0;
+                while remaining > 0 && index < makers.len() {
+                    let maker = &mut makers[index];
+                    if maker.is_expired(now) {
+                        taker.expire(event_queue, &makers.remove(index))?;
+                        continue;
+                    }
+                    if !midpoint_allows(side.invert(), maker.price, mid) {
+                        index += 1;
+                        continue;
+                    }
+                    let quantity = remaining.min(maker.quantity);
+                    remaining -= quantity;
+                    halted = taker.record_trade(market, mid);
+                    if taker.fill(event_queue, maker, mid, quantity, false, &mut outcome)? {
+                        makers.remove(index);
+                    } else if market.is_below_minimum(mid, maker.quantity) {
+                        taker.cancel_maker(event_queue, side.invert(), &makers.remove(index))?;
+                    }
+                    if halted {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if params.order_type == OrderType::PostOnly && !auction {
+            let crosses = self
+                .side_mut(side.invert())
+                .best_live_price(oracle_price, &tick_sizes, now)
+                .is_some_and(|price| side.crosses(params.price, price));
+            require!(!crosses, ClobError::WouldCrossPostOnly);
+        }
+
+        while remaining > 0 && !halted && !auction && !matches!(resting, Resting::Midpoint) {
+            let opposite = self.side_mut(side.invert());
+            let (at, price) = match opposite.next_maker(oracle_price, &tick_sizes) {
+                Some(next) => next,
+                None => break,
+            };
+
+            if opposite.order(at).is_expired(now) {
+                let mut expired = opposite.remove_at(at);
+                expired.quantity += self.take_reserve(expired.order_id);
+                taker.expire(event_queue, &expired)?;
+                continue;
+            }
+
+            if !side.crosses(params.price, price) {
+                break;
+            }
+
+            if market.matching_mode == MatchingMode::ProRata && matches!(at, BookOrder::Fixed(_)) {
+                let filled;
+                (filled, halted) = self.fill_pro_rata(
+                    market,
+                    event_queue,
+                    &taker,
+                    price,
+                    remaining,
+                    &mut outcome,
+                )?;
+                remaining -= filled;
+                continue;
+            }
+            let quantity = remaining.min(opposite.order(at).quantity);
+            remaining -= quantity;
+            halted = taker.record_trade(market, price);
+            self.fill_maker(
+                market,
+                event_queue,
+                &taker,
+                at,
+                price,
+                quantity,
+                &mut outcome,
+            )?;
+        }
+
+        let quote_native = market.quote_lots_to_native(outcome.quote_lots_filled)?;
+        outcome.taker_fee = market.taker_fee(quote_native)?;
+        market.fees_accrued = market
+            .fees_accrued
+            .checked_add(outcome.taker_fee)
+            .ok_or_else(|| error!(ClobError::MathOverflow))?;
+
+        // A remainder below the market's minimum is cancelled rather than rested.
+        if remaining > 0
+            && !halted
+            && params.order_type != OrderType::ImmediateOrCancel
+            && !market.is_below_minimum(params.price, remaining)
+        {
+            let open_orders = open_orders
+                .as_mut()
+                .ok_or_else(|| error!(ClobError::OpenOrdersRequired))?;
+            let order = Order {
+                order_id,
+                owner: taker.key,
+                client_order_id: params.client_order_id,
+                price: params.price,
+                quantity: remaining,
+                timestamp: now,
+                expiry_timestamp: params.expiry_timestamp,
+                group_id: 0,
+            };
+            self.make_room(market, event_queue, &taker, resting, params.price)?;
+            let locked_price = match resting {
+                Resting::Fixed => {
+                    self.side_mut(side).insert(order)?;
+                    params.price
+                }
+                Resting::Pegged(peg) => {
+                    self.side_mut(side).insert_pegged(PeggedOrder {
+                        order: Order {
+                            price: peg.peg_limit,
+                            ..order
+                        },
+                        price_offset: peg.price_offset,
+                    })?;
+                    peg.peg_limit
+                }
+                Resting::Midpoint => {
+                    self.midpoint
+                        .as_deref_mut()
+                        .ok_or_else(|| error!(ClobError::MidpointBookRequired))?
+                        .insert(side, order)?;
+                    params.price
+                }
+                Resting::Iceberg(display_base_lots) => {
+                    let shown = display_base_lots.min(remaining);
+                    if shown < remaining {
+                        self.midpoint
+                            .as_deref_mut()
+                            .ok_or_else(|| error!(ClobError::MidpointBookRequired))?
+                            .insert_reserve(IcebergReserve {
+                                order_id,
+                                display_base_lots,
+                                reserve_base_lots: remaining - shown,
+                            })?;
+                    }
+                    self.side_mut(side).insert(Order {
+                        quantity: shown,
+                        ..order
+                    })?;
+                    params.price
+                }
+            };
+            open_orders.add_order(OpenOrder {
+                order_id,
+                client_order_id: params.client_order_id,
+                side,
+                price: locked_price,
+                group_id: 0,
+                bond: 0,
+            })?;
+            outcome.posted_base_lots = remaining;
+        }
+
+        emit!(OrderPlaced {
+            market: taker.market,
+            owner: taker.key,
+            order_id,
+            client_order_id: params.client_order_id,
+            side,
+            order_type: params.order_type,
+            price: params.price,
+            max_base_lots: params.max_base_lots,
+            base_lots_filled: outcome.base_lots_filled,
+            quote_lots_filled: outcome.quote_lots_filled,
+            taker_fee: outcome.taker_fee,
+            posted_base_lots: outcome.posted_base_lots,
+        });
+        Ok(outcome)
+    }
+
+    /// Fills `quantity` of the maker at `at`, on the side opposite `taker`, at `price`.
+    /// Refills a filled iceberg, cancels a remainder below the market's minimum and the
+    /// rest of the maker's group.
+    #[allow(clippy::too_many_arguments)]
+    fn fill_maker(
+        &mut self,
+        market: &Market,
+        event_queue: &mut EventQueue,
+        taker: &Taker,
+        at: BookOrder,
+        price: u64,
+        quantity: u64,
+        outcome: &mut OrderOutcome,
+    ) -> Result<()> {
+        let (maker, group_id) =
+            self.fill_maker_order(market, event_queue, taker, at, price, quantity, outcome)?;
+        if group_id != 0 {
+            self.cancel_group(event_queue, taker, maker, group_id)?;
+        }
+        Ok(())
+    }
+
+    /// [`Orderbook::fill_maker`] without cancelling the maker's group. Returns the maker
+    /// and the group it was taken out of, 0 if none.
+    #[allow(clippy::too_many_arguments)]
+    fn fill_maker_order(
+        &mut self,
+        market: &Market,
+        event_queue: &mut EventQueue,
+        taker: &Taker,
+        at: BookOrder,
+        price: u64,
+        quantity: u64,
+        outcome: &mut OrderOutcome,
+    ) -> Result<(Pubkey, u64)> {
+        let side = taker.side.invert();
+        let order_id = self.side_mut(side).order(at).order_id;
+        let reserve = self.midpoint.as_ref().map_or(0, |m| m.reserve(order_id));
+        let refills = quantity == self.side_mut(side).order(at).quantity && reserve > 0;
+
+        let opposite = self.side_mut(side);
+        let best = opposite.order_mut(at);
+        // A partly filled member stays on the book, out of its group.
+        let (maker, group_id) = (best.owner, std::mem::take(&mut best.group_id));
+        if taker.fill(event_queue, best, price, quantity, refills, outcome)? {
+            let filled = opposite.remove_at(at);
+            if refills {
+                self.refill(side, filled, taker.now)?;
+            }
+        } else if market.is_below_minimum(price, best.quantity + reserve) {
+            let mut dust = opposite.remove_at(at);
+            dust.quantity += self.take_reserve(order_id);
+            taker.cancel_maker(event_queue, side, &dust)?;
+        }
+        Ok((maker, group_id))
+    }
+
+    /// Fills up to `quantity` from the live fixed-price orders at `price`, the best on the
+    /// side opposite `taker`, split by [`pro_rata_allocation`]. Returns the base lots
+    /// filled and whether a fill tripped the circuit breaker.
+    fn fill_pro_rata(
+        &mut self,
+        market: &mut Market,
+        event_queue: &mut EventQueue,
+        taker: &Taker,
+        price: u64,
+        quantity: u64,
+        outcome: &mut OrderOutcome,
+    ) -> Result<(u64, bool)> {
+        let side = taker.side.invert();
+        let (order_ids, sizes): (Vec<u64>, Vec<u64>) = self
+            .side_mut(side)
+            .orders
+            .iter()
+            .take_while(|o| o.price == price)
+            .filter(|o| !o.is_expired(taker.now))
+            .map(|o| (o.order_id, o.quantity))
+            .unzip();
+        let allocation = pro_rata_allocation(&sizes, quantity, market.pro_rata_top_share_bps);
+
+        let mut filled = 0;
+        for (order_id, allocated) in order_ids.into_iter().zip(allocation) {
+            // Filling an order may cancel others of its group at this price.
+            let Some(at) = self.side_mut(side).find(order_id) else {
+                continue;
+            };
+            if allocated == 0 {
+                continue;
+            }
+            filled += allocated;
+            let halted = taker.record_trade(market, price);
+            self.fill_maker(market, event_queue, taker, at, price, allocated, outcome)?;
+            if halted {
+                return Ok((filled, true));
+            }
+        }
+        Ok((filled, false))
+    }
+
+    /// Ends an auction by filling the crossing fixed-price orders at a single price, see
+    /// [`clearing_price`], best price and then oldest first. Both sides settle as makers,
+    /// through the event queue and without a taker fee. Returns the price and the base
+    /// lots traded, the price being 0 when nothing crossed.
+    pub fn uncross(
+        &mut self,
+        market: &mut Market,
+        event_queue: &mut EventQueue,
+        now: i64,
+    ) -> Result<(u64, u64)> {
+        let live = |book: &BookSide| -> Vec<(u64, u64)> {
+            book.orders
+                .iter()
+                .filter(|o| !o.is_expired(now))
+                .map(|o| (o.price, o.quantity))
+                .collect()
+        };
+        let Some((price, volume)) =
+            clearing_price(&live(self.bids), &live(self.asks), market.last_trade_price)
+        else {
+            return Ok((0, 0));
+        };
+
+        let market_key = self.bids.market;
+        let mut outcome = OrderOutcome::default();
+        let mut remaining = volume;
+        while remaining > 0 {
+            if self.expire_best(event_queue, market_key, Side::Bid, now)?
+                || self.expire_best(event_queue, market_key, Side::Ask, now)?
+            {
+                continue;
+            }
+            let (Some(bid), Some(ask)) = (self.bids.best(), self.asks.best()) else {
+                break;
+            };
+            if bid.price < price || ask.price > price {
+                break;
+            }
+            let quantity = remaining.min(bid.quantity).min(ask.quantity);
+            remaining -= quantity;
+            // Each order is the other's taker.
+            let against_ask = Taker {
+                market: market_key,
+                key: ask.owner,
+                order_id: ask.order_id,
+                side: Side::Ask,
+                now,
+                auction: true,
+            };
+            let against_bid = Taker {
+                market: market_key,
+                key: bid.owner,
+                order_id: bid.order_id,
+                side: Side::Bid,
+                now,
+                auction: true,
+            };
+            // Both fills go through before either group is cancelled, the bid and ask
+            // may be linked orders of one owner.
+            let at = BookOrder::Fixed(0);
+            let bid_group = self.fill_maker_order(
+                market,
+                event_queue,
+                &against_ask,
+                at,
+                price,
+                quantity,
+                &mut outcome,
+            )?;
+            let ask_group = self.fill_maker_order(
+                market,
+                event_queue,
+                &against_bid,
+                at,
+                price,
+                quantity,
+                &mut outcome,
+            )?;
+            for (taker, (maker, group_id)) in [(&against_ask, bid_group), (&against_bid, ask_group)]
+            {
+                if group_id != 0 {
+                    self.cancel_group(event_queue, taker, maker, group_id)?;
+                }
+            }
+        }
+
+        // Continuous trading starts a fresh circuit breaker window at the auction price.
+        market.last_trade_price = price;
+        market.reference_price = 0;
+        Ok((price, volume - remaining))
+    }
+
+    /// Removes the best fixed-price order on `side` if it has expired, returns whether
+    /// one was.
+    fn expire_best(
+        &mut self,
+        event_queue: &mut EventQueue,
+        market: Pubkey,
+        side: Side,
+        now: i64,
+    ) -> Result<bool> {
+        let book = self.side_mut(side);
+        if !book.best().is_some_and(|o| o.is_expired(now)) {
+            return Ok(false);
+        }
+        let mut expired = book.remove_at(BookOrder::Fixed(0));
+        expired.quantity += self.take_reserve(expired.order_id);
+        let taker = Taker {
+            market,
+            key: Pubkey::default(),
+            order_id: 0,
+            side: side.invert(),
+            now,
+            auction: false,
+        };
+        taker.expire(event_queue, &expired)?;
+        Ok(true)
+    }
+
+    /// Evicts the worst priced order where the taker is to rest as `resting` if that is
+    /// full, fails unless the taker's `price` is strictly better than it. Pegged orders are
+    /// compared at the oracle price, midpoint orders by their limits.
+    fn make_room(
+        &mut self,
+        market: &Market,
+        event_queue: &mut EventQueue,
+        taker: &Taker,
+        resting: Resting,
+        price: u64,
+    ) -> Result<()> {
+        let side = taker.side;
+        let oracle_price = self.oracle_price;
+        let mut evicted = match resting {
+            Resting::Fixed | Resting::Iceberg(_) => {
+                let book = self.side_mut(side);
+                if !book.is_full() {
+                    return Ok(());
+                }
+                let worst = BookOrder::Fixed(book.orders.len() - 1);
+                require!(
+                    book.is_better(price, book.order(worst).price),
+                    ClobError::BookFull
+                );
+                book.remove_at(worst)
+            }
+            Resting::Pegged(peg) => {
+                let book = self.side_mut(side);
+                if !book.is_pegged_full() {
+                    return Ok(());
+                }
+                let oracle_price = oracle_price.ok_or_else(|| error!(ClobError::InvalidPeg))?;
+                let tick_sizes = market.tick_sizes();
+                let (worst, worst_price) = book
+                    .worst_pegged(oracle_price, &tick_sizes)
+                    .ok_or_else(|| error!(ClobError::BookFull))?;
+                let price = peg_price(
+                    side,
+                    oracle_price,
+                    peg.price_offset,
+                    peg.peg_limit,
+                    &tick_sizes,
+                );
+                require!(
+                    book.is_better_pegged(price, worst_price),
+                    ClobError::BookFull
+                );
+                book.remove_at(worst)
+            }
+            Resting::Midpoint => {
+                let midpoint = self
+                    .midpoint
+                    .as_deref_mut()
+                    .ok_or_else(|| error!(ClobError::MidpointBookRequired))?;
+                if !midpoint.is_full(side) {
+                    return Ok(());
+                }
+                let worst = midpoint
+                    .worst(side)
+                    .ok_or_else(|| error!(ClobError::BookFull))?;
+                let orders = midpoint.side_mut(side);
+                require!(
+                    limit_is_better(side, price, orders[worst].price),
+                    ClobError::BookFull
+                );
+                orders.remove(worst)
+            }
+        };
+        evicted.quantity += self.take_reserve(evicted.order_id);
+        taker.evict(event_queue, &evicted)
+    }
+
+    /// Removes the hidden reserve of an iceberg order and returns its base lots.
+    fn take_reserve(&mut self, order_id: u64) -> u64 {
+        self.midpoint
+            .as_deref_mut()
+            .map_or(0, |m| m.take_reserve(order_id))
+    }
+
+    /// Shows the next part of a filled iceberg `order` on `side`, at the back of its price.
+    fn refill(&mut self, side: Side, order: Order, now: i64) -> Result<()> {
+        let quantity = self
+            .midpoint
+            .as_deref_mut()
+            .ok_or_else(|| error!(ClobError::MidpointBookRequired))?
+            .refill(order.order_id);
+        self.side_mut(side).insert(Order {
+            quantity,
+            timestamp: now,
+            ..order
+        })
+    }
+
+    /// Removes the resting orders of `maker` in `group_id` after a member filled. Their
+    /// funds are released through the event queue.
+    fn cancel_group(
+        &mut self,
+        event_queue: &mut EventQueue,
+        taker: &Taker,
+        maker: Pubkey,
+        group_id: u64,
+    ) -> Result<()> {
+        for side in [Side::Bid, Side::Ask] {
+            while let Some(at) = self.side_mut(side).find_group(maker, group_id) {
+                let mut order = self.side_mut(side).remove_at(at);
+                order.quantity += self.take_reserve(order.order_id);
+                taker.cancel_maker(event_queue, side, &order)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Cancels the orders of `open_orders` still resting on the book.
+    pub fn cancel_all_orders(
+        &mut self,
+        market: &Market,
+        open_orders: &mut OpenOrders,
+        open_orders_key: Pubkey,
+    ) -> Result<()> {
+        let resting: Vec<(u64, Side)> = open_orders
+            .orders
+            .iter()
+            .map(|o| (o.order_id, o.side))
+            .collect();
+        for (order_id, side) in resting {
+            // Fully filled orders stay on the account until their fill event is consumed.
+            if self.contains(side, order_id) {
+                self.cancel_order(market, open_orders, open_orders_key, order_id)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Cancels the orders of `open_orders` in `group_id` still resting on the book.
+    pub fn cancel_group_orders(
+        &mut self,
+        market: &Market,
+        open_orders: &mut OpenOrders,
+        open_orders_key: Pubkey,
+        group_id: u64,
+    ) -> Result<()> {
+        let members: Vec<(Side, u64)> = open_orders
+            .orders
+            .iter()
+            .filter(|o| o.group_id == group_id)
+            .map(|o| (o.side, o.order_id))
+            .collect();
+        for (side, order_id) in members {
+            if self.contains(side, order_id) {
+                self.cancel_order(market, open_orders, open_orders_key, order_id)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes a resting order, lit or in the midpoint book, and releases its remaining
+    /// funds on `open_orders`.
+    pub fn cancel_order(
+        &mut self,
+        market: &Market,
+        open_orders: &mut OpenOrders,
+        open_orders_key: Pubkey,
+        order_id: u64,
+    ) -> Result<Order> {
+        let side = open_orders
+            .find_order(order_id)
+            .map(|o| o.side)
+            .ok_or_else(|| error!(ClobError::OrderNotFound))?;
+        let book = self.side_mut(side);
+        let order = match book.find(order_id) {
+            Some(at) => {
+                require_keys_eq!(
+                    book.order(at).owner,
+                    open_orders_key,
+                    ClobError::OrderNotFound
+                );
+                let mut order = book.remove_at(at);
+                order.quantity += self.take_reserve(order_id);
+                order
+            }
+            None => {
+                let midpoint = self
+                    .midpoint
+                    .as_deref_mut()
+                    .ok_or_else(|| error!(ClobError::OrderNotFound))?;
+                let index = midpoint
+                    .find(side, order_id)
+                    .filter(|&i| midpoint.side(side)[i].owner == open_orders_key)
+                    .ok_or_else(|| error!(ClobError::OrderNotFound))?;
+                midpoint.side_mut(side).remove(index)
+            }
+        };
+
+        open_orders.unlock(market, side, order.price, order.quantity)?;
+        open_orders.remove_order(order_id);
+        emit!(OrderCancelled {
+            market: open_orders.market,
+            owner: open_orders_key,
+            order_id,
+            client_order_id: order.client_order_id,
+            side,
+            price: order.price,
+            quantity: order.quantity,
+        });
+        Ok(order)
+    }
+}
+
+/// Splits `quantity` between orders of `sizes`, oldest first: the first order gets
+/// `top_share_bps` of `quantity`, the rest is split in proportion to what each order
+/// still has open, and lots lost to rounding down go in queue order.
+fn pro_rata_allocation(sizes: &[u64], quantity: u64, top_share_bps: u16) -> Vec<u64> {
+    let total: u64 = sizes.iter().sum();
+    if quantity >= total {
+        return sizes.to_vec();
+    }
+    let mut allocation = vec![0; sizes.len()];
+    allocation[0] = sizes[0].min((quantity as u128 * top_share_bps as u128 / 10_000) as u64);
+    let (rest, open) = (quantity - allocation[0], total - allocation[0]);
+    for (allocated, &size) in allocation.iter_mut().zip(sizes) {
+        *allocated += (rest as u128 * (size - *allocated) as u128 / open as u128) as u64;
+    }
+    let mut unallocated = quantity - allocation.iter().sum::<u64>();
+    for (allocated, &size) in allocation.iter_mut().zip(sizes) {
+        let extra = (size - *allocated).min(unallocated);
+        *allocated += extra;
+        unallocated -= extra;
+    }
+    allocation
+}
+
+/// The price at which the most base lots cross between `bids` and `asks`, given as
+/// `(price, base_lots)` orders, with that volume. Ties go to the price leaving the smallest
+/// imbalance between the crossing bids and asks, then to the one nearest
+/// `reference_price`, then to the lowest. `None` when nothing crosses.
+fn clearing_price(
+    bids: &[(u64, u64)],
+    asks: &[(u64, u64)],
+    reference_price: u64,
+) -> Option<(u64, u64)> {
+    let volume_at = |price: u64| {
+        let demand: u64 = bids.iter().filter(|b| b.0 >= price).map(|b| b.1).sum();
+        let supply: u64 = asks.iter().filter(|a| a.0 <= price).map(|a| a.1).sum();
+        (demand.min(supply), demand.abs_diff(supply))
+    };
+    bids.iter()
+        .chain(asks)
+        .map(|&(price, _)| {
+            let (volume, imbalance) = volume_at(price);
+            (price, volume, imbalance)
+        })
+        .filter(|&(_, volume, _)| volume > 0)
+        .min_by_key(|&(price, volume, imbalance)| {
+            (
+                std::cmp::Reverse(volume),
+                imbalance,
+                price.abs_diff(reference_price),
+                price,
+            )
+        })
+        .map(|(price, volume, _)| (price, volume))
+}
+
+/// Applies the taker side of `outcome` to `open_orders`, spending free balances first.
+///
+/// Returns the native amount (quote for bids, base for asks) the trader still has to
+/// deposit into the market vault.
+pub fn settle_taker(
+    market: &Market,
+    open_orders: &mut OpenOrders,
+    params: &OrderParams,
+    outcome: &OrderOutcome,
+) -> Result<u64> {
+    let overflow = || error!(ClobError::MathOverflow);
+    let filled_base = market.base_lots_to_native(outcome.base_lots_filled)?;
+    let filled_quote = market.quote_lots_to_native(outcome.quote_lots_filled)?;
+    open_orders.track_fill(params.side, outcome.base_lots_filled);
+    open_orders.track_resting(market, params.side, params.price, outcome.posted_base_lots)?;
+
+    let required = match params.side {
+        Side::Bid => {
+            let locked = market.notional_native(params.price, outcome.posted_base_lots)?;
+            open_orders.base_free = open_orders
+                .base_free
+                .checked_add(filled_base)
+                .ok_or_else(overflow)?;
+            open_orders.quote_locked = open_orders
+                .quote_locked
+                .checked_add(locked)
+                .ok_or_else(overflow)?;
+            filled_quote
+                .checked_add(outcome.taker_fee)
+                .and_then(|v| v.checked_add(locked))
+                .ok_or_else(overflow)?
+        }
+        Side::Ask => {
+            let locked = market.base_lots_to_native(outcome.posted_base_lots)?;
+            let proceeds = filled_quote
+                .checked_sub(outcome.taker_fee)
+                .ok_or_else(overflow)?;
+            open_orders.quote_free = open_orders
+                .quote_free
+                .checked_add(proceeds)
+                .ok_or_else(overflow)?;
+            open_orders.base_locked = open_orders
+                .base_locked
+                .checked_add(locked)
+                .ok_or_else(overflow)?;
+            filled_base.checked_add(locked).ok_or_else(overflow)?
+        }
+    };
+
+    let free = match params.side {
+        Side::Bid => &mut open_orders.quote_free,
+        Side::Ask => &mut open_orders.base_free,
+    };
+    let from_free = required.min(*free);
+    *free -= from_free;
+    Ok(required - from_free)
+}
+
+/// Bonds the order `outcome` rested, if any, with the market's order bond. Returns the
+/// lamports the trader has to post to the market for it.
+pub fn post_bond(market: &Market, open_orders: &mut OpenOrders, outcome: &OrderOutcome) -> u64 {
+    open_orders
+        .find_order_mut(outcome.order_id)
+        .map_or(0, |order| {
+            order.bond = market.order_bond;
+            order.bond
+        })
+}
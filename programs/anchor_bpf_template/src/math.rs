@@ -0,0 +1,192 @@
+//! Exact conversions between UI amounts, decimal numbers of tokens, and the market's
+//! native units, lots and ticks.
+//!
+//! No floats are involved: UI amounts are [`Decimal`]s, every intermediate value is a
+//! checked `u128`, and each conversion that may not be exact takes a [`Rounding`].
+
+use std::fmt;
+use std::str::FromStr;
+
+use anchor_lang::prelude::*;
+
+use crate::errors::ClobError;
+use crate::state::Market;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Towards zero.
+    Down,
+    /// Away from zero.
+    Up,
+    /// To the nearest value, halves away from zero.
+    Nearest,
+}
+
+/// `mantissa / 10^scale`, e.g. `Decimal { mantissa: 12345, scale: 2 }` is 123.45.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Decimal {
+    pub mantissa: u128,
+    pub scale: u32,
+}
+
+impl Decimal {
+    pub fn new(mantissa: u128, scale: u32) -> Self {
+        Decimal { mantissa, scale }
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = format!(
+            "{:0>width$}",
+            self.mantissa,
+            width = self.scale as usize + 1
+        );
+        let (int, frac) = digits.split_at(digits.len() - self.scale as usize);
+        if frac.is_empty() {
+            write!(f, "{int}")
+        } else {
+            write!(f, "{int}.{frac}")
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseDecimalError;
+
+impl fmt::Display for ParseDecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid decimal number")
+    }
+}
+
+impl std::error::Error for ParseDecimalError {}
+
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    /// Parses a non-negative number such as `"12"`, `"0.5"` or `"123.450"`, keeping every
+    /// digit given.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if int.is_empty() || !all_digits(int) || !all_digits(frac) {
+            return Err(ParseDecimalError);
+        }
+        let mantissa = format!("{int}{frac}")
+            .parse()
+            .map_err(|_| ParseDecimalError)?;
+        Ok(Decimal {
+            mantissa,
+            scale: frac.len() as u32,
+        })
+    }
+}
+
+fn pow10(exponent: u32) -> Result<u128> {
+    10u128
+        .checked_pow(exponent)
+        .ok_or_else(|| error!(ClobError::MathOverflow))
+}
+
+fn mul(a: u128, b: u128) -> Result<u128> {
+    a.checked_mul(b)
+        .ok_or_else(|| error!(ClobError::MathOverflow))
+}
+
+/// `numerator / denominator` rounded as asked.
+pub fn div_round(numerator: u128, denominator: u128, rounding: Rounding) -> Result<u128> {
+    require!(denominator > 0, ClobError::MathOverflow);
+    let (quotient, remainder) = (numerator / denominator, numerator % denominator);
+    let round_up = match rounding {
+        Rounding::Down => false,
+        Rounding::Up => remainder > 0,
+        Rounding::Nearest => remainder >= denominator - remainder,
+    };
+    Ok(quotient + round_up as u128)
+}
+
+fn to_u64(value: u128) -> Result<u64> {
+    u64::try_from(value).map_err(|_| error!(ClobError::MathOverflow))
+}
+
+/// Native units of a token with `decimals` in `amount` tokens.
+pub fn ui_to_native(amount: Decimal, decimals: u8, rounding: Rounding) -> Result<u64> {
+    let numerator = mul(amount.mantissa, pow10(decimals as u32)?)?;
+    to_u64(div_round(numerator, pow10(amount.scale)?, rounding)?)
+}
+
+/// Tokens in `amount` native units of a token with `decimals`, exactly.
+pub fn native_to_ui(amount: u64, decimals: u8) -> Decimal {
+    Decimal::new(amount as u128, decimals as u32)
+}
+
+/// Base lots in `quantity` base tokens.
+pub fn ui_quantity_to_base_lots(
+    market: &Market,
+    quantity: Decimal,
+    rounding: Rounding,
+) -> Result<u64> {
+    let numerator = mul(quantity.mantissa, pow10(market.base_decimals as u32)?)?;
+    let denominator = mul(pow10(quantity.scale)?, market.base_lot_size as u128)?;
+    to_u64(div_round(numerator, denominator, rounding)?)
+}
+
+/// Base tokens in `base_lots`, exactly.
+pub fn base_lots_to_ui_quantity(market: &Market, base_lots: u64) -> Result<Decimal> {
+    let native = mul(base_lots as u128, market.base_lot_size as u128)?;
+    Ok(Decimal::new(native, market.base_decimals as u32))
+}
+
+/// Book price, in quote lots per base lot and on the market's tick, of `price` quote
+/// tokens per base token. The tick is the one of the price rounded down, see
+/// [`Market::tick_size_at`].
+pub fn ui_price_to_price(market: &Market, price: Decimal, rounding: Rounding) -> Result<u64> {
+    // quote lots per base lot = price * base_lot_size * 10^quote_decimals
+    //                           / (10^scale * 10^base_decimals * quote_lot_size)
+    let numerator = mul(
+        mul(price.mantissa, market.base_lot_size as u128)?,
+        pow10(market.quote_decimals as u32)?,
+    )?;
+    let denominator = mul(
+        mul(pow10(price.scale)?, pow10(market.base_decimals as u32)?)?,
+        market.quote_lot_size as u128,
+    )?;
+    let floor = to_u64(div_round(numerator, denominator, Rounding::Down)?)?;
+    let tick = market.tick_size_at(floor) as u128;
+    let ticks = div_round(numerator, mul(denominator, tick)?, rounding)?;
+    let price = to_u64(mul(ticks, tick)?)?;
+    // Rounding up may reach a band with a coarser tick, the price is re-rounded to it.
+    let band_tick = market.tick_size_at(price) as u128;
+    if band_tick == tick {
+        return Ok(price);
+    }
+    to_u64(mul(
+        div_round(price as u128, band_tick, rounding)?,
+        band_tick,
+    )?)
+}
+
+/// Quote tokens per base token at book `price`, with `scale` decimals.
+pub fn price_to_ui_price(
+    market: &Market,
+    price: u64,
+    scale: u32,
+    rounding: Rounding,
+) -> Result<Decimal> {
+    let numerator = mul(
+        mul(
+            mul(price as u128, market.quote_lot_size as u128)?,
+            pow10(market.base_decimals as u32)?,
+        )?,
+        pow10(scale)?,
+    )?;
+    let denominator = mul(
+        market.base_lot_size as u128,
+        pow10(market.quote_decimals as u32)?,
+    )?;
+    Ok(Decimal::new(
+        div_round(numerator, denominator, rounding)?,
+        scale,
+    ))
+}
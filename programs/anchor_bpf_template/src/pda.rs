@@ -0,0 +1,13 @@
+//! Seeds and address derivation for program-derived accounts.
+//!
+//! Markets, books, event queues and `OpenOrders` are ordinary accounts created by the
+//! client and handed to the program zeroed, so the only PDA is the market authority
+//! that owns the vaults and signs withdrawals from them.
+
+use anchor_lang::prelude::*;
+
+pub const MARKET_AUTHORITY_SEED: &[u8] = b"market_authority";
+
+pub fn find_market_authority_address(market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MARKET_AUTHORITY_SEED, market.as_ref()], &crate::ID)
+}
@@ -0,0 +1,322 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ClobError;
+
+use super::TickSizes;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Side {
+    #[default]
+    Bid,
+    Ask,
+}
+
+impl Side {
+    pub fn invert(self) -> Side {
+        match self {
+            Side::Bid => Side::Ask,
+            Side::Ask => Side::Bid,
+        }
+    }
+
+    /// Whether a taker on this side with `limit_price` trades against a maker at `maker_price`.
+    pub fn crosses(self, limit_price: u64, maker_price: u64) -> bool {
+        match self {
+            Side::Bid => maker_price <= limit_price,
+            Side::Ask => maker_price >= limit_price,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OrderType {
+    /// Match what crosses, rest the remainder.
+    #[default]
+    Limit,
+    /// Match what crosses, drop the remainder.
+    ImmediateOrCancel,
+    /// Rest the whole order, fail if any of it would cross.
+    PostOnly,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Order {
+    pub order_id: u64,
+    /// `OpenOrders` account the order belongs to.
+    pub owner: Pubkey,
+    pub client_order_id: u64,
+    /// Quote lots per base lot.
+    pub price: u64,
+    /// Remaining base lots.
+    pub quantity: u64,
+    pub timestamp: i64,
+    /// Unix timestamp after which the order can no longer match, 0 for good-till-cancelled.
+    pub expiry_timestamp: i64,
+    /// Other orders of the owner cancelled once this one fills, 0 if not in a group.
+    pub group_id: u64,
+}
+
+impl Order {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 8 + 8 + 8;
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expiry_timestamp != 0 && now >= self.expiry_timestamp
+    }
+}
+
+/// A resting order priced at the oracle price plus `price_offset`, rounded to the tick
+/// size away from the other side and clamped to its peg limit.
+///
+/// `order.price` holds the peg limit, the highest price a bid or the lowest price an ask
+/// can trade at, 0 for none on asks. Bids lock their quote at it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PeggedOrder {
+    pub order: Order,
+    /// Quote lots per base lot added to the oracle price.
+    pub price_offset: i64,
+}
+
+impl PeggedOrder {
+    pub const LEN: usize = Order::LEN + 8;
+
+    pub fn price(&self, side: Side, oracle_price: u64, tick_sizes: &TickSizes) -> Option<u64> {
+        peg_price(
+            side,
+            oracle_price,
+            self.price_offset,
+            self.order.price,
+            tick_sizes,
+        )
+    }
+}
+
+/// Price of a pegged order at `oracle_price`, on the tick of its band, `None` if it is
+/// not positive.
+pub fn peg_price(
+    side: Side,
+    oracle_price: u64,
+    price_offset: i64,
+    peg_limit: u64,
+    tick_sizes: &TickSizes,
+) -> Option<u64> {
+    let raw = oracle_price as i128 + price_offset as i128;
+    // Rounding up stops at the next band's minimum, a multiple of the tick below it.
+    let tick = tick_sizes.at(u64::try_from(raw).unwrap_or(0)) as i128;
+    let rounded = match side {
+        Side::Bid => raw.div_euclid(tick) * tick,
+        Side::Ask => (raw + tick - 1).div_euclid(tick) * tick,
+    };
+    let price = match (side, peg_limit as i128) {
+        (_, 0) => rounded,
+        (Side::Bid, limit) => rounded.min(limit),
+        (Side::Ask, limit) => rounded.max(limit),
+    };
+    u64::try_from(price).ok().filter(|&p| p > 0)
+}
+
+/// Where an order sits in a [`BookSide`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BookOrder {
+    Fixed(usize),
+    Pegged(usize),
+}
+
+/// One side of the book, kept sorted best price first and oldest first within a price.
+///
+/// Pegged orders are kept apart, in placement order, since their prices move with the
+/// oracle relative to the fixed-price orders.
+#[account]
+#[derive(Debug, Default)]
+pub struct BookSide {
+    pub market: Pubkey,
+    pub side: Side,
+    pub orders: Vec<Order>,
+    pub pegged_orders: Vec<PeggedOrder>,
+}
+
+impl BookSide {
+    pub const MAX_ORDERS: usize = 64;
+    pub const MAX_PEGGED_ORDERS: usize = 16;
+    pub const LEN: usize =
+        32 + 1 + 4 + Self::MAX_ORDERS * Order::LEN + 4 + Self::MAX_PEGGED_ORDERS * PeggedOrder::LEN;
+
+    pub fn is_full(&self) -> bool {
+        self.orders.len() >= Self::MAX_ORDERS
+    }
+
+    pub fn best(&self) -> Option<&Order> {
+        self.orders.first()
+    }
+
+    pub fn best_price(&self) -> Option<u64> {
+        self.best().map(|o| o.price)
+    }
+
+    /// Whether `a` has strictly better price priority than `b` on this side.
+    pub fn is_better(&self, a: u64, b: u64) -> bool {
+        match self.side {
+            Side::Bid => a > b,
+            Side::Ask => a < b,
+        }
+    }
+
+    pub fn insert(&mut self, order: Order) -> Result<()> {
+        require!(!self.is_full(), ClobError::BookFull);
+        let index = self
+            .orders
+            .iter()
+            .position(|o| self.is_better(order.price, o.price))
+            .unwrap_or(self.orders.len());
+        self.orders.insert(index, order);
+        Ok(())
+    }
+
+    pub fn is_pegged_full(&self) -> bool {
+        self.pegged_orders.len() >= Self::MAX_PEGGED_ORDERS
+    }
+
+    /// [`BookSide::is_better`] for pegged prices, `None` for a non-positive price being
+    /// the worst.
+    pub fn is_better_pegged(&self, a: Option<u64>, b: Option<u64>) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => self.is_better(a, b),
+            (a, b) => a.is_some() && b.is_none(),
+        }
+    }
+
+    /// The pegged order with the worst price at `oracle_price`, the newest of equally
+    /// priced ones, and its price.
+    pub fn worst_pegged(
+        &self,
+        oracle_price: u64,
+        tick_sizes: &TickSizes,
+    ) -> Option<(BookOrder, Option<u64>)> {
+        self.pegged_orders
+            .iter()
+            .enumerate()
+            .map(|(index, o)| (index, o.price(self.side, oracle_price, tick_sizes)))
+            .reduce(|worst, o| {
+                if self.is_better_pegged(o.1, worst.1) {
+                    worst
+                } else {
+                    o
+                }
+            })
+            .map(|(index, price)| (BookOrder::Pegged(index), price))
+    }
+
+    pub fn insert_pegged(&mut self, order: PeggedOrder) -> Result<()> {
+        require!(!self.is_pegged_full(), ClobError::BookFull);
+        self.pegged_orders.push(order);
+        Ok(())
+    }
+
+    pub fn find(&self, order_id: u64) -> Option<BookOrder> {
+        if let Some(index) = self.orders.iter().position(|o| o.order_id == order_id) {
+            return Some(BookOrder::Fixed(index));
+        }
+        self.pegged_orders
+            .iter()
+            .position(|o| o.order.order_id == order_id)
+            .map(BookOrder::Pegged)
+    }
+
+    /// An order of `owner` in `group_id`, fixed or pegged.
+    pub fn find_group(&self, owner: Pubkey, group_id: u64) -> Option<BookOrder> {
+        let in_group = |o: &Order| o.owner == owner && o.group_id == group_id;
+        if let Some(index) = self.orders.iter().position(in_group) {
+            return Some(BookOrder::Fixed(index));
+        }
+        self.pegged_orders
+            .iter()
+            .position(|o| in_group(&o.order))
+            .map(BookOrder::Pegged)
+    }
+
+    pub fn order(&self, at: BookOrder) -> &Order {
+        match at {
+            BookOrder::Fixed(index) => &self.orders[index],
+            BookOrder::Pegged(index) => &self.pegged_orders[index].order,
+        }
+    }
+
+    pub fn order_mut(&mut self, at: BookOrder) -> &mut Order {
+        match at {
+            BookOrder::Fixed(index) => &mut self.orders[index],
+            BookOrder::Pegged(index) => &mut self.pegged_orders[index].order,
+        }
+    }
+
+    pub fn remove_at(&mut self, at: BookOrder) -> Order {
+        match at {
+            BookOrder::Fixed(index) => self.orders.remove(index),
+            BookOrder::Pegged(index) => self.pegged_orders.remove(index).order,
+        }
+    }
+
+    pub fn remove(&mut self, order_id: u64) -> Option<Order> {
+        self.find(order_id).map(|at| self.remove_at(at))
+    }
+
+    /// Best price among orders that have not expired, pegged ones at `oracle_price`.
+    pub fn best_live_price(
+        &self,
+        oracle_price: Option<u64>,
+        tick_sizes: &TickSizes,
+        now: i64,
+    ) -> Option<u64> {
+        let fixed = self
+            .orders
+            .iter()
+            .find(|o| !o.is_expired(now))
+            .map(|o| o.price);
+        let pegged = oracle_price.into_iter().flat_map(|oracle_price| {
+            self.pegged_orders
+                .iter()
+                .filter(move |o| !o.order.is_expired(now))
+                .filter_map(move |o| o.price(self.side, oracle_price, tick_sizes))
+        });
+        fixed.into_iter().chain(pegged).reduce(|best, price| {
+            if self.is_better(price, best) {
+                price
+            } else {
+                best
+            }
+        })
+    }
+
+    /// The order a taker meets next and its price: the better priced of the best fixed
+    /// order and the best pegged order at `oracle_price`, the older one on equal prices.
+    /// Pegged orders are skipped without an oracle price.
+    pub fn next_maker(
+        &self,
+        oracle_price: Option<u64>,
+        tick_sizes: &TickSizes,
+    ) -> Option<(BookOrder, u64)> {
+        let fixed = self
+            .orders
+            .first()
+            .map(|o| (BookOrder::Fixed(0), o.price, o.timestamp));
+        let pegged = oracle_price.and_then(|oracle_price| {
+            self.pegged_orders
+                .iter()
+                .enumerate()
+                .filter_map(|(index, o)| {
+                    let price = o.price(self.side, oracle_price, tick_sizes)?;
+                    Some((BookOrder::Pegged(index), price, o.order.timestamp))
+                })
+                .reduce(|best, o| if self.is_better(o.1, best.1) { o } else { best })
+        });
+        let best = match (fixed, pegged) {
+            (Some(f), Some(p)) => {
+                if self.is_better(p.1, f.1) || (p.1 == f.1 && p.2 < f.2) {
+                    p
+                } else {
+                    f
+                }
+            }
+            (f, p) => f.or(p)?,
+        };
+        Some((best.0, best.1))
+    }
+}
@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ClobError;
+
+use super::Side;
+
+/// A maker order traded against a taker.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FillEvent {
+    pub maker: Pubkey,
+    pub maker_order_id: u64,
+    pub maker_client_order_id: u64,
+    pub maker_side: Side,
+    /// Whether the maker order left the book with this fill.
+    pub maker_out: bool,
+    /// Taker `OpenOrders` account, or the taker wallet for swaps.
+    pub taker: Pubkey,
+    pub taker_order_id: u64,
+    pub price: u64,
+    pub quantity: u64,
+    pub timestamp: i64,
+}
+
+/// Why a maker order left the book without being filled.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutReason {
+    /// Removed with its order group, or as a remainder below the market's minimum.
+    #[default]
+    Cancelled,
+    Expired,
+    /// Removed from a full book side for a better priced order.
+    Evicted,
+}
+
+/// A maker order left the book without being filled, e.g. because it expired.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct OutEvent {
+    pub owner: Pubkey,
+    pub order_id: u64,
+    pub client_order_id: u64,
+    pub side: Side,
+    pub price: u64,
+    /// Base lots still unfilled, whose funds are returned to the owner.
+    pub quantity: u64,
+    pub timestamp: i64,
+    pub reason: OutReason,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    Fill(FillEvent),
+    Out(OutEvent),
+}
+
+impl Event {
+    pub const LEN: usize = 1 + 32 + 8 + 8 + 1 + 1 + 32 + 8 + 8 + 8 + 8;
+
+    /// `OpenOrders` account that has to be passed to `consume_events` for this event.
+    pub fn owner(&self) -> Pubkey {
+        match self {
+            Event::Fill(fill) => fill.maker,
+            Event::Out(out) => out.owner,
+        }
+    }
+}
+
+/// Maker-side settlement backlog, drained by the `consume_events` crank.
+#[account]
+#[derive(Debug, Default)]
+pub struct EventQueue {
+    pub market: Pubkey,
+    /// Total number of events ever pushed.
+    pub seq_num: u64,
+    pub events: Vec<Event>,
+}
+
+impl EventQueue {
+    pub const MAX_EVENTS: usize = 64;
+    pub const LEN: usize = 32 + 8 + 4 + Self::MAX_EVENTS * Event::LEN;
+
+    pub fn is_full(&self) -> bool {
+        self.events.len() >= Self::MAX_EVENTS
+    }
+
+    pub fn push(&mut self, event: Event) -> Result<()> {
+        require!(!self.is_full(), ClobError::EventQueueFull);
+        self.events.push(event);
+        self.seq_num += 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,336 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ClobError;
+use crate::pda::MARKET_AUTHORITY_SEED;
+
+use super::{OracleKind, OutReason, Side};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MarketStatus {
+    /// Orders can be placed, matched and cancelled.
+    #[default]
+    Active,
+    /// Only cancels and settlement are allowed.
+    CancelOnly,
+    /// Nothing but settlement is allowed.
+    Paused,
+    /// Limit orders rest without matching, crossed or not, until `uncross_auction` fills
+    /// the crossing ones at a single price and makes the market `Active`.
+    Auction,
+}
+
+/// How a taker's quantity is split between the orders resting at one price.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MatchingMode {
+    /// Oldest order first.
+    #[default]
+    Fifo,
+    /// The oldest order first gets its top-of-queue share of the quantity, the rest is
+    /// split in proportion to the open size of each order. Only fixed-price orders are
+    /// matched pro-rata, pegged ones stay FIFO.
+    ProRata,
+}
+
+/// Tick size of the prices from `min_price` up to the next band's, in quote lots per base
+/// lot.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TickBand {
+    pub min_price: u64,
+    pub tick_size: u64,
+}
+
+impl TickBand {
+    pub const LEN: usize = 8 + 8;
+}
+
+/// A market's tick sizes by price, detached from the market so it can be borrowed
+/// while the market is updated.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TickSizes {
+    /// Tick size of the prices below the first band.
+    pub tick_size: u64,
+    pub bands: Vec<TickBand>,
+}
+
+impl TickSizes {
+    /// A single tick size for all prices.
+    pub fn flat(tick_size: u64) -> Self {
+        TickSizes {
+            tick_size,
+            bands: Vec::new(),
+        }
+    }
+
+    /// Tick size prices of `price` must be a multiple of.
+    pub fn at(&self, price: u64) -> u64 {
+        tick_size_at(self.tick_size, &self.bands, price)
+    }
+}
+
+fn tick_size_at(tick_size: u64, bands: &[TickBand], price: u64) -> u64 {
+    bands
+        .iter()
+        .rev()
+        .find(|band| band.min_price <= price)
+        .map_or(tick_size, |band| band.tick_size)
+}
+
+#[account]
+#[derive(Debug, Default)]
+pub struct Market {
+    pub admin: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub event_queue: Pubkey,
+
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    /// Native base units in one base lot.
+    pub base_lot_size: u64,
+    /// Native quote units in one quote lot.
+    pub quote_lot_size: u64,
+    /// Prices are quoted in quote lots per base lot and must be a multiple of this, or of
+    /// the tick size of their band in `tick_schedule`.
+    pub tick_size: u64,
+    pub taker_fee_bps: u16,
+
+    pub status: MarketStatus,
+    /// Taker fees collected in native quote units, not yet swept.
+    pub fees_accrued: u64,
+    /// Monotonic counter used to assign order ids.
+    pub order_seq_num: u64,
+    /// Bump of the market authority PDA that owns the vaults.
+    pub authority_bump: u8,
+
+    /// Reference price account limit prices are banded around, unset without an oracle.
+    pub oracle: Pubkey,
+    pub oracle_kind: OracleKind,
+    /// Maximum distance of a limit price from the oracle price, in basis points of it.
+    pub price_band_bps: u16,
+    /// Oldest oracle update, in seconds, order entry accepts.
+    pub oracle_max_staleness: u32,
+
+    /// Hidden midpoint orders, unset until the admin adds a midpoint book.
+    pub midpoint_book: Pubkey,
+
+    /// Price of the most recent fill in quote lots per base lot, 0 before the first.
+    pub last_trade_price: u64,
+
+    /// Largest move of a fill from the reference price, in basis points of it, before the
+    /// market halts. 0 disables the circuit breaker.
+    pub circuit_breaker_bps: u16,
+    /// Seconds a reference price is kept before the next fill replaces it.
+    pub circuit_breaker_window: u32,
+    /// Seconds the market stays cancel-only once the breaker trips.
+    pub circuit_breaker_cooldown: u32,
+    /// Last trade price when the current window started, 0 for none.
+    pub reference_price: u64,
+    pub reference_timestamp: i64,
+    /// Unix timestamp until which an `Active` market is cancel-only.
+    pub halted_until: i64,
+
+    /// Slots orders are collected for before `clear_batch` uncrosses them, 0 for
+    /// continuous matching.
+    pub batch_slots: u64,
+    /// Slot the current batch started in.
+    pub batch_start_slot: u64,
+
+    pub matching_mode: MatchingMode,
+    /// Share of a taker's quantity at a price given to the oldest order first under
+    /// `MatchingMode::ProRata`, in basis points.
+    pub pro_rata_top_share_bps: u16,
+
+    /// Smallest order, and smallest remainder left resting, in base lots. 0 for none.
+    pub min_base_lots: u64,
+    /// Smallest value of an order, and of a remainder left resting, in native quote
+    /// units. 0 for none.
+    pub min_notional: u64,
+
+    /// Lamports posted to this account with every order placed to rest, refunded once it
+    /// fills or is cancelled. Orders rested by executed triggers are not bonded.
+    pub order_bond: u64,
+    /// Share of the bond of an order that expired or was evicted paid to whoever settles
+    /// its removal with `consume_events`, in basis points.
+    pub bond_forfeit_bps: u16,
+
+    /// Tick sizes by price, ascending `min_price`. Prices below the first band, or all of
+    /// them when empty, use `tick_size`.
+    pub tick_schedule: Vec<TickBand>,
+}
+
+impl Market {
+    pub const MAX_TICK_BANDS: usize = 8;
+    pub const LEN: usize = 8 * 32
+        + 1
+        + 1
+        + 8
+        + 8
+        + 8
+        + 2
+        + 1
+        + 8
+        + 8
+        + 1
+        + 32
+        + 1
+        + 2
+        + 4
+        + 32
+        + 8
+        + 2
+        + 4
+        + 4
+        + 8
+        + 8
+        + 8
+        + 8
+        + 8
+        + 1
+        + 2
+        + 8
+        + 8
+        + 8
+        + 2
+        + 4
+        + Self::MAX_TICK_BANDS * TickBand::LEN;
+
+    /// Seeds of the market authority PDA, `market` being this account's address.
+    pub fn authority_seeds<'a>(&'a self, market: &'a Pubkey) -> [&'a [u8]; 3] {
+        [
+            MARKET_AUTHORITY_SEED,
+            market.as_ref(),
+            std::slice::from_ref(&self.authority_bump),
+        ]
+    }
+
+    /// The status trading is subject to at `now`: `CancelOnly` while a tripped circuit
+    /// breaker cools down.
+    pub fn status_at(&self, now: i64) -> MarketStatus {
+        if self.status == MarketStatus::Active && now < self.halted_until {
+            MarketStatus::CancelOnly
+        } else {
+            self.status
+        }
+    }
+
+    /// Tick size prices of `price` must be a multiple of.
+    pub fn tick_size_at(&self, price: u64) -> u64 {
+        tick_size_at(self.tick_size, &self.tick_schedule, price)
+    }
+
+    pub fn tick_sizes(&self) -> TickSizes {
+        TickSizes {
+            tick_size: self.tick_size,
+            bands: self.tick_schedule.clone(),
+        }
+    }
+
+    /// Whether `base_lots` at `price` is below the market's minimum order size or value.
+    /// A `price` of 0, as for an ask without a limit, only has its size checked.
+    pub fn is_below_minimum(&self, price: u64, base_lots: u64) -> bool {
+        let notional = price as u128 * base_lots as u128 * self.quote_lot_size as u128;
+        base_lots < self.min_base_lots || (price > 0 && notional < self.min_notional as u128)
+    }
+
+    /// Whether orders rest without matching, to be uncrossed at a single price: during an
+    /// auction, or always in batch mode.
+    pub fn collects_orders(&self) -> bool {
+        self.status == MarketStatus::Auction || self.batch_slots > 0
+    }
+
+    /// Whether limit orders can be placed at `now`.
+    pub fn accepts_orders(&self, now: i64) -> bool {
+        matches!(
+            self.status_at(now),
+            MarketStatus::Active | MarketStatus::Auction
+        )
+    }
+
+    /// Records a fill at `price`. If it moved the price more than the circuit breaker
+    /// allows within its window, halts the market and returns the reference price.
+    pub fn record_trade(&mut self, price: u64, now: i64) -> Option<u64> {
+        let previous = std::mem::replace(&mut self.last_trade_price, price);
+        if self.circuit_breaker_bps == 0 {
+            return None;
+        }
+        if self.reference_price == 0
+            || now.saturating_sub(self.reference_timestamp) >= self.circuit_breaker_window as i64
+        {
+            self.reference_price = if previous > 0 { previous } else { price };
+            self.reference_timestamp = now;
+        }
+        let reference_price = self.reference_price;
+        let moved = price.abs_diff(reference_price) as u128 * 10_000
+            > reference_price as u128 * self.circuit_breaker_bps as u128;
+        if !moved {
+            return None;
+        }
+        // Trading resumes with a fresh window.
+        self.halted_until = now.saturating_add(self.circuit_breaker_cooldown as i64);
+        self.reference_price = 0;
+        Some(reference_price)
+    }
+
+    /// Lamports kept from a `bond` when its order leaves the book for `reason`.
+    pub fn bond_forfeit(&self, bond: u64, reason: OutReason) -> u64 {
+        match reason {
+            OutReason::Cancelled => 0,
+            OutReason::Expired | OutReason::Evicted => {
+                (bond as u128 * self.bond_forfeit_bps as u128 / 10_000) as u64
+            }
+        }
+    }
+
+    pub fn next_order_id(&mut self) -> u64 {
+        self.order_seq_num += 1;
+        self.order_seq_num
+    }
+
+    pub fn base_lots_to_native(&self, base_lots: u64) -> Result<u64> {
+        base_lots
+            .checked_mul(self.base_lot_size)
+            .ok_or_else(|| error!(ClobError::MathOverflow))
+    }
+
+    pub fn quote_lots_to_native(&self, quote_lots: u64) -> Result<u64> {
+        quote_lots
+            .checked_mul(self.quote_lot_size)
+            .ok_or_else(|| error!(ClobError::MathOverflow))
+    }
+
+    /// Native quote value of `base_lots` at `price` (quote lots per base lot).
+    pub fn notional_native(&self, price: u64, base_lots: u64) -> Result<u64> {
+        let quote_lots = price
+            .checked_mul(base_lots)
+            .ok_or_else(|| error!(ClobError::MathOverflow))?;
+        self.quote_lots_to_native(quote_lots)
+    }
+
+    /// Native funds backing an order of `base_lots` at `price` in full: its quote plus the
+    /// worst case taker fee for bids, its base for asks.
+    pub fn order_funds(&self, side: Side, price: u64, base_lots: u64) -> Result<u64> {
+        match side {
+            Side::Bid => {
+                let quote = self.notional_native(price, base_lots)?;
+                quote
+                    .checked_add(self.taker_fee(quote)?)
+                    .ok_or_else(|| error!(ClobError::MathOverflow))
+            }
+            Side::Ask => self.base_lots_to_native(base_lots),
+        }
+    }
+
+    /// Taker fee on a native quote amount, rounded up in favour of the market.
+    pub fn taker_fee(&self, quote_native: u64) -> Result<u64> {
+        let fee = (quote_native as u128)
+            .checked_mul(self.taker_fee_bps as u128)
+            .and_then(|v| v.checked_add(9_999))
+            .map(|v| v / 10_000)
+            .ok_or_else(|| error!(ClobError::MathOverflow))?;
+        u64::try_from(fee).map_err(|_| error!(ClobError::MathOverflow))
+    }
+}
@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ClobError;
+
+use super::{Market, Order, Side};
+
+/// The part of a resting iceberg order not shown on the book yet.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct IcebergReserve {
+    pub order_id: u64,
+    /// Base lots shown each time the displayed part fills.
+    pub display_base_lots: u64,
+    pub reserve_base_lots: u64,
+}
+
+impl IcebergReserve {
+    pub const LEN: usize = 8 + 8 + 8;
+}
+
+/// Hidden orders that rest at the midpoint of the best bid and offer and only trade
+/// against incoming takers, at the mid. Kept apart from the bids and asks so they never
+/// show in the book, as are the reserves of iceberg orders.
+///
+/// `order.price` holds the order's limit, the highest mid a bid or the lowest mid an
+/// ask trades at, 0 for none on asks. Bids lock their quote at it.
+#[account]
+#[derive(Debug, Default)]
+pub struct MidpointBook {
+    pub market: Pubkey,
+    /// Oldest first, there is no price priority at a single price.
+    pub bids: Vec<Order>,
+    pub asks: Vec<Order>,
+    pub reserves: Vec<IcebergReserve>,
+}
+
+impl MidpointBook {
+    pub const MAX_ORDERS: usize = 32;
+    pub const MAX_RESERVES: usize = 32;
+    pub const LEN: usize =
+        32 + 2 * (4 + Self::MAX_ORDERS * Order::LEN) + 4 + Self::MAX_RESERVES * IcebergReserve::LEN;
+
+    pub fn side(&self, side: Side) -> &Vec<Order> {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    pub fn side_mut(&mut self, side: Side) -> &mut Vec<Order> {
+        match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        }
+    }
+
+    pub fn is_full(&self, side: Side) -> bool {
+        self.side(side).len() >= Self::MAX_ORDERS
+    }
+
+    /// Index of the order on `side` with the worst limit, the newest of equal ones.
+    pub fn worst(&self, side: Side) -> Option<usize> {
+        let orders = self.side(side);
+        (0..orders.len()).reduce(|worst, index| {
+            if limit_is_better(side, orders[index].price, orders[worst].price) {
+                worst
+            } else {
+                index
+            }
+        })
+    }
+
+    pub fn insert(&mut self, side: Side, order: Order) -> Result<()> {
+        let orders = self.side_mut(side);
+        require!(orders.len() < Self::MAX_ORDERS, ClobError::BookFull);
+        orders.push(order);
+        Ok(())
+    }
+
+    pub fn find(&self, side: Side, order_id: u64) -> Option<usize> {
+        self.side(side).iter().position(|o| o.order_id == order_id)
+    }
+
+    pub fn insert_reserve(&mut self, reserve: IcebergReserve) -> Result<()> {
+        require!(
+            self.reserves.len() < Self::MAX_RESERVES,
+            ClobError::BookFull
+        );
+        self.reserves.push(reserve);
+        Ok(())
+    }
+
+    /// Hidden base lots behind the displayed part of `order_id`, 0 if it is no iceberg.
+    pub fn reserve(&self, order_id: u64) -> u64 {
+        self.reserves
+            .iter()
+            .find(|r| r.order_id == order_id)
+            .map_or(0, |r| r.reserve_base_lots)
+    }
+
+    /// Removes the reserve of `order_id` and returns its base lots.
+    pub fn take_reserve(&mut self, order_id: u64) -> u64 {
+        match self.reserves.iter().position(|r| r.order_id == order_id) {
+            Some(index) => self.reserves.remove(index).reserve_base_lots,
+            None => 0,
+        }
+    }
+
+    /// Moves the next displayed part of `order_id` out of its reserve and returns its
+    /// base lots, 0 once the reserve is used up.
+    pub fn refill(&mut self, order_id: u64) -> u64 {
+        let index = match self.reserves.iter().position(|r| r.order_id == order_id) {
+            Some(index) => index,
+            None => return 0,
+        };
+        let reserve = &mut self.reserves[index];
+        let shown = reserve.display_base_lots.min(reserve.reserve_base_lots);
+        reserve.reserve_base_lots -= shown;
+        if reserve.reserve_base_lots == 0 {
+            self.reserves.remove(index);
+        }
+        shown
+    }
+}
+
+/// Whether midpoint limit `a` on `side` trades at strictly more mids than `b`, an ask
+/// without a limit at all of them.
+pub fn limit_is_better(side: Side, a: u64, b: u64) -> bool {
+    match side {
+        Side::Bid => a > b,
+        Side::Ask => b != 0 && (a == 0 || a < b),
+    }
+}
+
+/// Whether a midpoint order on `side` with `limit` trades at `mid`.
+pub fn midpoint_allows(side: Side, limit: u64, mid: u64) -> bool {
+    match side {
+        Side::Bid => mid <= limit,
+        Side::Ask => limit == 0 || mid >= limit,
+    }
+}
+
+impl Market {
+    /// The market's midpoint book, found among `accounts` by address. `None` if the market
+    /// has none; required in `accounts` if it has one.
+    pub fn load_midpoint_book<'info>(
+        &self,
+        accounts: &[AccountInfo<'info>],
+    ) -> Result<Option<Account<'info, MidpointBook>>> {
+        if self.midpoint_book == Pubkey::default() {
+            return Ok(None);
+        }
+        let info = accounts
+            .iter()
+            .find(|info| *info.key == self.midpoint_book)
+            .ok_or_else(|| error!(ClobError::MidpointBookRequired))?;
+        Account::try_from(info).map(Some)
+    }
+}
@@ -0,0 +1,17 @@
+pub mod book;
+pub mod event_queue;
+pub mod market;
+pub mod midpoint_book;
+pub mod open_orders;
+pub mod oracle;
+pub mod trigger;
+pub mod twap;
+
+pub use book::*;
+pub use event_queue::*;
+pub use market::*;
+pub use midpoint_book::*;
+pub use open_orders::*;
+pub use oracle::*;
+pub use trigger::*;
+pub use twap::*;
@@ -0,0 +1,410 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ClobError;
+
+use super::{Event, EventQueue, FillEvent, Market, OutEvent, Side};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct OpenOrder {
+    pub order_id: u64,
+    pub client_order_id: u64,
+    pub side: Side,
+    /// Price the order's funds are locked at: its limit price, or the peg limit of a
+    /// pegged order.
+    pub price: u64,
+    /// See [`Order::group_id`].
+    pub group_id: u64,
+    /// Lamports posted to the market with the order, see [`Market::order_bond`].
+    pub bond: u64,
+}
+
+impl OpenOrder {
+    pub const LEN: usize = 8 + 8 + 1 + 8 + 8 + 8;
+}
+
+/// Caps on what an `OpenOrders` account may do, set by the market admin or the delegate,
+/// which the owner cannot replace while any is set. 0 means no cap.
+/// Trigger and TWAP orders are checked as if resting in full when placed and again as
+/// each executes, when a trigger over a cap is dropped and a TWAP slice skipped.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RiskLimits {
+    /// Orders placed within one slot.
+    pub max_orders_per_slot: u16,
+    /// Native quote value of the resting orders, at the prices their funds are locked at.
+    /// Pegged asks need a peg limit while it is set.
+    pub max_open_notional: u64,
+    /// Net base position in base lots, either way, if all resting orders of one side were
+    /// filled.
+    pub max_net_base_lots: u64,
+}
+
+impl RiskLimits {
+    pub const LEN: usize = 2 + 8 + 8;
+}
+
+/// What [`RiskLimits`] caps, at one point in time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Exposure {
+    pub open_notional: u64,
+    /// Base lots held if every resting bid were filled.
+    pub max_long: u64,
+    /// Base lots owed if every resting ask were filled.
+    pub max_short: u64,
+}
+
+/// A trader's balances and resting orders on one market.
+#[account]
+#[derive(Debug, Default)]
+pub struct OpenOrders {
+    pub market: Pubkey,
+    pub owner: Pubkey,
+    /// May place and cancel orders on the owner's behalf, `Pubkey::default()` if unset.
+    pub delegate: Pubkey,
+
+    pub base_free: u64,
+    pub base_locked: u64,
+    pub quote_free: u64,
+    pub quote_locked: u64,
+    /// Lamports of refunded order bonds, held on the market until `settle_funds`.
+    pub bond_free: u64,
+
+    /// Seconds without a `heartbeat` after which anyone may cancel the resting orders, 0
+    /// to disable.
+    pub heartbeat_interval: u32,
+    /// Unix timestamp of the last `heartbeat`, or of enabling them.
+    pub last_heartbeat: i64,
+    /// Number of `place_order`, `place_pegged_order`, `place_midpoint_order`,
+    /// `place_iceberg_order` and `batch_orders` calls so far.
+    pub sequence: u64,
+
+    pub risk_limits: RiskLimits,
+    /// Slot of the last order placed, and the number of orders placed in it.
+    pub last_order_slot: u64,
+    pub orders_in_slot: u16,
+    /// Base lots bought minus base lots sold.
+    pub net_base_lots: i64,
+    /// Base lots of the resting bids and asks, including iceberg reserves.
+    pub open_bid_lots: u64,
+    pub open_ask_lots: u64,
+    /// Native quote value of the resting orders, at the prices their funds are locked at.
+    pub open_notional: u64,
+
+    pub orders: Vec<OpenOrder>,
+    /// Order groups a member of which was filled, most recent last, so that their trigger
+    /// orders are dropped instead of executed.
+    pub filled_groups: Vec<u64>,
+}
+
+impl OpenOrders {
+    pub const MAX_ORDERS: usize = 24;
+    pub const MAX_FILLED_GROUPS: usize = 8;
+    pub const LEN: usize = 32 * 3
+        + 8 * 5
+        + 4
+        + 8
+        + 8
+        + RiskLimits::LEN
+        + 8
+        + 2
+        + 8 * 4
+        + 4
+        + Self::MAX_ORDERS * OpenOrder::LEN
+        + 4
+        + Self::MAX_FILLED_GROUPS * 8;
+
+    pub fn is_owner_or_delegate(&self, key: &Pubkey) -> bool {
+        *key == self.owner || self.is_delegate(key)
+    }
+
+    pub fn is_delegate(&self, key: &Pubkey) -> bool {
+        self.delegate != Pubkey::default() && *key == self.delegate
+    }
+
+    /// Whether heartbeats are enabled and the last one is older than the interval.
+    pub fn is_stale(&self, now: i64) -> bool {
+        self.heartbeat_interval > 0
+            && now
+                > self
+                    .last_heartbeat
+                    .saturating_add(self.heartbeat_interval as i64)
+    }
+
+    /// Fails unless `expected`, if given, is the current sequence number, then moves to
+    /// the next one, so that a trader's updates only apply in the order they were sent.
+    pub fn bump_sequence(&mut self, expected: Option<u64>) -> Result<()> {
+        require!(
+            expected.is_none_or(|sequence| sequence == self.sequence),
+            ClobError::SequenceMismatch
+        );
+        self.sequence += 1;
+        Ok(())
+    }
+
+    /// Counts an order placed in `slot` against [`RiskLimits::max_orders_per_slot`].
+    pub fn count_order(&mut self, slot: u64) -> Result<()> {
+        if slot != self.last_order_slot {
+            self.last_order_slot = slot;
+            self.orders_in_slot = 0;
+        }
+        self.orders_in_slot = self.orders_in_slot.saturating_add(1);
+        let max = self.risk_limits.max_orders_per_slot;
+        require!(
+            max == 0 || self.orders_in_slot <= max,
+            ClobError::OrderRateLimitExceeded
+        );
+        Ok(())
+    }
+
+    pub fn exposure(&self) -> Exposure {
+        self.exposure_with(Side::Bid, 0, 0)
+    }
+
+    /// The exposure with `base_lots` more resting on `side`, worth `notional` native quote.
+    pub fn exposure_with(&self, side: Side, base_lots: u64, notional: u64) -> Exposure {
+        let (bid_lots, ask_lots) = match side {
+            Side::Bid => (base_lots as i128, 0),
+            Side::Ask => (0, base_lots as i128),
+        };
+        let net = self.net_base_lots as i128;
+        let clamp = |lots: i128| u64::try_from(lots.max(0)).unwrap_or(u64::MAX);
+        Exposure {
+            open_notional: self.open_notional.saturating_add(notional),
+            max_long: clamp(net + self.open_bid_lots as i128 + bid_lots),
+            max_short: clamp(self.open_ask_lots as i128 + ask_lots - net),
+        }
+    }
+
+    /// Fails if an exposure is over its cap and above what it was `before` the orders just
+    /// placed, so that orders which do not add to an exposure are always allowed.
+    pub fn check_risk_limits(&self, before: &Exposure) -> Result<()> {
+        self.check_exposure(before, &self.exposure())
+    }
+
+    /// Fails as [`OpenOrders::check_risk_limits`] would if `base_lots` more were resting on
+    /// `side` with their funds locked at `price`.
+    pub fn check_order_risk(
+        &self,
+        market: &Market,
+        side: Side,
+        price: u64,
+        base_lots: u64,
+    ) -> Result<()> {
+        let notional = market.notional_native(price, base_lots)?;
+        self.check_exposure(
+            &self.exposure(),
+            &self.exposure_with(side, base_lots, notional),
+        )
+    }
+
+    fn check_exposure(&self, before: &Exposure, after: &Exposure) -> Result<()> {
+        let allowed = |cap: u64, before: u64, after: u64| cap == 0 || after <= cap.max(before);
+        let limits = &self.risk_limits;
+        require!(
+            allowed(
+                limits.max_open_notional,
+                before.open_notional,
+                after.open_notional
+            ),
+            ClobError::OpenNotionalLimitExceeded
+        );
+        require!(
+            allowed(limits.max_net_base_lots, before.max_long, after.max_long)
+                && allowed(limits.max_net_base_lots, before.max_short, after.max_short),
+            ClobError::PositionLimitExceeded
+        );
+        Ok(())
+    }
+
+    /// Tracks `base_lots` of an order entering the book with its funds locked at `price`.
+    pub fn track_resting(
+        &mut self,
+        market: &Market,
+        side: Side,
+        price: u64,
+        base_lots: u64,
+    ) -> Result<()> {
+        let notional = market.notional_native(price, base_lots)?;
+        self.open_notional = self.open_notional.saturating_add(notional);
+        let open_lots = match side {
+            Side::Bid => &mut self.open_bid_lots,
+            Side::Ask => &mut self.open_ask_lots,
+        };
+        *open_lots = open_lots.saturating_add(base_lots);
+        Ok(())
+    }
+
+    /// Tracks `base_lots` of a resting order locked at `price` leaving the book.
+    fn untrack_resting(
+        &mut self,
+        market: &Market,
+        side: Side,
+        price: u64,
+        base_lots: u64,
+    ) -> Result<()> {
+        let notional = market.notional_native(price, base_lots)?;
+        self.open_notional = self.open_notional.saturating_sub(notional);
+        let open_lots = match side {
+            Side::Bid => &mut self.open_bid_lots,
+            Side::Ask => &mut self.open_ask_lots,
+        };
+        *open_lots = open_lots.saturating_sub(base_lots);
+        Ok(())
+    }
+
+    /// Tracks `base_lots` bought or sold, as taker or maker.
+    pub fn track_fill(&mut self, side: Side, base_lots: u64) {
+        let lots = i64::try_from(base_lots).unwrap_or(i64::MAX);
+        self.net_base_lots = match side {
+            Side::Bid => self.net_base_lots.saturating_add(lots),
+            Side::Ask => self.net_base_lots.saturating_sub(lots),
+        };
+    }
+
+    pub fn find_order(&self, order_id: u64) -> Option<&OpenOrder> {
+        self.orders.iter().find(|o| o.order_id == order_id)
+    }
+
+    pub fn find_order_mut(&mut self, order_id: u64) -> Option<&mut OpenOrder> {
+        self.orders.iter_mut().find(|o| o.order_id == order_id)
+    }
+
+    pub fn add_order(&mut self, order: OpenOrder) -> Result<()> {
+        require!(
+            self.orders.len() < Self::MAX_ORDERS,
+            ClobError::TooManyOpenOrders
+        );
+        self.orders.push(order);
+        Ok(())
+    }
+
+    /// Removes an order that left the book and refunds its bond to `bond_free`.
+    pub fn remove_order(&mut self, order_id: u64) -> Option<OpenOrder> {
+        let index = self.orders.iter().position(|o| o.order_id == order_id)?;
+        let order = self.orders.remove(index);
+        self.bond_free = self.bond_free.saturating_add(order.bond);
+        Some(order)
+    }
+
+    /// Whether a member of `group_id` has been filled, as recorded by `consume_events` or
+    /// still pending in `event_queue`. `key` is this account's address.
+    pub fn is_group_filled(&self, key: Pubkey, event_queue: &EventQueue, group_id: u64) -> bool {
+        self.filled_groups.contains(&group_id)
+            || event_queue.events.iter().any(|event| match event {
+                Event::Fill(fill) => {
+                    fill.maker == key
+                        && self
+                            .find_order(fill.maker_order_id)
+                            .is_some_and(|o| o.group_id == group_id)
+                }
+                Event::Out(_) => false,
+            })
+    }
+
+    /// Records a fill of a member of `group_id`, forgetting the oldest group when full.
+    pub fn record_filled_group(&mut self, group_id: u64) {
+        if self.filled_groups.contains(&group_id) {
+            return;
+        }
+        if self.filled_groups.len() >= Self::MAX_FILLED_GROUPS {
+            self.filled_groups.remove(0);
+        }
+        self.filled_groups.push(group_id);
+    }
+
+    /// Moves the funds backing `base_lots` of a resting order from locked to free.
+    pub fn unlock(
+        &mut self,
+        market: &Market,
+        side: Side,
+        price: u64,
+        base_lots: u64,
+    ) -> Result<()> {
+        let amount = match side {
+            Side::Bid => market.notional_native(price, base_lots)?,
+            Side::Ask => market.base_lots_to_native(base_lots)?,
+        };
+        self.untrack_resting(market, side, price, base_lots)?;
+        self.unlock_native(side, amount)
+    }
+
+    /// Moves `amount` native units, quote for bids and base for asks, from locked to free.
+    pub fn unlock_native(&mut self, side: Side, amount: u64) -> Result<()> {
+        let (free, locked) = self.balances_mut(side);
+        *locked = sub(*locked, amount)?;
+        *free = add(*free, amount)?;
+        Ok(())
+    }
+
+    /// Locks `amount` native units, quote for bids and base for asks, spending the free
+    /// balance first. Returns the part the trader still has to deposit.
+    pub fn lock_native(&mut self, side: Side, amount: u64) -> Result<u64> {
+        let (free, locked) = self.balances_mut(side);
+        *locked = add(*locked, amount)?;
+        let from_free = amount.min(*free);
+        *free -= from_free;
+        Ok(amount - from_free)
+    }
+
+    fn balances_mut(&mut self, side: Side) -> (&mut u64, &mut u64) {
+        match side {
+            Side::Bid => (&mut self.quote_free, &mut self.quote_locked),
+            Side::Ask => (&mut self.base_free, &mut self.base_locked),
+        }
+    }
+
+    /// Settles the maker side of a fill.
+    pub fn apply_fill(&mut self, market: &Market, fill: &FillEvent) -> Result<()> {
+        let base = market.base_lots_to_native(fill.quantity)?;
+        let quote = market.notional_native(fill.price, fill.quantity)?;
+        if let Some(group_id) = self
+            .find_order(fill.maker_order_id)
+            .map(|o| o.group_id)
+            .filter(|&g| g != 0)
+        {
+            self.record_filled_group(group_id);
+        }
+        // Pegged orders lock at their peg limit and may fill at a better price.
+        let locked_price = self
+            .find_order(fill.maker_order_id)
+            .map_or(fill.price, |o| o.price);
+        self.untrack_resting(market, fill.maker_side, locked_price, fill.quantity)?;
+        self.track_fill(fill.maker_side, fill.quantity);
+        match fill.maker_side {
+            Side::Bid => {
+                let locked = market.notional_native(locked_price, fill.quantity)?;
+                self.quote_locked = sub(self.quote_locked, locked)?;
+                self.quote_free = add(self.quote_free, sub(locked, quote)?)?;
+                self.base_free = add(self.base_free, base)?;
+            }
+            Side::Ask => {
+                self.base_locked = sub(self.base_locked, base)?;
+                self.quote_free = add(self.quote_free, quote)?;
+            }
+        }
+        if fill.maker_out {
+            self.remove_order(fill.maker_order_id);
+        }
+        Ok(())
+    }
+
+    /// Settles an order that left the book unfilled, returns the lamports of its bond
+    /// forfeited for leaving as it did.
+    pub fn apply_out(&mut self, market: &Market, out: &OutEvent) -> Result<u64> {
+        self.unlock(market, out.side, out.price, out.quantity)?;
+        let forfeited = self
+            .remove_order(out.order_id)
+            .map_or(0, |o| market.bond_forfeit(o.bond, out.reason));
+        self.bond_free = sub(self.bond_free, forfeited)?;
+        Ok(forfeited)
+    }
+}
+
+fn add(a: u64, b: u64) -> Result<u64> {
+    a.checked_add(b)
+        .ok_or_else(|| error!(ClobError::MathOverflow))
+}
+
+fn sub(a: u64, b: u64) -> Result<u64> {
+    a.checked_sub(b)
+        .ok_or_else(|| error!(ClobError::MathOverflow))
+}
@@ -0,0 +1,179 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ClobError;
+
+use super::Market;
+
+/// The Pyth oracle program, which must own the price accounts of `OracleKind::Pyth`.
+pub mod pyth_program {
+    anchor_lang::declare_id!("FsJ3A3u2vn5cTVodAjvy8kMkPUQZXpv5Q4Gp46h7BoM3");
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OracleKind {
+    /// No reference price, limit prices are not banded.
+    #[default]
+    None,
+    /// A Pyth v2 price account.
+    Pyth,
+    /// An [`InternalOracle`] account updated by its authority.
+    Internal,
+}
+
+/// Reference price pushed by a trusted authority, in the same format as Pyth:
+/// `price * 10^expo` quote tokens per base token.
+#[account]
+#[derive(Debug, Default)]
+pub struct InternalOracle {
+    pub authority: Pubkey,
+    pub price: i64,
+    pub expo: i32,
+    pub last_update_timestamp: i64,
+}
+
+impl InternalOracle {
+    pub const LEN: usize = 32 + 8 + 4 + 8;
+}
+
+/// A price read from an oracle account, `price * 10^expo` quote tokens per base token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub expo: i32,
+    pub timestamp: i64,
+}
+
+impl OraclePrice {
+    pub fn load(kind: OracleKind, account: &AccountInfo) -> Result<OraclePrice> {
+        match kind {
+            OracleKind::None => err!(ClobError::InvalidOracle),
+            OracleKind::Pyth => {
+                require_keys_eq!(*account.owner, pyth_program::ID, ClobError::InvalidOracle);
+                pyth::load(&account.try_borrow_data()?)
+            }
+            OracleKind::Internal => {
+                let oracle = Account::<InternalOracle>::try_from(account)
+                    .map_err(|_| error!(ClobError::InvalidOracle))?;
+                Ok(OraclePrice {
+                    price: oracle.price,
+                    expo: oracle.expo,
+                    timestamp: oracle.last_update_timestamp,
+                })
+            }
+        }
+    }
+
+    /// Reads an oracle account's data without checking its owner, for off-chain readers
+    /// that have already looked the account up by address.
+    pub fn from_data(kind: OracleKind, mut data: &[u8]) -> Result<OraclePrice> {
+        match kind {
+            OracleKind::None => err!(ClobError::InvalidOracle),
+            OracleKind::Pyth => pyth::load(data),
+            OracleKind::Internal => {
+                let oracle = InternalOracle::try_deserialize(&mut data)
+                    .map_err(|_| error!(ClobError::InvalidOracle))?;
+                Ok(OraclePrice {
+                    price: oracle.price,
+                    expo: oracle.expo,
+                    timestamp: oracle.last_update_timestamp,
+                })
+            }
+        }
+    }
+
+    /// The price in quote lots per base lot of `market`, rounded down.
+    pub fn to_lots(self, market: &Market) -> Result<u64> {
+        require!(self.price > 0, ClobError::InvalidOracle);
+        let overflow = || error!(ClobError::MathOverflow);
+        let mut num = (self.price as u128)
+            .checked_mul(market.base_lot_size as u128)
+            .ok_or_else(overflow)?;
+        let mut den = market.quote_lot_size as u128;
+        let expo = self.expo + market.quote_decimals as i32 - market.base_decimals as i32;
+        let scale = 10u128
+            .checked_pow(expo.unsigned_abs())
+            .ok_or_else(overflow)?;
+        if expo >= 0 {
+            num = num.checked_mul(scale).ok_or_else(overflow)?;
+        } else {
+            den = den.checked_mul(scale).ok_or_else(overflow)?;
+        }
+        u64::try_from(num / den).map_err(|_| overflow())
+    }
+}
+
+impl Market {
+    /// The oracle price in quote lots per base lot, `None` if the market has no oracle.
+    /// The oracle is found among `accounts` by address, and required there whenever the
+    /// market has one configured.
+    pub fn oracle_price(&self, accounts: &[AccountInfo], now: i64) -> Result<Option<u64>> {
+        if self.oracle_kind == OracleKind::None {
+            return Ok(None);
+        }
+        let oracle = accounts
+            .iter()
+            .find(|info| *info.key == self.oracle)
+            .ok_or_else(|| error!(ClobError::InvalidOracle))?;
+
+        let reference = OraclePrice::load(self.oracle_kind, oracle)?;
+        require!(
+            now.saturating_sub(reference.timestamp) <= self.oracle_max_staleness as i64,
+            ClobError::StaleOracle
+        );
+        reference.to_lots(self).map(Some)
+    }
+
+    /// Rejects `price` if it is further than `price_band_bps` from `oracle_price`, see
+    /// [`Market::oracle_price`].
+    pub fn check_price_band(&self, oracle_price: Option<u64>, price: u64) -> Result<()> {
+        let reference = match oracle_price {
+            Some(reference) => reference as u128,
+            None => return Ok(()),
+        };
+        let band = reference * self.price_band_bps as u128 / 10_000;
+        let price = price as u128;
+        require!(
+            price + band >= reference && price <= reference + band,
+            ClobError::PriceOutsideBand
+        );
+        Ok(())
+    }
+}
+
+/// Just enough of the Pyth v2 price account layout to read the aggregate price.
+mod pyth {
+    use super::*;
+
+    const MAGIC: u32 = 0xa1b2_c3d4;
+    const VERSION: u32 = 2;
+    const ACCOUNT_TYPE_PRICE: u32 = 3;
+    const STATUS_TRADING: u32 = 1;
+
+    const EXPO_OFFSET: usize = 20;
+    const TIMESTAMP_OFFSET: usize = 96;
+    const AGG_PRICE_OFFSET: usize = 208;
+    const AGG_STATUS_OFFSET: usize = 224;
+
+    fn read<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+        data.get(offset..offset + N)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| error!(ClobError::InvalidOracle))
+    }
+
+    pub fn load(data: &[u8]) -> Result<OraclePrice> {
+        let u32_at = |offset| read::<4>(data, offset).map(u32::from_le_bytes);
+        require!(
+            u32_at(0)? == MAGIC && u32_at(4)? == VERSION && u32_at(8)? == ACCOUNT_TYPE_PRICE,
+            ClobError::InvalidOracle
+        );
+        require!(
+            u32_at(AGG_STATUS_OFFSET)? == STATUS_TRADING,
+            ClobError::StaleOracle
+        );
+        Ok(OraclePrice {
+            price: i64::from_le_bytes(read(data, AGG_PRICE_OFFSET)?),
+            expo: i32::from_le_bytes(read(data, EXPO_OFFSET)?),
+            timestamp: i64::from_le_bytes(read(data, TIMESTAMP_OFFSET)?),
+        })
+    }
+}
@@ -0,0 +1,133 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ClobError;
+
+use super::{OrderType, Side};
+
+/// Price a trigger order watches.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TriggerSource {
+    /// The market's oracle price.
+    #[default]
+    Oracle,
+    /// The price of the market's most recent fill.
+    LastTrade,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TriggerCondition {
+    /// Fires once the price is at or above the trigger price, as for take-profit sells
+    /// and stop buys.
+    #[default]
+    Above,
+    /// Fires once the price is at or below the trigger price, as for stop-loss sells.
+    Below,
+}
+
+/// An order kept off the book until its trigger fires.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TriggerOrder {
+    /// Drawn from the market's order id sequence.
+    pub trigger_id: u64,
+    pub client_order_id: u64,
+    pub side: Side,
+    /// `ImmediateOrCancel` for a market order bounded by `price`, or `Limit`.
+    pub order_type: OrderType,
+    /// Limit price of the order placed when the trigger fires.
+    pub price: u64,
+    pub max_base_lots: u64,
+    pub source: TriggerSource,
+    pub condition: TriggerCondition,
+    /// Quote lots per base lot.
+    pub trigger_price: u64,
+    /// Native units locked on the `OpenOrders` account: quote for bids, including the
+    /// worst case taker fee, base for asks.
+    pub locked: u64,
+    /// See [`crate::state::Order::group_id`].
+    pub group_id: u64,
+}
+
+impl TriggerOrder {
+    pub const LEN: usize = 8 + 8 + 1 + 1 + 8 + 8 + 1 + 1 + 8 + 8 + 8;
+
+    /// Whether the trigger fires at the given prices, see [`crate::state::Market::oracle_price`].
+    pub fn is_triggered(&self, oracle_price: Option<u64>, last_trade_price: u64) -> bool {
+        let price = match self.source {
+            TriggerSource::Oracle => oracle_price,
+            TriggerSource::LastTrade => (last_trade_price > 0).then_some(last_trade_price),
+        };
+        price.is_some_and(|price| match self.condition {
+            TriggerCondition::Above => price >= self.trigger_price,
+            TriggerCondition::Below => price <= self.trigger_price,
+        })
+    }
+}
+
+/// A trader's trigger orders on one market, executed by anyone through `execute_triggers`.
+///
+/// Every trigger order holds [`TriggerOrders::EXECUTION_REWARD`] lamports on this account,
+/// paid to whoever executes it and refunded to the owner on cancel.
+#[account]
+#[derive(Debug, Default)]
+pub struct TriggerOrders {
+    pub market: Pubkey,
+    pub open_orders: Pubkey,
+    pub triggers: Vec<TriggerOrder>,
+}
+
+impl TriggerOrders {
+    pub const MAX_TRIGGERS: usize = 8;
+    pub const LEN: usize = 32 + 32 + 4 + Self::MAX_TRIGGERS * TriggerOrder::LEN;
+    /// Lamports paid per executed trigger order.
+    pub const EXECUTION_REWARD: u64 = 10_000;
+
+    pub fn insert(&mut self, trigger: TriggerOrder) -> Result<()> {
+        require!(
+            self.triggers.len() < Self::MAX_TRIGGERS,
+            ClobError::TooManyTriggers
+        );
+        self.triggers.push(trigger);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, trigger_id: u64) -> Option<TriggerOrder> {
+        let index = self
+            .triggers
+            .iter()
+            .position(|t| t.trigger_id == trigger_id)?;
+        Some(self.triggers.remove(index))
+    }
+
+    pub fn find_mut(&mut self, trigger_id: u64) -> Option<&mut TriggerOrder> {
+        self.triggers
+            .iter_mut()
+            .find(|t| t.trigger_id == trigger_id)
+    }
+
+    /// Removes and returns the trigger orders matching `predicate`, oldest first.
+    pub fn take_where(
+        &mut self,
+        predicate: impl FnMut(&TriggerOrder) -> bool,
+    ) -> Vec<TriggerOrder> {
+        let (taken, kept) = std::mem::take(&mut self.triggers)
+            .into_iter()
+            .partition(predicate);
+        self.triggers = kept;
+        taken
+    }
+
+    /// Puts back trigger orders taken out but not executed, keeping the oldest first.
+    pub fn restore(&mut self, triggers: impl IntoIterator<Item = TriggerOrder>) {
+        self.triggers.extend(triggers);
+        self.triggers.sort_by_key(|t| t.trigger_id);
+    }
+
+    /// Removes and returns the trigger orders that fire at the given prices, oldest first.
+    pub fn take_triggered(
+        &mut self,
+        oracle_price: Option<u64>,
+        last_trade_price: u64,
+    ) -> Vec<TriggerOrder> {
+        self.take_where(|t| t.is_triggered(oracle_price, last_trade_price))
+    }
+}
@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ClobError;
+
+use super::{Market, Side};
+
+/// A parent order executed in immediate-or-cancel slices of `slice_base_lots`, one every
+/// `interval_seconds`, by anyone calling `execute_twap_slice`.
+///
+/// The whole order is funded up front: the funds for what is left are locked on the
+/// `OpenOrders` account, and the account holds [`TwapOrder::SLICE_REWARD`] lamports per
+/// slice for whoever executes it. The unfilled part of a slice is not retried.
+#[account]
+#[derive(Debug, Default)]
+pub struct TwapOrder {
+    pub market: Pubkey,
+    pub open_orders: Pubkey,
+    pub side: Side,
+    /// Limit price of every slice, in quote lots per base lot.
+    pub price: u64,
+    pub total_base_lots: u64,
+    pub slice_base_lots: u64,
+    pub interval_seconds: u32,
+    pub client_order_id: u64,
+    /// Base lots not sliced yet.
+    pub remaining_base_lots: u64,
+    pub base_lots_filled: u64,
+    /// Unix timestamp from which the next slice can be executed.
+    pub next_slice_timestamp: i64,
+    /// Native units locked for the remaining slices, see [`TwapOrder::required_funds`].
+    pub locked: u64,
+}
+
+impl TwapOrder {
+    pub const LEN: usize = 32 + 32 + 1 + 8 + 8 + 8 + 4 + 8 + 8 + 8 + 8 + 8;
+    /// Lamports paid per executed slice.
+    pub const SLICE_REWARD: u64 = 10_000;
+
+    /// Slices left to execute.
+    pub fn slices_left(&self) -> u64 {
+        self.remaining_base_lots.div_ceil(self.slice_base_lots)
+    }
+
+    /// Funds for the remaining slices, each paying its own rounded-up taker fee, so that
+    /// what a slice does not spend always covers the ones after it.
+    pub fn required_funds(&self, market: &Market) -> Result<u64> {
+        let full_slices = self.remaining_base_lots / self.slice_base_lots;
+        let last_slice = self.remaining_base_lots % self.slice_base_lots;
+        market
+            .order_funds(self.side, self.price, self.slice_base_lots)?
+            .checked_mul(full_slices)
+            .and_then(|v| {
+                v.checked_add(market.order_funds(self.side, self.price, last_slice).ok()?)
+            })
+            .ok_or_else(|| error!(ClobError::MathOverflow))
+    }
+}
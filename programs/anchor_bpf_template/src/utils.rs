@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer as SystemTransfer};
+use anchor_spl::token::{self, TokenAccount, Transfer};
+
+use crate::errors::ClobError;
+
+/// Moves `amount` tokens out of a trader-owned account, signed by the trader.
+pub fn deposit<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &Account<'info, TokenAccount>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    require!(from.amount >= amount, ClobError::InsufficientFunds);
+    token::transfer(
+        CpiContext::new(
+            token_program.clone(),
+            Transfer {
+                from: from.to_account_info(),
+                to: to.clone(),
+                authority: authority.clone(),
+            },
+        ),
+        amount,
+    )
+}
+
+/// Moves `amount` tokens out of a market vault, signed by the market authority PDA.
+pub fn withdraw<'info>(
+    token_program: &AccountInfo<'info>,
+    vault: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    market_authority: &AccountInfo<'info>,
+    authority_seeds: &[&[u8]],
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            Transfer {
+                from: vault.clone(),
+                to: to.clone(),
+                authority: market_authority.clone(),
+            },
+            &[authority_seeds],
+        ),
+        amount,
+    )
+}
+
+/// Moves `amount` lamports out of a system account, signed by it.
+pub fn deposit_lamports<'info>(
+    system_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    system_program::transfer(
+        CpiContext::new(
+            system_program.clone(),
+            SystemTransfer {
+                from: from.clone(),
+                to: to.clone(),
+            },
+        ),
+        amount,
+    )
+}
+
+/// Moves `amount` lamports out of an account owned by this program.
+pub fn pay_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    let overflow = || error!(ClobError::MathOverflow);
+    let mut from_lamports = from.try_borrow_mut_lamports()?;
+    let mut to_lamports = to.try_borrow_mut_lamports()?;
+    **from_lamports = from_lamports.checked_sub(amount).ok_or_else(overflow)?;
+    **to_lamports = to_lamports.checked_add(amount).ok_or_else(overflow)?;
+    Ok(())
+}
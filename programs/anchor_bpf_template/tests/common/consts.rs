@@ -1,3 +1,10 @@
 pub mod solend {
     solana_sdk::declare_id!("So1endDq2YkqhipRh3WViPa8hdiSpxWy6z3Z6tMCpAo");
 }
+
+/// One base token, the fixtures' base mint has 9 decimals.
+pub const BASE: u64 = 1_000_000_000;
+/// One quote token, the fixtures' quote mint has 6 decimals.
+pub const QUOTE: u64 = 1_000_000;
+/// The base lot size of `default_market_args`.
+pub const BASE_LOT: u64 = 1_000_000;
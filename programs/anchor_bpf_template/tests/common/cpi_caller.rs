@@ -0,0 +1,132 @@
+//! A minimal vault program that trades on the order book through CPI, as an integrating
+//! program would: its `VAULT_SEED` PDA owns an `OpenOrders` account and its token
+//! accounts, and signs every call. The `OrderSummary` an order returns is written to a
+//! record account so that tests can read it.
+
+use anchor_bpf_template::{interface::MarketAccounts, OrderParams, Side, SwapArgs};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+
+use super::fixtures::MarketAddresses;
+
+declare_id!("Cm2RQHpsn6bB9jU22iBpHLwjVdASHuMzFFLdBfTMovYT");
+
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/// Room for one borsh-serialized `OrderSummary`.
+pub const RECORD_LEN: usize = 64;
+
+pub fn vault_authority() -> Pubkey {
+    Pubkey::find_program_address(&[VAULT_SEED], &ID).0
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub enum VaultInstruction {
+    InitOpenOrders,
+    PlaceOrder(OrderParams),
+    CancelOrder(u64),
+    Swap(SwapArgs),
+}
+
+/// Accounts: the order book program, the vault authority, the vault's `OpenOrders`, the
+/// market's accounts in [`MarketAccounts`] order, the vault's base and quote token
+/// accounts, then the record account, owned by this program.
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let clob_program = accounts[0].clone();
+    let vault_authority = accounts[1].clone();
+    let open_orders = accounts[2].clone();
+    let market = MarketAccounts {
+        market: accounts[3].clone(),
+        bids: accounts[4].clone(),
+        asks: accounts[5].clone(),
+        event_queue: accounts[6].clone(),
+        base_vault: accounts[7].clone(),
+        quote_vault: accounts[8].clone(),
+        market_authority: accounts[9].clone(),
+        token_program: accounts[10].clone(),
+        system_program: accounts[11].clone(),
+    };
+    let (base_account, quote_account) = (accounts[12].clone(), accounts[13].clone());
+    let record = &accounts[14];
+    let bump = Pubkey::find_program_address(&[VAULT_SEED], &ID).1;
+    let seeds: &[&[&[u8]]] = &[&[VAULT_SEED, &[bump]]];
+
+    let summary = match VaultInstruction::try_from_slice(data)? {
+        VaultInstruction::InitOpenOrders => {
+            let accounts = market.init_open_orders(vault_authority, open_orders);
+            anchor_bpf_template::cpi::init_open_orders(CpiContext::new_with_signer(
+                clob_program,
+                accounts,
+                seeds,
+            ))?;
+            return Ok(());
+        }
+        VaultInstruction::PlaceOrder(params) => {
+            let deposit = match params.side {
+                Side::Bid => quote_account,
+                Side::Ask => base_account,
+            };
+            let accounts = market.place_order(vault_authority, open_orders, deposit, params.side);
+            anchor_bpf_template::cpi::place_order(
+                CpiContext::new_with_signer(clob_program, accounts, seeds),
+                params,
+                None,
+            )?
+        }
+        VaultInstruction::CancelOrder(order_id) => {
+            let accounts = market.cancel_order(vault_authority, open_orders);
+            anchor_bpf_template::cpi::cancel_order(
+                CpiContext::new_with_signer(clob_program, accounts, seeds),
+                order_id,
+            )?
+        }
+        VaultInstruction::Swap(args) => {
+            let accounts = market.swap(vault_authority, base_account, quote_account);
+            anchor_bpf_template::cpi::swap(
+                CpiContext::new_with_signer(clob_program, accounts, seeds),
+                args,
+            )?
+        }
+    };
+    summary
+        .get()
+        .serialize(&mut &mut record.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
+
+pub fn instruction(
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    base_account: &Pubkey,
+    quote_account: &Pubkey,
+    record: &Pubkey,
+    data: VaultInstruction,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(anchor_bpf_template::ID, false),
+        AccountMeta::new(vault_authority(), false),
+        AccountMeta::new(*open_orders, false),
+        AccountMeta::new(market.market, false),
+        AccountMeta::new(market.bids, false),
+        AccountMeta::new(market.asks, false),
+        AccountMeta::new(market.event_queue, false),
+        AccountMeta::new(market.base_vault, false),
+        AccountMeta::new(market.quote_vault, false),
+        AccountMeta::new_readonly(market.market_authority, false),
+        AccountMeta::new_readonly(spl_token::ID, false),
+        AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        AccountMeta::new(*base_account, false),
+        AccountMeta::new(*quote_account, false),
+        AccountMeta::new(*record, false),
+    ];
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: data.try_to_vec().unwrap(),
+    }
+}
@@ -1,7 +1,23 @@
-use super::{runner::test, setup::funded_kp, types::TestContext};
+use anchor_bpf_template::{
+    pda::find_market_authority_address,
+    state::{BookSide, EventQueue, Market, OpenOrders},
+    InitializeArgs, OrderParams, OrderType, Side,
+};
+pub use clob_sdk::MarketAddresses;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signer::Signer, system_instruction};
 
+use super::{
+    instructions,
+    runner::{test, token},
+    setup::{funded_kp, kp, KP},
+    types::TestContext,
+};
+
+#[allow(clippy::upper_case_acronyms)]
 pub enum ProgramDependency {
     SOLEND,
+    /// The vault program in [`super::cpi_caller`].
+    CpiCaller,
 }
 
 pub async fn setup_empty_market_with_dependencies(
@@ -11,13 +27,181 @@ pub async fn setup_empty_market_with_dependencies(
 
     let admin = funded_kp(&mut program, SOL::from(10.0));
 
-    let mut ctx = test::start(program, &admin).await;
+    test::start(program, &admin).await
+}
+
+pub const BASE_DECIMALS: u8 = 9;
+pub const QUOTE_DECIMALS: u8 = 6;
+
+/// 0.001 base per lot, 0.00001 quote per lot: a price of 2000 ticks is 20 quote per base.
+pub fn default_market_args() -> InitializeArgs {
+    InitializeArgs {
+        base_lot_size: 1_000_000,
+        quote_lot_size: 10,
+        tick_size: 1,
+        taker_fee_bps: 10,
+    }
+}
+
+pub struct MarketFixture {
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub addresses: MarketAddresses,
+}
+
+pub struct Trader {
+    pub kp: KP,
+    pub base_account: Pubkey,
+    pub quote_account: Pubkey,
+    pub open_orders: Pubkey,
+}
+
+/// Allocates a zeroed, rent-exempt account of `space` bytes owned by the program.
+pub fn create_program_account(
+    ctx: &TestContext,
+    payer: &Pubkey,
+    account: &Pubkey,
+    space: usize,
+) -> Instruction {
+    system_instruction::create_account(
+        payer,
+        account,
+        ctx.rent.minimum_balance(space),
+        space as u64,
+        &anchor_bpf_template::id(),
+    )
+}
+
+pub async fn setup_market(args: InitializeArgs) -> (TestContext, MarketFixture) {
+    setup_market_with_dependencies(args, &[]).await
+}
+
+pub async fn setup_market_with_dependencies(
+    args: InitializeArgs,
+    dependencies: &[ProgramDependency],
+) -> (TestContext, MarketFixture) {
+    let mut ctx = setup_empty_market_with_dependencies(dependencies).await;
+    let market = create_market(&mut ctx, args).await;
+    (ctx, market)
+}
+
+/// Creates the mints, vaults and accounts of a market administered by the context's
+/// initial market owner.
+pub async fn create_market(ctx: &mut TestContext, args: InitializeArgs) -> MarketFixture {
+    let admin = ctx.initial_market_owner.clone();
+
+    let base_mint = kp();
+    let quote_mint = kp();
+    token::create_mint(ctx, &base_mint, BASE_DECIMALS, &admin.pubkey()).await;
+    token::create_mint(ctx, &quote_mint, QUOTE_DECIMALS, &admin.pubkey()).await;
+
+    let market = kp();
+    let bids = kp();
+    let asks = kp();
+    let event_queue = kp();
+    let base_vault = kp();
+    let quote_vault = kp();
+    let (market_authority, _) = find_market_authority_address(&market.pubkey());
+    ctx.create_token_account(&base_vault, &base_mint.pubkey(), &market_authority)
+        .await
+        .unwrap();
+    ctx.create_token_account(&quote_vault, &quote_mint.pubkey(), &market_authority)
+        .await
+        .unwrap();
+
+    let addresses = MarketAddresses {
+        market: market.pubkey(),
+        bids: bids.pubkey(),
+        asks: asks.pubkey(),
+        event_queue: event_queue.pubkey(),
+        base_vault: base_vault.pubkey(),
+        quote_vault: quote_vault.pubkey(),
+        market_authority,
+        oracle: None,
+        midpoint_book: None,
+    };
+    let payer = admin.pubkey();
+    ctx.send(
+        &[
+            create_program_account(ctx, &payer, &addresses.market, 8 + Market::LEN),
+            create_program_account(ctx, &payer, &addresses.bids, 8 + BookSide::LEN),
+            create_program_account(ctx, &payer, &addresses.asks, 8 + BookSide::LEN),
+            create_program_account(ctx, &payer, &addresses.event_queue, 8 + EventQueue::LEN),
+            instructions::initialize(
+                &payer,
+                &base_mint.pubkey(),
+                &quote_mint.pubkey(),
+                &addresses,
+                args,
+            ),
+        ],
+        &[&market, &bids, &asks, &event_queue],
+    )
+    .await
+    .unwrap();
+
+    MarketFixture {
+        base_mint: base_mint.pubkey(),
+        quote_mint: quote_mint.pubkey(),
+        addresses,
+    }
+}
+
+/// A funded wallet with token accounts holding `base_amount`/`quote_amount` native units
+/// and an `OpenOrders` account on `market`.
+pub async fn new_trader(
+    ctx: &mut TestContext,
+    market: &MarketFixture,
+    base_amount: u64,
+    quote_amount: u64,
+) -> Trader {
+    let kp = ctx.new_keypair(SOL::from(1.0)).await;
+    let base_account = new_token_account(ctx, &market.base_mint, &kp.pubkey(), base_amount).await;
+    let quote_account =
+        new_token_account(ctx, &market.quote_mint, &kp.pubkey(), quote_amount).await;
+
+    let open_orders = self::kp();
+    ctx.send(
+        &[
+            create_program_account(
+                ctx,
+                &kp.pubkey(),
+                &open_orders.pubkey(),
+                8 + OpenOrders::LEN,
+            ),
+            instructions::init_open_orders(&kp.pubkey(), &market.addresses, &open_orders.pubkey()),
+        ],
+        &[&kp, &open_orders],
+    )
+    .await
+    .unwrap();
+
+    Trader {
+        kp,
+        base_account,
+        quote_account,
+        open_orders: open_orders.pubkey(),
+    }
+}
 
-    ctx
+/// A token account for `mint` owned by `owner`, holding `amount` native units.
+pub async fn new_token_account(
+    ctx: &mut TestContext,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let account = kp();
+    ctx.create_token_account(&account, mint, owner)
+        .await
+        .unwrap();
+    ctx.mint_to(mint, &account.pubkey(), amount).await.unwrap();
+    account.pubkey()
 }
 
 use solana_sdk::native_token::sol_to_lamports;
 
+#[allow(clippy::upper_case_acronyms)]
 pub struct SOL;
 impl SOL {
     pub fn one() -> u64 {
@@ -27,3 +211,15 @@ impl SOL {
         sol_to_lamports(amt)
     }
 }
+
+/// A limit order that never expires.
+pub fn order(side: Side, price: u64, max_base_lots: u64, client_order_id: u64) -> OrderParams {
+    OrderParams {
+        side,
+        price,
+        max_base_lots,
+        order_type: OrderType::Limit,
+        client_order_id,
+        expiry_timestamp: 0,
+    }
+}
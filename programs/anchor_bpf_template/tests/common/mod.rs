@@ -1,7 +1,12 @@
+// Each test binary compiles its own copy and only uses part of it.
+#![allow(dead_code)]
+
 pub mod consts;
+pub mod cpi_caller;
 pub mod fixtures;
-pub mod instructions;
 pub mod macros;
 pub mod runner;
 pub mod setup;
 pub mod types;
+
+pub use clob_sdk::instructions;
@@ -6,16 +6,19 @@ use arrayref::array_ref;
 use solana_program_test::BanksClientError;
 use solana_sdk::commitment_config::CommitmentLevel;
 use solana_sdk::hash::Hash;
+use solana_sdk::instruction::{Instruction, InstructionError};
 use solana_sdk::program_error::ProgramError;
 use solana_sdk::program_pack::Pack;
 use solana_sdk::signature::Keypair;
 use solana_sdk::signer::Signer;
-use solana_sdk::transaction::Transaction;
+use solana_sdk::transaction::{Transaction, TransactionError};
 use solana_sdk::transport::TransportError;
 use solana_sdk::{system_instruction, system_program};
 use spl_associated_token_account as ata;
 use spl_token::state::Mint;
 
+use anchor_bpf_template::errors::ClobError;
+
 use crate::common::setup::KP;
 
 use self::token::create_token_account;
@@ -25,7 +28,9 @@ use super::types::TestContext;
 pub mod test {
     use solana_program_test::{processor, ProgramTest};
 
-    use crate::common::{consts::solend, fixtures::ProgramDependency, types::TestContext};
+    use crate::common::{
+        consts::solend, cpi_caller, fixtures::ProgramDependency, types::TestContext,
+    };
 
     use super::*;
     pub fn program(dependencies: &[ProgramDependency]) -> ProgramTest {
@@ -34,11 +39,30 @@ pub mod test {
             anchor_bpf_template::ID,
             processor!(anchor_bpf_template::entry),
         );
+        // The BPF builds of the SPL programs bundled with program-test do not run on
+        // current toolchains, so use their native processors instead.
+        program_test.add_program(
+            "spl_token",
+            spl_token::id(),
+            processor!(spl_token::processor::Processor::process),
+        );
+        program_test.add_program(
+            "spl_associated_token_account",
+            ata::id(),
+            processor!(ata::processor::process_instruction),
+        );
 
         dependencies.iter().for_each(|dep| match dep {
             ProgramDependency::SOLEND => {
                 program_test.add_program("solend", solend::ID, None);
             }
+            ProgramDependency::CpiCaller => {
+                program_test.add_program(
+                    "cpi_caller",
+                    cpi_caller::ID,
+                    processor!(cpi_caller::process_instruction),
+                );
+            }
         });
         program_test
     }
@@ -87,7 +111,7 @@ pub mod state {
                 println!("Error {:?}", e);
                 TestError::CannotDeserialize
             }),
-            None => return Err(TestError::AccountNotFound),
+            None => Err(TestError::AccountNotFound),
         }
     }
     pub unsafe fn any_as_u8_slice<T: Sized>(p: &T) -> &[u8] {
@@ -105,7 +129,7 @@ pub mod state {
         let mut data: &[u8] = &account.data;
         let user: T = T::try_deserialize(&mut data).map_err(|_| TestError::CannotDeserialize)?;
 
-        return Ok(user);
+        Ok(user)
     }
 }
 
@@ -120,7 +144,7 @@ pub mod token {
     pub async fn create_ata(env: &mut TestContext, user: &KP, mint: &Pubkey) -> Pubkey {
         let address = ata::get_associated_token_address(&user.pubkey(), mint);
         let instruction =
-            ata::create_associated_token_account(&user.pubkey(), &user.pubkey(), mint);
+            ata::instruction::create_associated_token_account(&user.pubkey(), &user.pubkey(), mint);
         let transaction = Transaction::new_signed_with_payer(
             std::slice::from_ref(&instruction),
             Some(&user.pubkey()),
@@ -335,7 +359,7 @@ pub mod token {
         let transaction = Transaction::new_signed_with_payer(
             &[spl_token::instruction::transfer(
                 &spl_token::id(),
-                &from,
+                from,
                 to,
                 &signer.pubkey(),
                 &[],
@@ -366,7 +390,7 @@ pub mod token {
     }
 
     fn get_token_balance(data: &[u8]) -> u64 {
-        check_data_len(&data, spl_token::state::Account::get_packed_len()).unwrap();
+        check_data_len(data, spl_token::state::Account::get_packed_len()).unwrap();
         let amount = array_ref![data, 64, 8];
 
         u64::from_le_bytes(*amount)
@@ -385,6 +409,19 @@ pub mod token {
     }
 }
 
+pub fn assert_clob_error<T: std::fmt::Debug>(
+    result: Result<T, BanksClientError>,
+    expected: ClobError,
+) {
+    match result {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(code),
+        ))) => assert_eq!(code, u32::from(expected), "expected {:?}", expected),
+        other => panic!("expected {:?}, got {:?}", expected, other),
+    }
+}
+
 impl TestContext {
     pub async fn fast_forward_minutes(&mut self, minutes: u64) {
         self.fast_forward(Duration::from_secs(minutes * 60)).await
@@ -415,6 +452,31 @@ impl TestContext {
         }
     }
 
+    /// Signs with the market owner as fee payer plus `signers` and processes the transaction.
+    pub async fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let payer = self.initial_market_owner.clone();
+        let mut all_signers = vec![payer.as_ref()];
+        all_signers.extend(
+            signers
+                .iter()
+                .filter(|signer| signer.pubkey() != payer.pubkey()),
+        );
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &all_signers,
+            self.get_recent_blockhash().await,
+        );
+        self.context
+            .banks_client
+            .process_transaction_with_commitment(transaction, CommitmentLevel::Processed)
+            .await
+    }
+
     pub async fn get_recent_blockhash(&mut self) -> Hash {
         self.context
             .banks_client
@@ -500,7 +562,7 @@ impl TestContext {
         let acc = self
             .context
             .banks_client
-            .get_account(token_account.clone())
+            .get_account(*token_account)
             .await
             .unwrap()
             .unwrap();
@@ -517,7 +579,7 @@ impl TestContext {
     }
 
     pub fn get_token_balance(data: &[u8]) -> u64 {
-        Self::check_data_len(&data, spl_token::state::Account::get_packed_len()).unwrap();
+        Self::check_data_len(data, spl_token::state::Account::get_packed_len()).unwrap();
         let amount = array_ref![data, 64, 8];
 
         u64::from_le_bytes(*amount)
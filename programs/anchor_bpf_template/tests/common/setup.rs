@@ -1,7 +1,6 @@
 use std::sync::Arc;
 
-use anchor_lang::prelude::Pubkey;
-use solana_program_test::{BanksClient, ProgramTest, ProgramTestContext};
+use solana_program_test::{ProgramTest, ProgramTestContext};
 use solana_sdk::{
     account::{Account, AccountSharedData},
     signature::Keypair,
@@ -9,11 +8,6 @@ use solana_sdk::{
     system_program,
 };
 
-pub struct Env<'a> {
-    pub program_id: &'a Pubkey,
-    pub client: &'a mut BanksClient,
-}
-
 pub type KP = Arc<Keypair>;
 pub fn kp() -> KP {
     Arc::new(Keypair::new())
@@ -0,0 +1,367 @@
+mod common;
+use anchor_bpf_template::{
+    errors::ClobError,
+    state::{BookSide, Market, OpenOrders},
+    MarketStatus, OrderParams, OrderType, Side,
+};
+use common::{
+    consts::{BASE, BASE_LOT, QUOTE},
+    fixtures::{default_market_args, new_trader, order, setup_market},
+    instructions,
+    runner::{assert_clob_error, state},
+};
+use solana_program_test::tokio;
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn test_uncross_fills_crossing_orders_at_one_price() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let buyer = new_trader(&mut ctx, &market, 0, 100 * QUOTE).await;
+    let seller = new_trader(&mut ctx, &market, BASE, 0).await;
+
+    ctx.send(
+        &[instructions::set_market_status(
+            &admin.pubkey(),
+            &market.addresses,
+            MarketStatus::Auction,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    let bid = |params| {
+        instructions::place_order(
+            &buyer.kp.pubkey(),
+            &market.addresses,
+            &buyer.open_orders,
+            &buyer.quote_account,
+            params,
+            None,
+        )
+    };
+    let ask = |params| {
+        instructions::place_order(
+            &seller.kp.pubkey(),
+            &market.addresses,
+            &seller.open_orders,
+            &seller.base_account,
+            params,
+            None,
+        )
+    };
+    let ioc = OrderParams {
+        order_type: OrderType::ImmediateOrCancel,
+        ..order(Side::Bid, 2_100, 1, 9)
+    };
+    let res = ctx.send(&[bid(ioc)], &[&buyer.kp]).await;
+    assert_clob_error(res, ClobError::InvalidAuctionOrder);
+
+    // Crossing orders rest until the auction ends.
+    ctx.send(
+        &[
+            bid(order(Side::Bid, 2_100, 10, 1)),
+            bid(order(Side::Bid, 2_000, 5, 2)),
+        ],
+        &[&buyer.kp],
+    )
+    .await
+    .unwrap();
+    ctx.send(
+        &[
+            ask(order(Side::Ask, 1_950, 8, 1)),
+            ask(order(Side::Ask, 2_050, 6, 2)),
+        ],
+        &[&seller.kp],
+    )
+    .await
+    .unwrap();
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert_eq!(asks.orders.len(), 2);
+
+    // 2050 and 2100 both trade 10 lots with the same imbalance, the lower one wins.
+    let res = ctx
+        .send(
+            &[instructions::uncross_auction(
+                &buyer.kp.pubkey(),
+                &market.addresses,
+            )],
+            &[&buyer.kp],
+        )
+        .await;
+    assert_clob_error(res, ClobError::Unauthorized);
+    let uncross = instructions::uncross_auction(&admin.pubkey(), &market.addresses);
+    ctx.send(std::slice::from_ref(&uncross), &[&admin])
+        .await
+        .unwrap();
+
+    let state_market = state::get::<Market>(&mut ctx, market.addresses.market).await;
+    assert_eq!(state_market.status, MarketStatus::Active);
+    assert_eq!(state_market.last_trade_price, 2_050);
+    assert_eq!(state_market.fees_accrued, 0);
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert_eq!(bids.orders.len(), 1);
+    assert_eq!((bids.orders[0].price, bids.orders[0].quantity), (2_000, 5));
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert_eq!(asks.orders.len(), 1);
+    assert_eq!((asks.orders[0].price, asks.orders[0].quantity), (2_050, 4));
+
+    ctx.send(
+        &[instructions::consume_events(
+            &market.addresses,
+            &ctx.context.payer.pubkey(),
+            &[buyer.open_orders, seller.open_orders],
+            10,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let quote_lot = state_market.quote_lot_size;
+    let buyer_oo = state::get::<OpenOrders>(&mut ctx, buyer.open_orders).await;
+    assert_eq!(buyer_oo.base_free, 10 * BASE_LOT);
+    // The 2100 bid paid 2050 and gets the difference back.
+    assert_eq!(buyer_oo.quote_free, 50 * 10 * quote_lot);
+    assert_eq!(buyer_oo.quote_locked, 2_000 * 5 * quote_lot);
+    let seller_oo = state::get::<OpenOrders>(&mut ctx, seller.open_orders).await;
+    assert_eq!(seller_oo.quote_free, 2_050 * 10 * quote_lot);
+    assert_eq!(seller_oo.base_locked, 4 * BASE_LOT);
+
+    // A later slot keeps the retry from being deduplicated.
+    ctx.fast_forward_seconds(1).await;
+    let res = ctx.send(&[uncross], &[&admin]).await;
+    assert_clob_error(res, ClobError::NotInAuction);
+}
+
+#[tokio::test]
+async fn test_batch_mode_clears_each_batch_at_one_price() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let buyer = new_trader(&mut ctx, &market, 0, 100 * QUOTE).await;
+    let seller = new_trader(&mut ctx, &market, BASE, 0).await;
+
+    let set_batch = |batch_slots| {
+        instructions::set_batch_auction(&admin.pubkey(), &market.addresses, batch_slots)
+    };
+    ctx.send(&[set_batch(10)], &[&admin]).await.unwrap();
+
+    let ask = |price, max_base_lots, client_order_id| {
+        instructions::place_order(
+            &seller.kp.pubkey(),
+            &market.addresses,
+            &seller.open_orders,
+            &seller.base_account,
+            order(Side::Ask, price, max_base_lots, client_order_id),
+            None,
+        )
+    };
+    ctx.send(
+        &[instructions::place_order(
+            &buyer.kp.pubkey(),
+            &market.addresses,
+            &buyer.open_orders,
+            &buyer.quote_account,
+            order(Side::Bid, 2_000, 5, 1),
+            None,
+        )],
+        &[&buyer.kp],
+    )
+    .await
+    .unwrap();
+    ctx.send(&[ask(1_990, 3, 1)], &[&seller.kp]).await.unwrap();
+
+    let res = ctx
+        .send(&[instructions::clear_batch(&market.addresses)], &[])
+        .await;
+    assert_clob_error(res, ClobError::BatchNotDue);
+    ctx.fast_forward_seconds(1).await;
+    ctx.send(&[instructions::clear_batch(&market.addresses)], &[])
+        .await
+        .unwrap();
+
+    // Both prices trade 3 lots, the lower one wins without a previous trade.
+    let state_market = state::get::<Market>(&mut ctx, market.addresses.market).await;
+    assert_eq!(state_market.last_trade_price, 1_990);
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert_eq!(bids.orders[0].quantity, 2);
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert!(asks.orders.is_empty());
+
+    // Leaving batch mode clears what was collected, here nearest the last trade.
+    ctx.send(&[ask(1_995, 1, 2)], &[&seller.kp]).await.unwrap();
+    ctx.send(&[set_batch(0)], &[&admin]).await.unwrap();
+    let state_market = state::get::<Market>(&mut ctx, market.addresses.market).await;
+    assert_eq!(state_market.last_trade_price, 1_995);
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert_eq!(bids.orders[0].quantity, 1);
+
+    // Continuous matching again.
+    ctx.send(&[ask(2_000, 1, 3)], &[&seller.kp]).await.unwrap();
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert!(bids.orders.is_empty());
+}
+
+#[tokio::test]
+async fn test_uncross_fills_a_linked_bid_and_ask_of_one_trader() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let trader = new_trader(&mut ctx, &market, BASE, 100 * QUOTE).await;
+    let seller = new_trader(&mut ctx, &market, BASE, 0).await;
+    ctx.send(
+        &[instructions::set_market_status(
+            &admin.pubkey(),
+            &market.addresses,
+            MarketStatus::Auction,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    let place = |params: OrderParams| {
+        let deposit = match params.side {
+            Side::Bid => trader.quote_account,
+            Side::Ask => trader.base_account,
+        };
+        instructions::place_order(
+            &trader.kp.pubkey(),
+            &market.addresses,
+            &trader.open_orders,
+            &deposit,
+            params,
+            None,
+        )
+    };
+    ctx.send(
+        &[
+            place(order(Side::Bid, 2_000, 5, 1)),
+            place(order(Side::Ask, 1_990, 3, 2)),
+            place(order(Side::Ask, 2_050, 1, 3)),
+            instructions::place_order(
+                &seller.kp.pubkey(),
+                &market.addresses,
+                &seller.open_orders,
+                &seller.base_account,
+                order(Side::Ask, 2_000, 4, 1),
+                None,
+            ),
+        ],
+        &[&trader.kp, &seller.kp],
+    )
+    .await
+    .unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, trader.open_orders).await;
+    let order_ids = open_orders.orders.iter().map(|o| o.order_id).collect();
+    ctx.send(
+        &[instructions::link_orders(
+            &trader.kp.pubkey(),
+            &market.addresses,
+            &trader.open_orders,
+            None,
+            1,
+            order_ids,
+            vec![],
+        )],
+        &[&trader.kp],
+    )
+    .await
+    .unwrap();
+
+    // The trader's bid and best ask cross each other first, then the bid's last 2 lots
+    // take the seller's ask. The fills take both out of the group, whose 2050 ask is
+    // cancelled.
+    ctx.send(
+        &[instructions::uncross_auction(
+            &admin.pubkey(),
+            &market.addresses,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let state_market = state::get::<Market>(&mut ctx, market.addresses.market).await;
+    assert_eq!(state_market.last_trade_price, 2_000);
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert!(bids.orders.is_empty());
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert_eq!(asks.orders.len(), 1);
+    assert_eq!(asks.orders[0].owner, seller.open_orders);
+    assert_eq!((asks.orders[0].price, asks.orders[0].quantity), (2_000, 2));
+}
+
+#[tokio::test]
+async fn test_auction_ends_only_by_uncrossing() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let buyer = new_trader(&mut ctx, &market, 0, 100 * QUOTE).await;
+    let seller = new_trader(&mut ctx, &market, BASE, 0).await;
+    let set_status =
+        |status| instructions::set_market_status(&admin.pubkey(), &market.addresses, status);
+    ctx.send(&[set_status(MarketStatus::Auction)], &[&admin])
+        .await
+        .unwrap();
+    ctx.send(
+        &[
+            instructions::place_order(
+                &buyer.kp.pubkey(),
+                &market.addresses,
+                &buyer.open_orders,
+                &buyer.quote_account,
+                order(Side::Bid, 2_100, 5, 1),
+                None,
+            ),
+            instructions::place_order(
+                &seller.kp.pubkey(),
+                &market.addresses,
+                &seller.open_orders,
+                &seller.base_account,
+                order(Side::Ask, 2_000, 3, 1),
+                None,
+            ),
+            instructions::place_order(
+                &seller.kp.pubkey(),
+                &market.addresses,
+                &seller.open_orders,
+                &seller.base_account,
+                order(Side::Ask, 2_200, 1, 2),
+                None,
+            ),
+        ],
+        &[&buyer.kp, &seller.kp],
+    )
+    .await
+    .unwrap();
+
+    // Resuming continuous trading would leave the crossing orders resting.
+    for status in [
+        MarketStatus::Active,
+        MarketStatus::CancelOnly,
+        MarketStatus::Paused,
+    ] {
+        let res = ctx.send(&[set_status(status)], &[&admin]).await;
+        assert_clob_error(res, ClobError::AuctionNotUncrossed);
+    }
+    let state_market = state::get::<Market>(&mut ctx, market.addresses.market).await;
+    assert_eq!(state_market.status, MarketStatus::Auction);
+
+    ctx.send(
+        &[instructions::uncross_auction(
+            &admin.pubkey(),
+            &market.addresses,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert_eq!((bids.orders[0].price, bids.orders[0].quantity), (2_100, 2));
+    assert_eq!(asks.orders.len(), 1);
+    assert!(bids.orders[0].price < asks.orders[0].price);
+    // A later slot keeps the retry from being deduplicated.
+    ctx.fast_forward_seconds(1).await;
+    ctx.send(&[set_status(MarketStatus::CancelOnly)], &[&admin])
+        .await
+        .unwrap();
+}
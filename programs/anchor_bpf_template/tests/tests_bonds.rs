@@ -0,0 +1,145 @@
+mod common;
+use anchor_bpf_template::{
+    errors::ClobError,
+    state::{Market, OpenOrders},
+    OrderParams, OrderType, Side,
+};
+use common::{
+    fixtures::{default_market_args, new_trader, setup_market},
+    instructions,
+    runner::{assert_clob_error, state},
+    types::TestContext,
+};
+use solana_program_test::tokio;
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+
+const BOND: u64 = 10_000_000;
+
+fn ask(price: u64, expiry_timestamp: i64) -> OrderParams {
+    OrderParams {
+        side: Side::Ask,
+        price,
+        max_base_lots: 1,
+        order_type: OrderType::Limit,
+        client_order_id: price,
+        expiry_timestamp,
+    }
+}
+
+async fn balance(ctx: &mut TestContext, key: Pubkey) -> u64 {
+    ctx.context.banks_client.get_balance(key).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_order_bonds_are_refunded_or_forfeited() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let maker = new_trader(&mut ctx, &market, 1_000_000_000, 0).await;
+    let taker = new_trader(&mut ctx, &market, 0, 1_000_000_000).await;
+
+    let set_bond = |forfeit_bps| {
+        instructions::set_order_bond(&admin.pubkey(), &market.addresses, BOND, forfeit_bps)
+    };
+    let res = ctx.send(&[set_bond(10_001)], &[&admin]).await;
+    assert_clob_error(res, ClobError::InvalidOrderBond);
+    ctx.send(&[set_bond(5_000)], &[&admin]).await.unwrap();
+
+    let market_lamports = balance(&mut ctx, market.addresses.market).await;
+    let expiry = ctx.get_now_timestamp().await as i64 + 5;
+    for params in [ask(2_000, 0), ask(2_010, expiry)] {
+        ctx.send(
+            &[instructions::place_order(
+                &maker.kp.pubkey(),
+                &market.addresses,
+                &maker.open_orders,
+                &maker.base_account,
+                params,
+                None,
+            )],
+            &[&maker.kp],
+        )
+        .await
+        .unwrap();
+    }
+    assert_eq!(
+        balance(&mut ctx, market.addresses.market).await - market_lamports,
+        2 * BOND
+    );
+
+    // A cancel refunds the bond in full.
+    let open_orders = state::get::<OpenOrders>(&mut ctx, maker.open_orders).await;
+    assert!(open_orders.orders.iter().all(|o| o.bond == BOND));
+    ctx.send(
+        &[instructions::cancel_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            open_orders.orders[0].order_id,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, maker.open_orders).await;
+    assert_eq!(open_orders.bond_free, BOND);
+
+    // A taker finds the other expired, whoever settles its removal gets half its bond.
+    ctx.fast_forward_seconds(10).await;
+    ctx.send(
+        &[instructions::place_order(
+            &taker.kp.pubkey(),
+            &market.addresses,
+            &taker.open_orders,
+            &taker.quote_account,
+            OrderParams {
+                side: Side::Bid,
+                order_type: OrderType::ImmediateOrCancel,
+                ..ask(2_010, 0)
+            },
+            None,
+        )],
+        &[&taker.kp],
+    )
+    .await
+    .unwrap();
+    let cranker = Pubkey::new_unique();
+    ctx.send(
+        &[instructions::consume_events(
+            &market.addresses,
+            &cranker,
+            &[maker.open_orders],
+            10,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(balance(&mut ctx, cranker).await, BOND / 2);
+    let open_orders = state::get::<OpenOrders>(&mut ctx, maker.open_orders).await;
+    assert!(open_orders.orders.is_empty());
+    assert_eq!(open_orders.bond_free, BOND + BOND / 2);
+
+    let owner_lamports = balance(&mut ctx, maker.kp.pubkey()).await;
+    ctx.send(
+        &[instructions::settle_funds(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.base_account,
+            &maker.quote_account,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        balance(&mut ctx, maker.kp.pubkey()).await - owner_lamports,
+        BOND + BOND / 2
+    );
+    assert_eq!(
+        balance(&mut ctx, market.addresses.market).await,
+        market_lamports
+    );
+    let state_market = state::get::<Market>(&mut ctx, market.addresses.market).await;
+    assert_eq!(state_market.order_bond, BOND);
+}
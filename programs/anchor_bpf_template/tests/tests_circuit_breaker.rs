@@ -0,0 +1,278 @@
+mod common;
+use anchor_bpf_template::{
+    errors::ClobError,
+    state::{BookSide, Market, OpenOrders, TriggerOrders},
+    CircuitBreakerArgs, OrderType, Side, TriggerCondition, TriggerOrderParams, TriggerSource,
+};
+use common::{
+    consts::{BASE, QUOTE},
+    fixtures::{
+        create_program_account, default_market_args, new_trader, order, setup_market, MarketFixture,
+    },
+    instructions,
+    runner::{assert_clob_error, state},
+    setup::kp,
+    types::TestContext,
+};
+use solana_program_test::tokio;
+use solana_sdk::signer::Signer;
+
+/// A 10% move within a minute halts the market for five minutes.
+async fn set_breaker(ctx: &mut TestContext, market: &MarketFixture) {
+    let admin = ctx.initial_market_owner.clone();
+    ctx.send(
+        &[instructions::set_circuit_breaker(
+            &admin.pubkey(),
+            &market.addresses,
+            CircuitBreakerArgs {
+                move_bps: 1_000,
+                window_seconds: 60,
+                cooldown_seconds: 300,
+            },
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_large_move_halts_the_market_for_the_cooldown() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let maker = new_trader(&mut ctx, &market, BASE, 0).await;
+    let taker = new_trader(&mut ctx, &market, 0, 100 * QUOTE).await;
+
+    let breaker = |window_seconds| {
+        instructions::set_circuit_breaker(
+            &admin.pubkey(),
+            &market.addresses,
+            CircuitBreakerArgs {
+                move_bps: 1_000,
+                window_seconds,
+                cooldown_seconds: 300,
+            },
+        )
+    };
+    let res = ctx.send(&[breaker(0)], &[&admin]).await;
+    assert_clob_error(res, ClobError::InvalidCircuitBreaker);
+    ctx.send(&[breaker(60)], &[&admin]).await.unwrap();
+
+    let ask = |price, max_base_lots, client_order_id| {
+        instructions::place_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.base_account,
+            order(Side::Ask, price, max_base_lots, client_order_id),
+            None,
+        )
+    };
+    ctx.send(
+        &[ask(2_000, 1, 1), ask(2_300, 1, 2), ask(2_350, 5, 3)],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+
+    // The fill at 2300 is 15% above the first trade at 2000: it goes through, matching
+    // stops there and the rest of the bid is cancelled rather than rested.
+    let bid = |client_order_id| {
+        instructions::place_order(
+            &taker.kp.pubkey(),
+            &market.addresses,
+            &taker.open_orders,
+            &taker.quote_account,
+            order(Side::Bid, 2_400, 4, client_order_id),
+            None,
+        )
+    };
+    ctx.send(&[bid(1)], &[&taker.kp]).await.unwrap();
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert_eq!(asks.orders.len(), 1);
+    assert_eq!(asks.orders[0].quantity, 5);
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert!(bids.orders.is_empty());
+    let state_market = state::get::<Market>(&mut ctx, market.addresses.market).await;
+    assert_eq!(state_market.last_trade_price, 2_300);
+    assert!(state_market.halted_until > 0);
+
+    // Cancel-only during the cool-down.
+    let res = ctx.send(&[bid(2)], &[&taker.kp]).await;
+    assert_clob_error(res, ClobError::MarketNotActive);
+    let open_orders = state::get::<OpenOrders>(&mut ctx, maker.open_orders).await;
+    let resting = open_orders
+        .orders
+        .iter()
+        .find(|o| o.client_order_id == 3)
+        .unwrap();
+    ctx.send(
+        &[instructions::cancel_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            resting.order_id,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+
+    ctx.fast_forward_seconds(300).await;
+    ctx.send(&[ask(2_300, 1, 4)], &[&maker.kp]).await.unwrap();
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert_eq!(asks.orders.len(), 1);
+    assert_eq!(asks.orders[0].price, 2_300);
+}
+
+#[tokio::test]
+async fn test_batch_stops_at_the_order_that_trips() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    set_breaker(&mut ctx, &market).await;
+    let maker = new_trader(&mut ctx, &market, BASE, 0).await;
+    let taker = new_trader(&mut ctx, &market, 0, 100 * QUOTE).await;
+
+    let ask = |price, max_base_lots, client_order_id| {
+        instructions::place_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.base_account,
+            order(Side::Ask, price, max_base_lots, client_order_id),
+            None,
+        )
+    };
+    ctx.send(
+        &[ask(2_000, 1, 1), ask(2_300, 1, 2), ask(2_350, 5, 3)],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+
+    // The first bid trades at 2000, the second at 2300 trips the breaker and the third,
+    // which would have lifted the ask at 2350, is not placed.
+    ctx.send(
+        &[instructions::batch_orders(
+            &taker.kp.pubkey(),
+            &market.addresses,
+            &taker.open_orders,
+            &taker.base_account,
+            &taker.quote_account,
+            vec![],
+            vec![
+                order(Side::Bid, 2_000, 1, 1),
+                order(Side::Bid, 2_300, 1, 2),
+                order(Side::Bid, 2_350, 1, 3),
+            ],
+            None,
+        )],
+        &[&taker.kp],
+    )
+    .await
+    .unwrap();
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert_eq!(asks.orders.len(), 1);
+    assert_eq!((asks.orders[0].price, asks.orders[0].quantity), (2_350, 5));
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert!(bids.orders.is_empty());
+    let state_market = state::get::<Market>(&mut ctx, market.addresses.market).await;
+    assert_eq!(state_market.last_trade_price, 2_300);
+    assert!(state_market.halted_until > 0);
+}
+
+#[tokio::test]
+async fn test_triggers_after_the_trip_stay_pending() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    set_breaker(&mut ctx, &market).await;
+    let maker = new_trader(&mut ctx, &market, 0, 100 * QUOTE).await;
+    let seller = new_trader(&mut ctx, &market, BASE, 0).await;
+
+    let bid = |price, max_base_lots, client_order_id| {
+        instructions::place_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.quote_account,
+            order(Side::Bid, price, max_base_lots, client_order_id),
+            None,
+        )
+    };
+    ctx.send(
+        &[
+            bid(2_000, 1, 1),
+            bid(1_700, 5, 2),
+            instructions::place_order(
+                &seller.kp.pubkey(),
+                &market.addresses,
+                &seller.open_orders,
+                &seller.base_account,
+                order(Side::Ask, 2_000, 1, 1),
+                None,
+            ),
+        ],
+        &[&maker.kp, &seller.kp],
+    )
+    .await
+    .unwrap();
+
+    let trigger_orders = kp();
+    let stop_loss = |client_order_id| {
+        instructions::place_trigger_order(
+            &seller.kp.pubkey(),
+            &market.addresses,
+            &seller.open_orders,
+            &trigger_orders.pubkey(),
+            &seller.base_account,
+            TriggerOrderParams {
+                side: Side::Ask,
+                order_type: OrderType::ImmediateOrCancel,
+                price: 1_600,
+                max_base_lots: 1,
+                client_order_id,
+                source: TriggerSource::LastTrade,
+                condition: TriggerCondition::Below,
+                trigger_price: 2_100,
+            },
+        )
+    };
+    ctx.send(
+        &[
+            create_program_account(
+                &ctx,
+                &seller.kp.pubkey(),
+                &trigger_orders.pubkey(),
+                8 + TriggerOrders::LEN,
+            ),
+            instructions::init_trigger_orders(
+                &seller.kp.pubkey(),
+                &seller.open_orders,
+                &trigger_orders.pubkey(),
+            ),
+            stop_loss(1),
+            stop_loss(2),
+        ],
+        &[&seller.kp, &trigger_orders],
+    )
+    .await
+    .unwrap();
+
+    // Both stops are met, the first sells at 1700, 15% below 2000, and halts the market.
+    ctx.send(
+        &[instructions::execute_triggers(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &seller.open_orders,
+            &trigger_orders.pubkey(),
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert_eq!((bids.orders[0].price, bids.orders[0].quantity), (1_700, 4));
+    let triggers = state::get::<TriggerOrders>(&mut ctx, trigger_orders.pubkey()).await;
+    assert_eq!(triggers.triggers.len(), 1);
+    assert_eq!(triggers.triggers[0].client_order_id, 2);
+    let state_market = state::get::<Market>(&mut ctx, market.addresses.market).await;
+    assert!(state_market.halted_until > 0);
+}
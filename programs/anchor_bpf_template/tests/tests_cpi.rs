@@ -0,0 +1,167 @@
+mod common;
+use anchor_bpf_template::{
+    state::{BookSide, OpenOrders},
+    OrderParams, OrderSummary, OrderType, Side, SwapArgs,
+};
+use anchor_lang::AnchorDeserialize;
+use common::{
+    consts::{BASE, QUOTE},
+    cpi_caller::{self, VaultInstruction},
+    fixtures::{
+        create_program_account, default_market_args, new_token_account, new_trader,
+        setup_market_with_dependencies, ProgramDependency,
+    },
+    instructions,
+    runner::state,
+    setup::kp,
+    types::TestContext,
+};
+use solana_program_test::tokio;
+use solana_sdk::{pubkey::Pubkey, signer::Signer, system_instruction};
+use std::alloc::{GlobalAlloc, Layout, System};
+
+/// `solana-program-test` 1.10 holds a builtin caller's instruction context by reference
+/// across its CPIs, while the runtime may move it to a larger buffer. Nothing is freed in
+/// this binary so that the old copy stays readable.
+struct NeverFree;
+
+unsafe impl GlobalAlloc for NeverFree {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
+}
+
+#[global_allocator]
+static ALLOCATOR: NeverFree = NeverFree;
+
+async fn read_summary(ctx: &mut TestContext, record: &Pubkey) -> OrderSummary {
+    let account = ctx
+        .context
+        .banks_client
+        .get_account(*record)
+        .await
+        .unwrap()
+        .unwrap();
+    OrderSummary::deserialize(&mut &account.data[..]).unwrap()
+}
+
+#[tokio::test]
+async fn test_program_trades_through_cpi() {
+    let (mut ctx, market) =
+        setup_market_with_dependencies(default_market_args(), &[ProgramDependency::CpiCaller])
+            .await;
+    let vault_authority = cpi_caller::vault_authority();
+    let base_account = new_token_account(&mut ctx, &market.base_mint, &vault_authority, 0).await;
+    let quote_account =
+        new_token_account(&mut ctx, &market.quote_mint, &vault_authority, 10 * QUOTE).await;
+    let open_orders = kp();
+    let record = kp();
+    let vault_ix = |data| {
+        cpi_caller::instruction(
+            &market.addresses,
+            &open_orders.pubkey(),
+            &base_account,
+            &quote_account,
+            &record.pubkey(),
+            data,
+        )
+    };
+
+    let payer = ctx.initial_market_owner.pubkey();
+    ctx.send(
+        &[
+            create_program_account(&ctx, &payer, &open_orders.pubkey(), 8 + OpenOrders::LEN),
+            system_instruction::create_account(
+                &payer,
+                &record.pubkey(),
+                ctx.rent.minimum_balance(cpi_caller::RECORD_LEN),
+                cpi_caller::RECORD_LEN as u64,
+                &cpi_caller::ID,
+            ),
+            vault_ix(VaultInstruction::InitOpenOrders),
+        ],
+        &[&open_orders, &record],
+    )
+    .await
+    .unwrap();
+    let params = OrderParams {
+        side: Side::Bid,
+        price: 2_000,
+        max_base_lots: 100,
+        order_type: OrderType::Limit,
+        client_order_id: 7,
+        expiry_timestamp: 0,
+    };
+    ctx.send(&[vault_ix(VaultInstruction::PlaceOrder(params))], &[])
+        .await
+        .unwrap();
+
+    let account = state::get::<OpenOrders>(&mut ctx, open_orders.pubkey()).await;
+    assert_eq!(account.owner, vault_authority);
+    assert_eq!(account.quote_locked, 2 * QUOTE);
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert_eq!(bids.orders.len(), 1);
+    assert_eq!(bids.orders[0].owner, open_orders.pubkey());
+    assert_eq!(ctx.get_balance(&quote_account).await, 8 * QUOTE);
+    let bid_id = bids.orders[0].order_id;
+    assert_eq!(
+        read_summary(&mut ctx, &record.pubkey()).await,
+        OrderSummary {
+            order_id: bid_id,
+            rested: true,
+            ..OrderSummary::default()
+        }
+    );
+
+    // The swap buys 10 lots from a resting ask at 2_100, worth 10 native quote per tick.
+    let seller = new_trader(&mut ctx, &market, BASE, 0).await;
+    ctx.send(
+        &[instructions::place_order(
+            &seller.kp.pubkey(),
+            &market.addresses,
+            &seller.open_orders,
+            &seller.base_account,
+            OrderParams {
+                side: Side::Ask,
+                price: 2_100,
+                max_base_lots: 30,
+                order_type: OrderType::Limit,
+                client_order_id: 1,
+                expiry_timestamp: 0,
+            },
+            None,
+        )],
+        &[&seller.kp],
+    )
+    .await
+    .unwrap();
+    let args = SwapArgs {
+        side: Side::Bid,
+        limit_price: 2_100,
+        max_base_lots: 10,
+        min_base_lots: 10,
+    };
+    ctx.send(&[vault_ix(VaultInstruction::Swap(args))], &[])
+        .await
+        .unwrap();
+    let summary = read_summary(&mut ctx, &record.pubkey()).await;
+    assert_eq!(summary.base_filled, 10_000_000);
+    assert_eq!(summary.quote_filled, 210_000);
+    assert_eq!(summary.fee_paid, 210);
+    assert!(!summary.rested);
+    assert_eq!(ctx.get_balance(&base_account).await, 10_000_000);
+
+    ctx.send(&[vault_ix(VaultInstruction::CancelOrder(bid_id))], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        read_summary(&mut ctx, &record.pubkey()).await,
+        OrderSummary {
+            order_id: bid_id,
+            base_cancelled: 100_000_000,
+            ..OrderSummary::default()
+        }
+    );
+}
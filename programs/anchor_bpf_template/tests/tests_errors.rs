@@ -0,0 +1,117 @@
+mod common;
+use anchor_bpf_template::{errors::ClobError, OrderParams, OrderType, Side};
+use common::{
+    consts::QUOTE,
+    fixtures::{default_market_args, new_trader, setup_market},
+    instructions,
+    runner::assert_clob_error,
+};
+use solana_program_test::tokio;
+use solana_sdk::signer::Signer;
+
+#[test]
+fn test_error_codes_are_stable() {
+    let codes = [
+        (ClobError::MathOverflow, 6000),
+        (ClobError::InvalidLotSize, 6001),
+        (ClobError::InvalidFee, 6002),
+        (ClobError::InvalidPrice, 6003),
+        (ClobError::InvalidTick, 6004),
+        (ClobError::InvalidQuantity, 6005),
+        (ClobError::InvalidExpiry, 6006),
+        (ClobError::WouldCrossPostOnly, 6007),
+        (ClobError::BookFull, 6008),
+        (ClobError::EventQueueFull, 6009),
+        (ClobError::TooManyOpenOrders, 6010),
+        (ClobError::OpenOrdersRequired, 6011),
+        (ClobError::OrderNotFound, 6012),
+        (ClobError::Unauthorized, 6013),
+        (ClobError::MarketNotActive, 6014),
+        (ClobError::MarketPaused, 6015),
+        (ClobError::SlippageExceeded, 6016),
+        (ClobError::WrongMarket, 6017),
+        (ClobError::InsufficientFunds, 6018),
+        (ClobError::WrongMint, 6019),
+        (ClobError::InvalidVault, 6020),
+        (ClobError::InvalidOracle, 6021),
+        (ClobError::StaleOracle, 6022),
+        (ClobError::PriceOutsideBand, 6023),
+        (ClobError::InvalidPriceBand, 6024),
+        (ClobError::InvalidPeg, 6025),
+        (ClobError::MidpointBookRequired, 6026),
+        (ClobError::AlreadyInitialized, 6027),
+        (ClobError::TooManyTriggers, 6028),
+        (ClobError::NoTriggersReady, 6029),
+        (ClobError::InvalidGroup, 6030),
+        (ClobError::InvalidIceberg, 6031),
+        (ClobError::InvalidTwap, 6032),
+        (ClobError::TwapNotDue, 6033),
+        (ClobError::InvalidCircuitBreaker, 6034),
+        (ClobError::InvalidAuctionOrder, 6035),
+        (ClobError::NotInAuction, 6036),
+        (ClobError::BatchNotDue, 6037),
+        (ClobError::InvalidMatchingMode, 6038),
+        (ClobError::InvalidTickSchedule, 6039),
+        (ClobError::OrderTooSmall, 6040),
+        (ClobError::InvalidOrderBond, 6041),
+        (ClobError::HeartbeatNotMissed, 6042),
+        (ClobError::SequenceMismatch, 6043),
+        (ClobError::OrderRateLimitExceeded, 6044),
+        (ClobError::OpenNotionalLimitExceeded, 6045),
+        (ClobError::PositionLimitExceeded, 6046),
+        (ClobError::DuplicateAccount, 6047),
+        (ClobError::AuctionNotUncrossed, 6048),
+        (ClobError::RiskLimitsSet, 6049),
+    ];
+    for (error, code) in codes {
+        assert_eq!(u32::from(error), code, "{:?}", error);
+    }
+}
+
+#[tokio::test]
+async fn test_insufficient_funds() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let trader = new_trader(&mut ctx, &market, 0, QUOTE).await;
+
+    let res = ctx
+        .send(
+            &[instructions::place_order(
+                &trader.kp.pubkey(),
+                &market.addresses,
+                &trader.open_orders,
+                &trader.quote_account,
+                OrderParams {
+                    side: Side::Bid,
+                    price: 2_000,
+                    max_base_lots: 100,
+                    order_type: OrderType::Limit,
+                    client_order_id: 0,
+                    expiry_timestamp: 0,
+                },
+                None,
+            )],
+            &[&trader.kp],
+        )
+        .await;
+    assert_clob_error(res, ClobError::InsufficientFunds);
+}
+
+#[tokio::test]
+async fn test_settle_to_wrong_mint() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let trader = new_trader(&mut ctx, &market, 0, QUOTE).await;
+
+    let res = ctx
+        .send(
+            &[instructions::settle_funds(
+                &trader.kp.pubkey(),
+                &market.addresses,
+                &trader.open_orders,
+                &trader.quote_account,
+                &trader.base_account,
+            )],
+            &[&trader.kp],
+        )
+        .await;
+    assert_clob_error(res, ClobError::WrongMint);
+}
@@ -0,0 +1,396 @@
+mod common;
+use std::sync::Mutex;
+
+use anchor_bpf_template::{
+    events::{
+        AuctionUncrossed, FeesSwept, FundsSettled, MarketCreated, OrderCancelled, OrderExpired,
+        OrderFilled, OrderPlaced,
+    },
+    MarketStatus, OrderParams, OrderType, Side,
+};
+use anchor_lang::solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+};
+use clob_sdk::events::ProgramEvent;
+use common::{
+    consts::{BASE, QUOTE},
+    fixtures::{
+        create_market, default_market_args, new_token_account, new_trader, order,
+        setup_empty_market_with_dependencies,
+    },
+    instructions,
+};
+use solana_program_test::tokio;
+use solana_sdk::signer::Signer;
+
+/// What the program passed to `sol_log_data`, which a validator writes to the transaction
+/// logs as `Program data:` lines. Natively run programs only have them printed, and the
+/// stubs are global, so this binary holds no other test.
+static LOGGED_DATA: Mutex<Vec<Vec<u8>>> = Mutex::new(Vec::new());
+
+/// Forwards every syscall to the stubs of `solana-program-test`, recording log data.
+struct RecordLogData(Box<dyn SyscallStubs>);
+
+impl SyscallStubs for RecordLogData {
+    fn sol_log(&self, message: &str) {
+        self.0.sol_log(message)
+    }
+    fn sol_log_compute_units(&self) {
+        self.0.sol_log_compute_units()
+    }
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.0
+            .sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_clock_sysvar(var_addr)
+    }
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_fees_sysvar(var_addr)
+    }
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_rent_sysvar(var_addr)
+    }
+    unsafe fn sol_memcpy(&self, dst: *mut u8, src: *const u8, n: usize) {
+        self.0.sol_memcpy(dst, src, n)
+    }
+    unsafe fn sol_memmove(&self, dst: *mut u8, src: *const u8, n: usize) {
+        self.0.sol_memmove(dst, src, n)
+    }
+    unsafe fn sol_memcmp(&self, s1: *const u8, s2: *const u8, n: usize, result: *mut i32) {
+        self.0.sol_memcmp(s1, s2, n, result)
+    }
+    unsafe fn sol_memset(&self, s: *mut u8, c: u8, n: usize) {
+        self.0.sol_memset(s, c, n)
+    }
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.0.sol_get_return_data()
+    }
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.0.sol_set_return_data(data)
+    }
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        LOGGED_DATA.lock().unwrap().push(fields.concat());
+    }
+    fn sol_get_processed_sibling_instruction(&self, index: usize) -> Option<Instruction> {
+        self.0.sol_get_processed_sibling_instruction(index)
+    }
+    fn sol_get_stack_height(&self) -> u64 {
+        self.0.sol_get_stack_height()
+    }
+}
+
+/// Wraps the stubs `solana-program-test` installs when it starts.
+fn record_log_data() {
+    struct Placeholder;
+    impl SyscallStubs for Placeholder {}
+
+    let program_test_stubs = set_syscall_stubs(Box::new(Placeholder));
+    set_syscall_stubs(Box::new(RecordLogData(program_test_stubs)));
+}
+
+/// Decodes and clears the events logged since the last call.
+fn take_events() -> Vec<ProgramEvent> {
+    LOGGED_DATA
+        .lock()
+        .unwrap()
+        .drain(..)
+        .map(|data| ProgramEvent::decode(&data).expect("not an event of the program"))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_order_lifecycle_emits_events() {
+    let mut ctx = setup_empty_market_with_dependencies(&[]).await;
+    record_log_data();
+    let admin = ctx.initial_market_owner.pubkey();
+    let market = create_market(&mut ctx, default_market_args()).await;
+    let addresses = market.addresses;
+    match &take_events()[..] {
+        [ProgramEvent::MarketCreated(MarketCreated {
+            market: key,
+            admin: market_admin,
+            base_lot_size: 1_000_000,
+            taker_fee_bps: 10,
+            ..
+        })] => assert_eq!((*key, *market_admin), (addresses.market, admin)),
+        other => panic!("unexpected events {:?}", other),
+    }
+
+    let maker = new_trader(&mut ctx, &market, BASE, 0).await;
+    let taker = new_trader(&mut ctx, &market, 0, 10 * QUOTE).await;
+    take_events();
+
+    ctx.send(
+        &[instructions::place_order(
+            &maker.kp.pubkey(),
+            &addresses,
+            &maker.open_orders,
+            &maker.base_account,
+            order(Side::Ask, 2_000, 100, 1),
+            None,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+    let ask_placed = OrderPlaced {
+        market: addresses.market,
+        owner: maker.open_orders,
+        order_id: 1,
+        client_order_id: 1,
+        side: Side::Ask,
+        order_type: OrderType::Limit,
+        price: 2_000,
+        max_base_lots: 100,
+        base_lots_filled: 0,
+        quote_lots_filled: 0,
+        taker_fee: 0,
+        posted_base_lots: 100,
+    };
+    assert_eq!(take_events(), vec![ProgramEvent::OrderPlaced(ask_placed)]);
+
+    // 60 lots at the maker's price of 2000 ticks, plus the 10 bps taker fee.
+    ctx.send(
+        &[instructions::place_order(
+            &taker.kp.pubkey(),
+            &addresses,
+            &taker.open_orders,
+            &taker.quote_account,
+            order(Side::Bid, 2_100, 60, 2),
+            None,
+        )],
+        &[&taker.kp],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        take_events(),
+        vec![
+            ProgramEvent::OrderFilled(OrderFilled {
+                market: addresses.market,
+                maker: maker.open_orders,
+                maker_order_id: 1,
+                maker_client_order_id: 1,
+                maker_out: false,
+                taker: taker.open_orders,
+                taker_order_id: 2,
+                taker_side: Side::Bid,
+                price: 2_000,
+                quantity: 60,
+                auction: false,
+            }),
+            ProgramEvent::OrderPlaced(OrderPlaced {
+                market: addresses.market,
+                owner: taker.open_orders,
+                order_id: 2,
+                client_order_id: 2,
+                side: Side::Bid,
+                order_type: OrderType::Limit,
+                price: 2_100,
+                max_base_lots: 60,
+                base_lots_filled: 60,
+                quote_lots_filled: 120_000,
+                taker_fee: 1_200,
+                posted_base_lots: 0,
+            }),
+        ]
+    );
+
+    ctx.send(
+        &[instructions::cancel_order(
+            &maker.kp.pubkey(),
+            &addresses,
+            &maker.open_orders,
+            1,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        take_events(),
+        vec![ProgramEvent::OrderCancelled(OrderCancelled {
+            market: addresses.market,
+            owner: maker.open_orders,
+            order_id: 1,
+            client_order_id: 1,
+            side: Side::Ask,
+            price: 2_000,
+            quantity: 40,
+        })]
+    );
+
+    // The taker finds the next ask expired and is left with nothing to match.
+    let expiry_timestamp = ctx.get_now_timestamp().await as i64 + 10;
+    ctx.send(
+        &[instructions::place_order(
+            &maker.kp.pubkey(),
+            &addresses,
+            &maker.open_orders,
+            &maker.base_account,
+            OrderParams {
+                expiry_timestamp,
+                ..order(Side::Ask, 2_050, 10, 3)
+            },
+            None,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+    take_events();
+    ctx.fast_forward_seconds(20).await;
+    ctx.send(
+        &[instructions::place_order(
+            &taker.kp.pubkey(),
+            &addresses,
+            &taker.open_orders,
+            &taker.quote_account,
+            OrderParams {
+                order_type: OrderType::ImmediateOrCancel,
+                ..order(Side::Bid, 2_100, 10, 4)
+            },
+            None,
+        )],
+        &[&taker.kp],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        take_events(),
+        vec![
+            ProgramEvent::OrderExpired(OrderExpired {
+                market: addresses.market,
+                owner: maker.open_orders,
+                order_id: 3,
+                client_order_id: 3,
+                side: Side::Ask,
+                price: 2_050,
+                quantity: 10,
+            }),
+            ProgramEvent::OrderPlaced(OrderPlaced {
+                market: addresses.market,
+                owner: taker.open_orders,
+                order_id: 4,
+                client_order_id: 4,
+                side: Side::Bid,
+                order_type: OrderType::ImmediateOrCancel,
+                price: 2_100,
+                max_base_lots: 10,
+                base_lots_filled: 0,
+                quote_lots_filled: 0,
+                taker_fee: 0,
+                posted_base_lots: 0,
+            }),
+        ]
+    );
+
+    ctx.send(
+        &[
+            instructions::consume_events(&addresses, &admin, &[maker.open_orders], 10),
+            instructions::settle_funds(
+                &maker.kp.pubkey(),
+                &addresses,
+                &maker.open_orders,
+                &maker.base_account,
+                &maker.quote_account,
+            ),
+        ],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        take_events(),
+        vec![ProgramEvent::FundsSettled(FundsSettled {
+            market: addresses.market,
+            open_orders: maker.open_orders,
+            owner: maker.kp.pubkey(),
+            base_amount: 40_000_000,
+            quote_amount: 1_200_000,
+            bond_lamports: 0,
+        })]
+    );
+
+    let fee_account = new_token_account(&mut ctx, &market.quote_mint, &admin, 0).await;
+    ctx.send(
+        &[instructions::sweep_fees(&admin, &addresses, &fee_account)],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        take_events(),
+        vec![ProgramEvent::FeesSwept(FeesSwept {
+            market: addresses.market,
+            destination: fee_account,
+            amount: 1_200,
+        })]
+    );
+
+    // An auction trade fills two resting orders and is reported once.
+    ctx.send(
+        &[
+            instructions::set_market_status(&admin, &addresses, MarketStatus::Auction),
+            instructions::place_order(
+                &maker.kp.pubkey(),
+                &addresses,
+                &maker.open_orders,
+                &maker.base_account,
+                order(Side::Ask, 2_000, 5, 5),
+                None,
+            ),
+            instructions::place_order(
+                &taker.kp.pubkey(),
+                &addresses,
+                &taker.open_orders,
+                &taker.quote_account,
+                order(Side::Bid, 2_050, 5, 6),
+                None,
+            ),
+        ],
+        &[&maker.kp, &taker.kp],
+    )
+    .await
+    .unwrap();
+    take_events();
+    ctx.send(&[instructions::uncross_auction(&admin, &addresses)], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        take_events(),
+        vec![
+            ProgramEvent::OrderFilled(OrderFilled {
+                market: addresses.market,
+                maker: taker.open_orders,
+                maker_order_id: 6,
+                maker_client_order_id: 6,
+                maker_out: true,
+                taker: maker.open_orders,
+                taker_order_id: 5,
+                taker_side: Side::Ask,
+                price: 2_000,
+                quantity: 5,
+                auction: true,
+            }),
+            ProgramEvent::AuctionUncrossed(AuctionUncrossed {
+                market: addresses.market,
+                price: 2_000,
+                base_lots: 5,
+            }),
+        ]
+    );
+}
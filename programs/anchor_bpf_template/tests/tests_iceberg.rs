@@ -0,0 +1,176 @@
+mod common;
+use anchor_bpf_template::{
+    errors::ClobError,
+    state::{BookSide, Event, EventQueue, MidpointBook, OpenOrders},
+    IcebergOrderParams, OrderParams, OrderType, Side,
+};
+use common::{
+    consts::{BASE, BASE_LOT, QUOTE},
+    fixtures::{create_program_account, default_market_args, new_trader, setup_market},
+    instructions,
+    runner::{assert_clob_error, state},
+    setup::kp,
+};
+use solana_program_test::tokio;
+use solana_sdk::signer::Signer;
+
+fn iceberg(max_base_lots: u64, display_base_lots: u64) -> IcebergOrderParams {
+    IcebergOrderParams {
+        side: Side::Ask,
+        price: 2_000,
+        max_base_lots,
+        display_base_lots,
+        order_type: OrderType::Limit,
+        client_order_id: 1,
+        expiry_timestamp: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_iceberg_refills_behind_the_queue() {
+    let (mut ctx, mut market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let midpoint_book = kp();
+    ctx.send(
+        &[
+            create_program_account(
+                &ctx,
+                &admin.pubkey(),
+                &midpoint_book.pubkey(),
+                8 + MidpointBook::LEN,
+            ),
+            instructions::init_midpoint_book(
+                &admin.pubkey(),
+                &market.addresses,
+                &midpoint_book.pubkey(),
+            ),
+        ],
+        &[&midpoint_book],
+    )
+    .await
+    .unwrap();
+    market.addresses.midpoint_book = Some(midpoint_book.pubkey());
+
+    let seller = new_trader(&mut ctx, &market, BASE, 0).await;
+    let other = new_trader(&mut ctx, &market, BASE, 0).await;
+    let buyer = new_trader(&mut ctx, &market, 0, 10 * QUOTE).await;
+
+    let res = ctx
+        .send(
+            &[instructions::place_iceberg_order(
+                &seller.kp.pubkey(),
+                &market.addresses,
+                &seller.open_orders,
+                &seller.base_account,
+                iceberg(10, 10),
+                None,
+            )],
+            &[&seller.kp],
+        )
+        .await;
+    assert_clob_error(res, ClobError::InvalidIceberg);
+
+    // The failed call left the sequence number at 0.
+    let place = |expected_sequence| {
+        instructions::place_iceberg_order(
+            &seller.kp.pubkey(),
+            &market.addresses,
+            &seller.open_orders,
+            &seller.base_account,
+            iceberg(25, 10),
+            Some(expected_sequence),
+        )
+    };
+    let res = ctx.send(&[place(1)], &[&seller.kp]).await;
+    assert_clob_error(res, ClobError::SequenceMismatch);
+    ctx.send(&[place(0)], &[&seller.kp]).await.unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, seller.open_orders).await;
+    assert_eq!(open_orders.sequence, 1);
+    ctx.send(
+        &[instructions::place_order(
+            &other.kp.pubkey(),
+            &market.addresses,
+            &other.open_orders,
+            &other.base_account,
+            OrderParams {
+                side: Side::Ask,
+                price: 2_000,
+                max_base_lots: 5,
+                order_type: OrderType::Limit,
+                client_order_id: 2,
+                expiry_timestamp: 0,
+            },
+            None,
+        )],
+        &[&other.kp],
+    )
+    .await
+    .unwrap();
+
+    // Only the displayed 10 lots are in the asks account.
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    let shown: Vec<(u64, u64)> = asks.orders.iter().map(|o| (o.price, o.quantity)).collect();
+    assert_eq!(shown, [(2_000, 10), (2_000, 5)]);
+    let iceberg_id = asks.orders[0].order_id;
+
+    // Filling the display shows 10 more behind the other ask, which takes the rest.
+    ctx.send(
+        &[instructions::place_order(
+            &buyer.kp.pubkey(),
+            &market.addresses,
+            &buyer.open_orders,
+            &buyer.quote_account,
+            OrderParams {
+                side: Side::Bid,
+                price: 2_000,
+                max_base_lots: 12,
+                order_type: OrderType::ImmediateOrCancel,
+                client_order_id: 3,
+                expiry_timestamp: 0,
+            },
+            None,
+        )],
+        &[&buyer.kp],
+    )
+    .await
+    .unwrap();
+    let event_queue = state::get::<EventQueue>(&mut ctx, market.addresses.event_queue).await;
+    let fills: Vec<(u64, u64, bool)> = event_queue
+        .events
+        .iter()
+        .map(|event| match event {
+            Event::Fill(fill) => (fill.maker_order_id, fill.quantity, fill.maker_out),
+            Event::Out(out) => panic!("unexpected out event {:?}", out),
+        })
+        .collect();
+    assert_eq!(fills[0], (iceberg_id, 10, false));
+    assert_eq!((fills[1].1, fills[1].2), (2, false));
+
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    let shown: Vec<(u64, u64)> = asks
+        .orders
+        .iter()
+        .map(|o| (o.order_id, o.quantity))
+        .collect();
+    assert_eq!(shown[1], (iceberg_id, 10));
+    assert_eq!(shown[0].1, 3);
+    let book = state::get::<MidpointBook>(&mut ctx, midpoint_book.pubkey()).await;
+    assert_eq!(book.reserves[0].reserve_base_lots, 5);
+
+    // Cancelling releases the displayed part and the reserve.
+    ctx.send(
+        &[instructions::cancel_order(
+            &seller.kp.pubkey(),
+            &market.addresses,
+            &seller.open_orders,
+            iceberg_id,
+        )],
+        &[&seller.kp],
+    )
+    .await
+    .unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, seller.open_orders).await;
+    assert_eq!(open_orders.base_free, 15 * BASE_LOT);
+    let book = state::get::<MidpointBook>(&mut ctx, midpoint_book.pubkey()).await;
+    assert!(book.reserves.is_empty());
+}
@@ -0,0 +1,123 @@
+mod common;
+use anchor_bpf_template::{
+    math::{self, Decimal, Rounding},
+    state::{BookSide, Market, OpenOrders},
+    OrderParams, OrderType, Side, TickBand,
+};
+use common::{
+    fixtures::{default_market_args, new_trader, setup_market, QUOTE_DECIMALS},
+    instructions,
+    runner::state,
+};
+use solana_program_test::tokio;
+use solana_sdk::signer::Signer;
+
+fn decimal(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+#[test]
+fn test_parse_and_display_decimals() {
+    assert_eq!(decimal("123.450"), Decimal::new(123_450, 3));
+    assert_eq!(decimal("7"), Decimal::new(7, 0));
+    assert_eq!(decimal("0.05").to_string(), "0.05");
+    assert_eq!(Decimal::new(5, 3).to_string(), "0.005");
+    assert_eq!(Decimal::new(42, 0).to_string(), "42");
+    for invalid in ["", ".5", "1.2.3", "-1", "1e3", "1,5"] {
+        assert!(invalid.parse::<Decimal>().is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn test_rounding_modes() {
+    assert_eq!(math::div_round(7, 2, Rounding::Down).unwrap(), 3);
+    assert_eq!(math::div_round(7, 2, Rounding::Up).unwrap(), 4);
+    assert_eq!(math::div_round(7, 2, Rounding::Nearest).unwrap(), 4);
+    assert_eq!(math::div_round(10, 4, Rounding::Nearest).unwrap(), 3);
+    assert_eq!(math::div_round(9, 4, Rounding::Nearest).unwrap(), 2);
+    assert_eq!(math::div_round(8, 4, Rounding::Up).unwrap(), 2);
+    assert!(math::div_round(1, 0, Rounding::Down).is_err());
+    assert!(math::ui_to_native(Decimal::new(u128::MAX, 0), 6, Rounding::Down).is_err());
+}
+
+#[test]
+fn test_ui_price_rounds_up_to_the_tick_of_its_band() {
+    // A schedule `set_tick_schedule` now rejects: 100 is not on the tick of 3 below it.
+    let market = Market {
+        base_decimals: 9,
+        quote_decimals: 6,
+        base_lot_size: 1_000_000,
+        quote_lot_size: 10,
+        tick_size: 3,
+        tick_schedule: vec![TickBand {
+            min_price: 100,
+            tick_size: 5,
+        }],
+        ..Market::default()
+    };
+    // 0.995 is 99.5 ticks: 102 on the tick of 3, in the band of 5.
+    let price = |s, rounding| math::ui_price_to_price(&market, decimal(s), rounding).unwrap();
+    assert_eq!(price("0.995", Rounding::Up), 105);
+    assert_eq!(price("0.995", Rounding::Down), 99);
+    assert_eq!(price("1.01", Rounding::Down), 100);
+}
+
+#[tokio::test]
+async fn test_ui_conversions_agree_with_the_program() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let state_market = state::get::<Market>(&mut ctx, market.addresses.market).await;
+
+    // 1 base lot is 0.001 base tokens and 1 tick 0.01 quote tokens per base token.
+    let lots =
+        |s, rounding| math::ui_quantity_to_base_lots(&state_market, decimal(s), rounding).unwrap();
+    assert_eq!(lots("0.0105", Rounding::Down), 10);
+    assert_eq!(lots("0.0105", Rounding::Up), 11);
+    assert_eq!(lots("0.0105", Rounding::Nearest), 11);
+    assert_eq!(
+        math::base_lots_to_ui_quantity(&state_market, 10)
+            .unwrap()
+            .to_string(),
+        "0.010000000"
+    );
+    let price = |s, rounding| math::ui_price_to_price(&state_market, decimal(s), rounding).unwrap();
+    assert_eq!(price("20.0049", Rounding::Down), 2_000);
+    assert_eq!(price("20.0049", Rounding::Up), 2_001);
+    assert_eq!(price("20.0049", Rounding::Nearest), 2_000);
+    assert_eq!(
+        math::price_to_ui_price(&state_market, 2_000, 2, Rounding::Down)
+            .unwrap()
+            .to_string(),
+        "20.00"
+    );
+
+    // A bid of 0.01 base at 20 locks 0.2 quote tokens.
+    let trader = new_trader(&mut ctx, &market, 0, 1_000_000).await;
+    let params = OrderParams {
+        side: Side::Bid,
+        price: price("20", Rounding::Down),
+        max_base_lots: lots("0.01", Rounding::Down),
+        order_type: OrderType::Limit,
+        client_order_id: 1,
+        expiry_timestamp: 0,
+    };
+    ctx.send(
+        &[instructions::place_order(
+            &trader.kp.pubkey(),
+            &market.addresses,
+            &trader.open_orders,
+            &trader.quote_account,
+            params,
+            None,
+        )],
+        &[&trader.kp],
+    )
+    .await
+    .unwrap();
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert_eq!(bids.orders[0].price, 2_000);
+    let open_orders = state::get::<OpenOrders>(&mut ctx, trader.open_orders).await;
+    assert_eq!(
+        open_orders.quote_locked,
+        math::ui_to_native(decimal("0.2"), QUOTE_DECIMALS, Rounding::Down).unwrap()
+    );
+}
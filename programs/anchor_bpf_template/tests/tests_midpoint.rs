@@ -0,0 +1,345 @@
+mod common;
+use anchor_bpf_template::{
+    errors::ClobError,
+    state::{BookSide, Event, EventQueue, MidpointBook, OpenOrders},
+    MidpointOrderParams, OrderParams, OrderType, Side, TickBand,
+};
+use common::{
+    consts::{BASE, BASE_LOT, QUOTE},
+    fixtures::{
+        create_program_account, default_market_args, new_trader, order, setup_market,
+        MarketAddresses, Trader,
+    },
+    instructions,
+    runner::{assert_clob_error, state},
+    setup::kp,
+};
+use solana_program_test::tokio;
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn test_midpoint_orders_trade_hidden_at_the_mid() {
+    let (mut ctx, mut market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let midpoint_book = kp();
+    ctx.send(
+        &[
+            create_program_account(
+                &ctx,
+                &admin.pubkey(),
+                &midpoint_book.pubkey(),
+                8 + MidpointBook::LEN,
+            ),
+            instructions::init_midpoint_book(
+                &admin.pubkey(),
+                &market.addresses,
+                &midpoint_book.pubkey(),
+            ),
+        ],
+        &[&midpoint_book],
+    )
+    .await
+    .unwrap();
+    market.addresses.midpoint_book = Some(midpoint_book.pubkey());
+
+    let lit = new_trader(&mut ctx, &market, BASE, 10 * QUOTE).await;
+    let hidden = new_trader(&mut ctx, &market, BASE, 10 * QUOTE).await;
+    let taker = new_trader(&mut ctx, &market, BASE, 0).await;
+
+    ctx.send(
+        &[
+            instructions::place_order(
+                &lit.kp.pubkey(),
+                &market.addresses,
+                &lit.open_orders,
+                &lit.quote_account,
+                order(Side::Bid, 1_990, 10, 0),
+                None,
+            ),
+            instructions::place_order(
+                &lit.kp.pubkey(),
+                &market.addresses,
+                &lit.open_orders,
+                &lit.base_account,
+                order(Side::Ask, 2_010, 10, 0),
+                None,
+            ),
+        ],
+        &[&lit.kp],
+    )
+    .await
+    .unwrap();
+    ctx.send(
+        &[instructions::place_midpoint_order(
+            &hidden.kp.pubkey(),
+            &market.addresses,
+            &hidden.open_orders,
+            &hidden.quote_account,
+            MidpointOrderParams {
+                side: Side::Bid,
+                limit_price: 2_005,
+                max_base_lots: 10,
+                client_order_id: 1,
+                expiry_timestamp: 0,
+            },
+            None,
+        )],
+        &[&hidden.kp],
+    )
+    .await
+    .unwrap();
+
+    // Not displayed: the lit bids only hold the lit order.
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert_eq!(bids.orders.len(), 1);
+    let book = state::get::<MidpointBook>(&mut ctx, midpoint_book.pubkey()).await;
+    assert_eq!(book.bids.len(), 1);
+
+    // 10 lots meet the midpoint bid at 2000 before the lit bid at 1990.
+    ctx.send(
+        &[instructions::place_order(
+            &taker.kp.pubkey(),
+            &market.addresses,
+            &taker.open_orders,
+            &taker.base_account,
+            OrderParams {
+                order_type: OrderType::ImmediateOrCancel,
+                ..order(Side::Ask, 1_990, 12, 0)
+            },
+            None,
+        )],
+        &[&taker.kp],
+    )
+    .await
+    .unwrap();
+    let event_queue = state::get::<EventQueue>(&mut ctx, market.addresses.event_queue).await;
+    let fills: Vec<(u64, u64)> = event_queue
+        .events
+        .iter()
+        .map(|event| match event {
+            Event::Fill(fill) => (fill.price, fill.quantity),
+            Event::Out(out) => panic!("unexpected out event {:?}", out),
+        })
+        .collect();
+    assert_eq!(fills, [(2_000, 10), (1_990, 2)]);
+    let book = state::get::<MidpointBook>(&mut ctx, midpoint_book.pubkey()).await;
+    assert!(book.bids.is_empty());
+
+    // Midpoint orders are cancelled like any other, given the midpoint book.
+    ctx.send(
+        &[instructions::place_midpoint_order(
+            &hidden.kp.pubkey(),
+            &market.addresses,
+            &hidden.open_orders,
+            &hidden.base_account,
+            MidpointOrderParams {
+                side: Side::Ask,
+                limit_price: 0,
+                max_base_lots: 5,
+                client_order_id: 2,
+                expiry_timestamp: 0,
+            },
+            None,
+        )],
+        &[&hidden.kp],
+    )
+    .await
+    .unwrap();
+    let book = state::get::<MidpointBook>(&mut ctx, midpoint_book.pubkey()).await;
+    let order_id = book.asks[0].order_id;
+
+    let without_book = MarketAddresses {
+        midpoint_book: None,
+        ..market.addresses
+    };
+    let res = ctx
+        .send(
+            &[instructions::cancel_all_orders(
+                &hidden.kp.pubkey(),
+                &without_book,
+                &hidden.open_orders,
+            )],
+            &[&hidden.kp],
+        )
+        .await;
+    assert_clob_error(res, ClobError::MidpointBookRequired);
+
+    ctx.send(
+        &[instructions::cancel_order(
+            &hidden.kp.pubkey(),
+            &market.addresses,
+            &hidden.open_orders,
+            order_id,
+        )],
+        &[&hidden.kp],
+    )
+    .await
+    .unwrap();
+    let book = state::get::<MidpointBook>(&mut ctx, midpoint_book.pubkey()).await;
+    assert!(book.asks.is_empty());
+
+    let replacement = kp();
+    let res = ctx
+        .send(
+            &[
+                create_program_account(
+                    &ctx,
+                    &admin.pubkey(),
+                    &replacement.pubkey(),
+                    8 + MidpointBook::LEN,
+                ),
+                instructions::init_midpoint_book(
+                    &admin.pubkey(),
+                    &market.addresses,
+                    &replacement.pubkey(),
+                ),
+            ],
+            &[&replacement],
+        )
+        .await;
+    assert_clob_error(res, ClobError::AlreadyInitialized);
+}
+
+#[tokio::test]
+async fn test_full_midpoint_book_evicts_the_worst_limit() {
+    let (mut ctx, mut market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let midpoint_book = kp();
+    ctx.send(
+        &[
+            create_program_account(
+                &ctx,
+                &admin.pubkey(),
+                &midpoint_book.pubkey(),
+                8 + MidpointBook::LEN,
+            ),
+            instructions::init_midpoint_book(
+                &admin.pubkey(),
+                &market.addresses,
+                &midpoint_book.pubkey(),
+            ),
+        ],
+        &[&midpoint_book],
+    )
+    .await
+    .unwrap();
+    market.addresses.midpoint_book = Some(midpoint_book.pubkey());
+    let makers = [
+        new_trader(&mut ctx, &market, BASE, 0).await,
+        new_trader(&mut ctx, &market, BASE, 0).await,
+    ];
+    let newcomer = new_trader(&mut ctx, &market, BASE, 0).await;
+
+    let ask = |trader: &Trader, limit_price| {
+        instructions::place_midpoint_order(
+            &trader.kp.pubkey(),
+            &market.addresses,
+            &trader.open_orders,
+            &trader.base_account,
+            MidpointOrderParams {
+                side: Side::Ask,
+                limit_price,
+                max_base_lots: 1,
+                client_order_id: limit_price,
+                expiry_timestamp: 0,
+            },
+            None,
+        )
+    };
+    // 24 and 8 asks fill the side, the second maker's limited at 2_025 to 2_032.
+    let mut limit_price = 2_001;
+    for (maker, count) in makers.iter().zip([24, 8]) {
+        for _ in 0..count {
+            ctx.send(&[ask(maker, limit_price)], &[&maker.kp])
+                .await
+                .unwrap();
+            limit_price += 1;
+        }
+    }
+
+    for limit_price in [2_040, 2_032] {
+        let res = ctx
+            .send(&[ask(&newcomer, limit_price)], &[&newcomer.kp])
+            .await;
+        assert_clob_error(res, ClobError::BookFull);
+    }
+    ctx.send(&[ask(&newcomer, 2_031)], &[&newcomer.kp])
+        .await
+        .unwrap();
+
+    let book = state::get::<MidpointBook>(&mut ctx, midpoint_book.pubkey()).await;
+    assert_eq!(book.asks.len(), MidpointBook::MAX_ORDERS);
+    assert!(book.asks.iter().all(|o| o.price != 2_032));
+
+    let evicted = &makers[1];
+    ctx.send(
+        &[instructions::consume_events(
+            &market.addresses,
+            &ctx.context.payer.pubkey(),
+            &[evicted.open_orders],
+            10,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, evicted.open_orders).await;
+    assert_eq!(open_orders.orders.len(), 7);
+    assert_eq!(open_orders.base_locked, 7 * BASE_LOT);
+    assert_eq!(open_orders.base_free, BASE_LOT);
+}
+
+#[tokio::test]
+async fn test_midpoint_limits_are_on_the_tick_of_their_band() {
+    let (mut ctx, mut market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let midpoint_book = kp();
+    ctx.send(
+        &[
+            create_program_account(
+                &ctx,
+                &admin.pubkey(),
+                &midpoint_book.pubkey(),
+                8 + MidpointBook::LEN,
+            ),
+            instructions::init_midpoint_book(
+                &admin.pubkey(),
+                &market.addresses,
+                &midpoint_book.pubkey(),
+            ),
+            instructions::set_tick_schedule(
+                &admin.pubkey(),
+                &market.addresses,
+                vec![TickBand {
+                    min_price: 1_000,
+                    tick_size: 10,
+                }],
+            ),
+        ],
+        &[&midpoint_book],
+    )
+    .await
+    .unwrap();
+    market.addresses.midpoint_book = Some(midpoint_book.pubkey());
+    let trader = new_trader(&mut ctx, &market, 0, 10 * QUOTE).await;
+
+    let bid = |limit_price| {
+        instructions::place_midpoint_order(
+            &trader.kp.pubkey(),
+            &market.addresses,
+            &trader.open_orders,
+            &trader.quote_account,
+            MidpointOrderParams {
+                side: Side::Bid,
+                limit_price,
+                max_base_lots: 1,
+                client_order_id: limit_price,
+                expiry_timestamp: 0,
+            },
+            None,
+        )
+    };
+    let res = ctx.send(&[bid(2_005)], &[&trader.kp]).await;
+    assert_clob_error(res, ClobError::InvalidTick);
+    ctx.send(&[bid(2_010)], &[&trader.kp]).await.unwrap();
+}
@@ -0,0 +1,617 @@
+mod common;
+use anchor_bpf_template::{
+    errors::ClobError,
+    state::{pyth_program, BookSide, Event, EventQueue, InternalOracle, Market, OpenOrders},
+    OracleConfigArgs, OracleKind, OrderParams, OrderType, PeggedOrderParams, RiskLimits, Side,
+    SwapArgs, TickBand,
+};
+use common::{
+    consts::{BASE, BASE_LOT, QUOTE},
+    fixtures::{
+        create_program_account, default_market_args, new_trader, setup_market, MarketAddresses,
+        MarketFixture, Trader,
+    },
+    instructions,
+    runner::{assert_clob_error, state},
+    setup::{kp, KP},
+    types::TestContext,
+};
+use solana_program_test::tokio;
+use solana_sdk::{
+    account::AccountSharedData, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signer::Signer,
+};
+
+fn bid(price: u64, client_order_id: u64) -> OrderParams {
+    OrderParams {
+        side: Side::Bid,
+        price,
+        max_base_lots: 10,
+        order_type: OrderType::Limit,
+        client_order_id,
+        expiry_timestamp: 0,
+    }
+}
+
+/// Bands the market around a new internal oracle at 20 quote per base, 2000 quote lots
+/// per base lot on the default market, and passes the oracle to order entry.
+async fn with_internal_oracle(ctx: &mut TestContext, market: &mut MarketFixture) -> KP {
+    let admin = ctx.initial_market_owner.clone();
+    let oracle = kp();
+    ctx.send(
+        &[
+            create_program_account(
+                ctx,
+                &admin.pubkey(),
+                &oracle.pubkey(),
+                8 + InternalOracle::LEN,
+            ),
+            instructions::init_internal_oracle(&admin.pubkey(), &oracle.pubkey(), 20, 0),
+            instructions::set_oracle_config(
+                &admin.pubkey(),
+                &market.addresses,
+                &oracle.pubkey(),
+                OracleConfigArgs {
+                    kind: OracleKind::Internal,
+                    price_band_bps: 1_000,
+                    max_staleness: 60,
+                },
+            ),
+        ],
+        &[&oracle],
+    )
+    .await
+    .unwrap();
+    market.addresses.oracle = Some(oracle.pubkey());
+    oracle
+}
+
+#[tokio::test]
+async fn test_internal_oracle_price_band() {
+    let (mut ctx, mut market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let trader = new_trader(&mut ctx, &market, 0, 10 * QUOTE).await;
+    let oracle = with_internal_oracle(&mut ctx, &mut market).await;
+
+    let state = state::get::<Market>(&mut ctx, market.addresses.market).await;
+    assert_eq!(state.oracle, oracle.pubkey());
+    assert_eq!(state.oracle_kind, OracleKind::Internal);
+
+    let place = |market: &MarketAddresses, params| {
+        instructions::place_order(
+            &trader.kp.pubkey(),
+            market,
+            &trader.open_orders,
+            &trader.quote_account,
+            params,
+            None,
+        )
+    };
+
+    // Without the oracle account order entry is rejected.
+    let without_oracle = MarketAddresses {
+        oracle: None,
+        ..market.addresses
+    };
+    let res = ctx
+        .send(&[place(&without_oracle, bid(2_000, 1))], &[&trader.kp])
+        .await;
+    assert_clob_error(res, ClobError::InvalidOracle);
+
+    let res = ctx
+        .send(&[place(&market.addresses, bid(2_201, 2))], &[&trader.kp])
+        .await;
+    assert_clob_error(res, ClobError::PriceOutsideBand);
+    let res = ctx
+        .send(&[place(&market.addresses, bid(1_799, 3))], &[&trader.kp])
+        .await;
+    assert_clob_error(res, ClobError::PriceOutsideBand);
+    ctx.send(&[place(&market.addresses, bid(2_200, 4))], &[&trader.kp])
+        .await
+        .unwrap();
+
+    // The band follows the oracle.
+    ctx.send(
+        &[instructions::set_oracle_price(
+            &admin.pubkey(),
+            &oracle.pubkey(),
+            225,
+            -1,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    ctx.send(&[place(&market.addresses, bid(2_400, 5))], &[&trader.kp])
+        .await
+        .unwrap();
+
+    ctx.fast_forward_minutes(2).await;
+    let res = ctx
+        .send(&[place(&market.addresses, bid(2_300, 6))], &[&trader.kp])
+        .await;
+    assert_clob_error(res, ClobError::StaleOracle);
+}
+
+#[tokio::test]
+async fn test_swap_limit_price_is_banded() {
+    let (mut ctx, mut market) = setup_market(default_market_args()).await;
+    with_internal_oracle(&mut ctx, &mut market).await;
+    let maker = new_trader(&mut ctx, &market, 0, 10 * QUOTE).await;
+    let swapper = new_trader(&mut ctx, &market, BASE, 0).await;
+    ctx.send(
+        &[instructions::place_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.quote_account,
+            bid(1_900, 1),
+            None,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+
+    let swap = |limit_price| {
+        instructions::swap(
+            &swapper.kp.pubkey(),
+            &market.addresses,
+            &swapper.base_account,
+            &swapper.quote_account,
+            SwapArgs {
+                side: Side::Ask,
+                limit_price,
+                max_base_lots: 10,
+                min_base_lots: 0,
+            },
+        )
+    };
+    let res = ctx.send(&[swap(1_000)], &[&swapper.kp]).await;
+    assert_clob_error(res, ClobError::PriceOutsideBand);
+    ctx.send(&[swap(1_800)], &[&swapper.kp]).await.unwrap();
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert!(bids.orders.is_empty());
+}
+
+#[tokio::test]
+async fn test_oracle_config_is_admin_only() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let trader = new_trader(&mut ctx, &market, 0, QUOTE).await;
+
+    let oracle = kp();
+    ctx.send(
+        &[
+            create_program_account(
+                &ctx,
+                &admin.pubkey(),
+                &oracle.pubkey(),
+                8 + InternalOracle::LEN,
+            ),
+            instructions::init_internal_oracle(&trader.kp.pubkey(), &oracle.pubkey(), 20, 0),
+        ],
+        &[&oracle, &trader.kp],
+    )
+    .await
+    .unwrap();
+
+    let config = OracleConfigArgs {
+        kind: OracleKind::Internal,
+        price_band_bps: 1_000,
+        max_staleness: 60,
+    };
+    let res = ctx
+        .send(
+            &[instructions::set_oracle_config(
+                &trader.kp.pubkey(),
+                &market.addresses,
+                &oracle.pubkey(),
+                config.clone(),
+            )],
+            &[&trader.kp],
+        )
+        .await;
+    assert_clob_error(res, ClobError::Unauthorized);
+
+    let res = ctx
+        .send(
+            &[instructions::set_oracle_config(
+                &admin.pubkey(),
+                &market.addresses,
+                &oracle.pubkey(),
+                OracleConfigArgs {
+                    price_band_bps: 0,
+                    ..config
+                },
+            )],
+            &[],
+        )
+        .await;
+    assert_clob_error(res, ClobError::InvalidPriceBand);
+
+    // Only the oracle's authority can move its price.
+    let res = ctx
+        .send(
+            &[instructions::set_oracle_price(
+                &admin.pubkey(),
+                &oracle.pubkey(),
+                30,
+                0,
+            )],
+            &[],
+        )
+        .await;
+    assert_clob_error(res, ClobError::Unauthorized);
+}
+
+fn pegged(
+    side: Side,
+    price_offset: i64,
+    peg_limit: u64,
+    client_order_id: u64,
+) -> PeggedOrderParams {
+    PeggedOrderParams {
+        side,
+        price_offset,
+        peg_limit,
+        max_base_lots: 10,
+        order_type: OrderType::Limit,
+        client_order_id,
+        expiry_timestamp: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_pegged_orders_follow_the_oracle() {
+    let (mut ctx, mut market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let oracle = with_internal_oracle(&mut ctx, &mut market).await;
+    let maker = new_trader(&mut ctx, &market, BASE, 10 * QUOTE).await;
+    let taker = new_trader(&mut ctx, &market, BASE, 10 * QUOTE).await;
+
+    ctx.send(
+        &[instructions::place_pegged_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.base_account,
+            pegged(Side::Ask, 10, 0, 1),
+            None,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert!(asks.orders.is_empty());
+    assert_eq!(asks.pegged_orders.len(), 1);
+
+    // The oracle moves to 2100, taking the ask to 2110 without a cancel/replace.
+    ctx.send(
+        &[instructions::set_oracle_price(
+            &admin.pubkey(),
+            &oracle.pubkey(),
+            21,
+            0,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let take = |price, max_base_lots, client_order_id| {
+        let side = if price > 2_100 { Side::Bid } else { Side::Ask };
+        instructions::place_order(
+            &taker.kp.pubkey(),
+            &market.addresses,
+            &taker.open_orders,
+            match side {
+                Side::Bid => &taker.quote_account,
+                Side::Ask => &taker.base_account,
+            },
+            OrderParams {
+                side,
+                price,
+                max_base_lots,
+                order_type: OrderType::ImmediateOrCancel,
+                client_order_id,
+                expiry_timestamp: 0,
+            },
+            None,
+        )
+    };
+    ctx.send(&[take(2_105, 4, 1)], &[&taker.kp]).await.unwrap();
+    let event_queue = state::get::<EventQueue>(&mut ctx, market.addresses.event_queue).await;
+    assert!(event_queue.events.is_empty());
+
+    ctx.send(&[take(2_110, 4, 2)], &[&taker.kp]).await.unwrap();
+    let event_queue = state::get::<EventQueue>(&mut ctx, market.addresses.event_queue).await;
+    match &event_queue.events[..] {
+        [Event::Fill(fill)] => assert_eq!((fill.price, fill.quantity), (2_110, 4)),
+        other => panic!("expected one fill, got {:?}", other),
+    }
+
+    let res = ctx
+        .send(
+            &[instructions::place_pegged_order(
+                &maker.kp.pubkey(),
+                &market.addresses,
+                &maker.open_orders,
+                &maker.quote_account,
+                pegged(Side::Bid, -100, 0, 2),
+                None,
+            )],
+            &[&maker.kp],
+        )
+        .await;
+    assert_clob_error(res, ClobError::InvalidPeg);
+
+    // Rests at 2000, locking quote at its 2050 limit.
+    ctx.send(
+        &[instructions::place_pegged_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.quote_account,
+            PeggedOrderParams {
+                max_base_lots: 5,
+                ..pegged(Side::Bid, -100, 2_050, 3)
+            },
+            None,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, maker.open_orders).await;
+    assert_eq!(open_orders.quote_locked, 2_050 * 5 * 10);
+
+    ctx.send(&[take(1_990, 5, 3)], &[&taker.kp]).await.unwrap();
+    ctx.send(
+        &[instructions::consume_events(
+            &market.addresses,
+            &ctx.context.payer.pubkey(),
+            &[maker.open_orders],
+            10,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // Sold 4 lots at 2110 and bought 5 at 2000, the excess locked for the bid is freed.
+    let open_orders = state::get::<OpenOrders>(&mut ctx, maker.open_orders).await;
+    assert_eq!(open_orders.quote_locked, 0);
+    assert_eq!(open_orders.quote_free, 2_110 * 4 * 10 + 50 * 5 * 10);
+    assert_eq!(open_orders.base_free, 5 * 1_000_000);
+    assert_eq!(open_orders.base_locked, 6 * 1_000_000);
+    assert_eq!(open_orders.orders.len(), 1);
+}
+
+#[tokio::test]
+async fn test_full_pegged_orders_evict_the_worst_at_the_oracle_price() {
+    let (mut ctx, mut market) = setup_market(default_market_args()).await;
+    with_internal_oracle(&mut ctx, &mut market).await;
+    let maker = new_trader(&mut ctx, &market, BASE, 0).await;
+    let newcomer = new_trader(&mut ctx, &market, BASE, 0).await;
+
+    let ask = |trader: &Trader, price_offset, client_order_id| {
+        instructions::place_pegged_order(
+            &trader.kp.pubkey(),
+            &market.addresses,
+            &trader.open_orders,
+            &trader.base_account,
+            PeggedOrderParams {
+                max_base_lots: 1,
+                ..pegged(Side::Ask, price_offset, 0, client_order_id)
+            },
+            None,
+        )
+    };
+    // 16 asks at the oracle price plus 10 to 25, the last at 2025.
+    for offset in 10..26 {
+        ctx.send(&[ask(&maker, offset, offset as u64)], &[&maker.kp])
+            .await
+            .unwrap();
+    }
+
+    for offset in [40, 25] {
+        let res = ctx
+            .send(&[ask(&newcomer, offset, 1)], &[&newcomer.kp])
+            .await;
+        assert_clob_error(res, ClobError::BookFull);
+    }
+    ctx.send(&[ask(&newcomer, 24, 2)], &[&newcomer.kp])
+        .await
+        .unwrap();
+
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert_eq!(asks.pegged_orders.len(), BookSide::MAX_PEGGED_ORDERS);
+    assert!(asks.pegged_orders.iter().all(|o| o.price_offset != 25));
+
+    ctx.send(
+        &[instructions::consume_events(
+            &market.addresses,
+            &ctx.context.payer.pubkey(),
+            &[maker.open_orders],
+            10,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, maker.open_orders).await;
+    assert_eq!(open_orders.orders.len(), 15);
+    assert_eq!(open_orders.base_locked, 15 * BASE_LOT);
+    assert_eq!(open_orders.base_free, BASE_LOT);
+}
+
+#[tokio::test]
+async fn test_pegged_prices_round_to_the_tick_of_their_band() {
+    let (mut ctx, mut market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    with_internal_oracle(&mut ctx, &mut market).await;
+    let maker = new_trader(&mut ctx, &market, BASE, 0).await;
+    let taker = new_trader(&mut ctx, &market, 0, 10 * QUOTE).await;
+    ctx.send(
+        &[instructions::set_tick_schedule(
+            &admin.pubkey(),
+            &market.addresses,
+            vec![TickBand {
+                min_price: 1_000,
+                tick_size: 10,
+            }],
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    // 3 above the oracle price of 2000, on a tick of 10 an ask rests at 2010.
+    ctx.send(
+        &[instructions::place_pegged_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.base_account,
+            pegged(Side::Ask, 3, 0, 1),
+            None,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+    let take = |price, client_order_id| {
+        instructions::place_order(
+            &taker.kp.pubkey(),
+            &market.addresses,
+            &taker.open_orders,
+            &taker.quote_account,
+            OrderParams {
+                order_type: OrderType::ImmediateOrCancel,
+                max_base_lots: 1,
+                ..bid(price, client_order_id)
+            },
+            None,
+        )
+    };
+    ctx.send(&[take(2_000, 1)], &[&taker.kp]).await.unwrap();
+    let event_queue = state::get::<EventQueue>(&mut ctx, market.addresses.event_queue).await;
+    assert!(event_queue.events.is_empty());
+
+    ctx.send(&[take(2_010, 2)], &[&taker.kp]).await.unwrap();
+    let event_queue = state::get::<EventQueue>(&mut ctx, market.addresses.event_queue).await;
+    match &event_queue.events[..] {
+        [Event::Fill(fill)] => assert_eq!((fill.price, fill.quantity), (2_010, 1)),
+        other => panic!("expected one fill, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_open_notional_cap_needs_a_peg_limit() {
+    let (mut ctx, mut market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    with_internal_oracle(&mut ctx, &mut market).await;
+    let maker = new_trader(&mut ctx, &market, BASE, 0).await;
+    ctx.send(
+        &[instructions::set_risk_limits(
+            &admin.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            RiskLimits {
+                max_open_notional: 1_000_000,
+                ..RiskLimits::default()
+            },
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let ask = |peg_limit, client_order_id| {
+        instructions::place_pegged_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.base_account,
+            PeggedOrderParams {
+                max_base_lots: 1,
+                ..pegged(Side::Ask, 10, peg_limit, client_order_id)
+            },
+            None,
+        )
+    };
+
+    // Without a limit the ask locks no quote the cap could count.
+    let res = ctx.send(&[ask(0, 1)], &[&maker.kp]).await;
+    assert_clob_error(res, ClobError::InvalidPeg);
+    ctx.send(&[ask(1_900, 2)], &[&maker.kp]).await.unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, maker.open_orders).await;
+    assert_eq!(open_orders.orders.len(), 1);
+}
+
+/// A trading Pyth v2 price account at `price * 10^expo`, published at `timestamp`.
+fn pyth_price_account(owner: Pubkey, price: i64, expo: i32, timestamp: i64) -> AccountSharedData {
+    let mut data = vec![0; 240];
+    data[0..4].copy_from_slice(&0xa1b2_c3d4u32.to_le_bytes());
+    data[4..8].copy_from_slice(&2u32.to_le_bytes());
+    data[8..12].copy_from_slice(&3u32.to_le_bytes());
+    data[20..24].copy_from_slice(&expo.to_le_bytes());
+    data[96..104].copy_from_slice(&timestamp.to_le_bytes());
+    data[208..216].copy_from_slice(&price.to_le_bytes());
+    data[224..228].copy_from_slice(&1u32.to_le_bytes());
+    let mut account = AccountSharedData::new(LAMPORTS_PER_SOL, data.len(), &owner);
+    account.set_data(data);
+    account
+}
+
+#[tokio::test]
+async fn test_pyth_oracle_must_be_owned_by_pyth() {
+    let (mut ctx, mut market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let trader = new_trader(&mut ctx, &market, 0, 10 * QUOTE).await;
+    let oracle = Pubkey::new_unique();
+    let now = ctx.get_now_timestamp().await as i64;
+    let set_config = || {
+        instructions::set_oracle_config(
+            &admin.pubkey(),
+            &market.addresses,
+            &oracle,
+            OracleConfigArgs {
+                kind: OracleKind::Pyth,
+                price_band_bps: 1_000,
+                max_staleness: 60,
+            },
+        )
+    };
+
+    // The layout alone does not make a Pyth price.
+    let impostor = pyth_price_account(Pubkey::new_unique(), 2_000, -2, now);
+    ctx.context.set_account(&oracle, &impostor);
+    let res = ctx.send(&[set_config()], &[]).await;
+    assert_clob_error(res, ClobError::InvalidOracle);
+
+    let pyth = pyth_price_account(pyth_program::ID, 2_000, -2, now);
+    ctx.context.set_account(&oracle, &pyth);
+    // A later slot keeps the retry from being deduplicated.
+    ctx.fast_forward_seconds(1).await;
+    ctx.send(&[set_config()], &[]).await.unwrap();
+    market.addresses.oracle = Some(oracle);
+    let state = state::get::<Market>(&mut ctx, market.addresses.market).await;
+    assert_eq!(state.oracle_kind, OracleKind::Pyth);
+
+    let place = |client_order_id| {
+        instructions::place_order(
+            &trader.kp.pubkey(),
+            &market.addresses,
+            &trader.open_orders,
+            &trader.quote_account,
+            bid(2_000, client_order_id),
+            None,
+        )
+    };
+    ctx.send(&[place(1)], &[&trader.kp]).await.unwrap();
+
+    // Nor is it trusted on order entry once configured.
+    ctx.context.set_account(&oracle, &impostor);
+    let res = ctx.send(&[place(2)], &[&trader.kp]).await;
+    assert_clob_error(res, ClobError::InvalidOracle);
+}
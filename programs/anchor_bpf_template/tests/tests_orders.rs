@@ -0,0 +1,723 @@
+mod common;
+use anchor_bpf_template::{
+    errors::ClobError,
+    state::{BookSide, EventQueue, Market, OpenOrders},
+    MatchingMode, OrderParams, OrderType, Side, SwapArgs, TickBand,
+};
+use common::{
+    consts::{BASE, QUOTE},
+    fixtures::{default_market_args, new_token_account, new_trader, setup_market},
+    instructions,
+    runner::{assert_clob_error, state},
+};
+use solana_program_test::tokio;
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+
+fn limit(side: Side, price: u64, max_base_lots: u64) -> OrderParams {
+    OrderParams {
+        side,
+        price,
+        max_base_lots,
+        order_type: OrderType::Limit,
+        client_order_id: 0,
+        expiry_timestamp: 0,
+    }
+}
+
+#[tokio::test]
+async fn test_initialize_market() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+
+    let state = state::get::<Market>(&mut ctx, market.addresses.market).await;
+    assert_eq!(state.base_mint, market.base_mint);
+    assert_eq!(state.bids, market.addresses.bids);
+    assert_eq!(state.asks, market.addresses.asks);
+    assert_eq!(state.event_queue, market.addresses.event_queue);
+    assert_eq!(state.base_vault, market.addresses.base_vault);
+    assert_eq!(state.quote_vault, market.addresses.quote_vault);
+    assert_eq!(state.base_decimals, 9);
+    assert_eq!(state.quote_decimals, 6);
+    assert_eq!(state.tick_size, 1);
+}
+
+#[tokio::test]
+async fn test_place_and_cancel_order() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let trader = new_trader(&mut ctx, &market, 0, 10 * QUOTE).await;
+    let owner = trader.kp.pubkey();
+
+    ctx.send(
+        &[instructions::place_order(
+            &owner,
+            &market.addresses,
+            &trader.open_orders,
+            &trader.quote_account,
+            limit(Side::Bid, 2_000, 100),
+            None,
+        )],
+        &[&trader.kp],
+    )
+    .await
+    .unwrap();
+
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert_eq!(bids.orders.len(), 1);
+    let order_id = bids.orders[0].order_id;
+    let open_orders = state::get::<OpenOrders>(&mut ctx, trader.open_orders).await;
+    assert_eq!(open_orders.quote_locked, 2 * QUOTE);
+    assert_eq!(ctx.get_balance(&trader.quote_account).await, 8 * QUOTE);
+
+    ctx.send(
+        &[
+            instructions::cancel_order(&owner, &market.addresses, &trader.open_orders, order_id),
+            instructions::settle_funds(
+                &owner,
+                &market.addresses,
+                &trader.open_orders,
+                &trader.base_account,
+                &trader.quote_account,
+            ),
+        ],
+        &[&trader.kp],
+    )
+    .await
+    .unwrap();
+
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert!(bids.orders.is_empty());
+    let open_orders = state::get::<OpenOrders>(&mut ctx, trader.open_orders).await;
+    assert_eq!(open_orders.quote_locked, 0);
+    assert!(open_orders.orders.is_empty());
+    assert_eq!(ctx.get_balance(&trader.quote_account).await, 10 * QUOTE);
+}
+
+#[tokio::test]
+async fn test_match_consume_and_settle() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let maker = new_trader(&mut ctx, &market, BASE, 0).await;
+    let taker = new_trader(&mut ctx, &market, 0, 10 * QUOTE).await;
+
+    ctx.send(
+        &[instructions::place_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.base_account,
+            limit(Side::Ask, 2_000, 100),
+            None,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+    ctx.send(
+        &[instructions::place_order(
+            &taker.kp.pubkey(),
+            &market.addresses,
+            &taker.open_orders,
+            &taker.quote_account,
+            limit(Side::Bid, 2_100, 60),
+            None,
+        )],
+        &[&taker.kp],
+    )
+    .await
+    .unwrap();
+
+    // 60 lots at the maker's price of 2000 ticks, plus the 10 bps taker fee.
+    assert_eq!(
+        ctx.get_balance(&taker.quote_account).await,
+        10 * QUOTE - 1_200_000 - 1_200
+    );
+    let event_queue = state::get::<EventQueue>(&mut ctx, market.addresses.event_queue).await;
+    assert_eq!(event_queue.events.len(), 1);
+
+    let res = ctx
+        .send(
+            &[instructions::consume_events(
+                &market.addresses,
+                &ctx.context.payer.pubkey(),
+                &[maker.open_orders, maker.open_orders],
+                10,
+            )],
+            &[],
+        )
+        .await;
+    assert_clob_error(res, ClobError::DuplicateAccount);
+
+    ctx.send(
+        &[
+            instructions::consume_events(
+                &market.addresses,
+                &ctx.context.payer.pubkey(),
+                &[maker.open_orders],
+                10,
+            ),
+            instructions::settle_funds(
+                &maker.kp.pubkey(),
+                &market.addresses,
+                &maker.open_orders,
+                &maker.base_account,
+                &maker.quote_account,
+            ),
+            instructions::settle_funds(
+                &taker.kp.pubkey(),
+                &market.addresses,
+                &taker.open_orders,
+                &taker.base_account,
+                &taker.quote_account,
+            ),
+        ],
+        &[&maker.kp, &taker.kp],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(ctx.get_balance(&maker.quote_account).await, 1_200_000);
+    assert_eq!(
+        ctx.get_balance(&maker.base_account).await,
+        BASE - 100_000_000
+    );
+    assert_eq!(ctx.get_balance(&taker.base_account).await, 60_000_000);
+    let maker_oo = state::get::<OpenOrders>(&mut ctx, maker.open_orders).await;
+    assert_eq!(maker_oo.base_locked, 40_000_000);
+    assert_eq!(maker_oo.orders.len(), 1);
+
+    let admin = ctx.initial_market_owner.clone();
+    let fee_destination = new_token_account(&mut ctx, &market.quote_mint, &admin.pubkey(), 0).await;
+    ctx.send(
+        &[instructions::sweep_fees(
+            &admin.pubkey(),
+            &market.addresses,
+            &fee_destination,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(ctx.get_balance(&fee_destination).await, 1_200);
+}
+
+#[tokio::test]
+async fn test_post_only_would_cross() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let maker = new_trader(&mut ctx, &market, BASE, 0).await;
+    let taker = new_trader(&mut ctx, &market, 0, 10 * QUOTE).await;
+
+    ctx.send(
+        &[instructions::place_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.base_account,
+            limit(Side::Ask, 2_000, 100),
+            None,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+
+    let res = ctx
+        .send(
+            &[instructions::place_order(
+                &taker.kp.pubkey(),
+                &market.addresses,
+                &taker.open_orders,
+                &taker.quote_account,
+                OrderParams {
+                    order_type: OrderType::PostOnly,
+                    ..limit(Side::Bid, 2_000, 10)
+                },
+                None,
+            )],
+            &[&taker.kp],
+        )
+        .await;
+    assert_clob_error(res, ClobError::WouldCrossPostOnly);
+}
+
+#[tokio::test]
+async fn test_swap() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let maker = new_trader(&mut ctx, &market, 0, 10 * QUOTE).await;
+    let swapper = new_trader(&mut ctx, &market, BASE, 0).await;
+
+    ctx.send(
+        &[instructions::place_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.quote_account,
+            limit(Side::Bid, 2_000, 100),
+            None,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+
+    let res = ctx
+        .send(
+            &[instructions::swap(
+                &swapper.kp.pubkey(),
+                &market.addresses,
+                &swapper.base_account,
+                &swapper.quote_account,
+                SwapArgs {
+                    side: Side::Ask,
+                    limit_price: 1_900,
+                    max_base_lots: 150,
+                    min_base_lots: 120,
+                },
+            )],
+            &[&swapper.kp],
+        )
+        .await;
+    assert_clob_error(res, ClobError::SlippageExceeded);
+
+    ctx.send(
+        &[instructions::swap(
+            &swapper.kp.pubkey(),
+            &market.addresses,
+            &swapper.base_account,
+            &swapper.quote_account,
+            SwapArgs {
+                side: Side::Ask,
+                limit_price: 1_900,
+                max_base_lots: 150,
+                min_base_lots: 100,
+            },
+        )],
+        &[&swapper.kp],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        ctx.get_balance(&swapper.base_account).await,
+        BASE - 100_000_000
+    );
+    assert_eq!(
+        ctx.get_balance(&swapper.quote_account).await,
+        2 * QUOTE - 2_000
+    );
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert!(bids.orders.is_empty());
+}
+
+#[tokio::test]
+async fn test_delegate_can_trade_but_not_strangers() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let trader = new_trader(&mut ctx, &market, BASE, 0).await;
+    let delegate = new_trader(&mut ctx, &market, BASE, 0).await;
+
+    // Distinct client order ids keep the two attempts from being identical transactions.
+    let place = |signer: &Pubkey, deposit: &Pubkey, client_order_id: u64| {
+        instructions::place_order(
+            signer,
+            &market.addresses,
+            &trader.open_orders,
+            deposit,
+            OrderParams {
+                client_order_id,
+                ..limit(Side::Ask, 2_000, 10)
+            },
+            None,
+        )
+    };
+
+    let res = ctx
+        .send(
+            &[place(&delegate.kp.pubkey(), &delegate.base_account, 1)],
+            &[&delegate.kp],
+        )
+        .await;
+    assert_clob_error(res, ClobError::Unauthorized);
+
+    ctx.send(
+        &[instructions::set_delegate(
+            &trader.kp.pubkey(),
+            &trader.open_orders,
+            delegate.kp.pubkey(),
+        )],
+        &[&trader.kp],
+    )
+    .await
+    .unwrap();
+    ctx.send(
+        &[place(&delegate.kp.pubkey(), &delegate.base_account, 2)],
+        &[&delegate.kp],
+    )
+    .await
+    .unwrap();
+
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert_eq!(asks.orders.len(), 1);
+    assert_eq!(asks.orders[0].owner, trader.open_orders);
+}
+
+#[tokio::test]
+async fn test_pro_rata_splits_a_level_by_size() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let first = new_trader(&mut ctx, &market, BASE, 0).await;
+    let second = new_trader(&mut ctx, &market, BASE, 0).await;
+    let taker = new_trader(&mut ctx, &market, 0, 100 * QUOTE).await;
+
+    let set_mode = |top_share_bps| {
+        instructions::set_matching_mode(
+            &admin.pubkey(),
+            &market.addresses,
+            MatchingMode::ProRata,
+            top_share_bps,
+        )
+    };
+    let res = ctx.send(&[set_mode(10_001)], &[&admin]).await;
+    assert_clob_error(res, ClobError::InvalidMatchingMode);
+    ctx.send(&[set_mode(2_000)], &[&admin]).await.unwrap();
+
+    for (maker, size) in [(&first, 10), (&second, 30)] {
+        ctx.send(
+            &[instructions::place_order(
+                &maker.kp.pubkey(),
+                &market.addresses,
+                &maker.open_orders,
+                &maker.base_account,
+                limit(Side::Ask, 2_000, size),
+                None,
+            )],
+            &[&maker.kp],
+        )
+        .await
+        .unwrap();
+    }
+    ctx.send(
+        &[instructions::place_order(
+            &taker.kp.pubkey(),
+            &market.addresses,
+            &taker.open_orders,
+            &taker.quote_account,
+            limit(Side::Bid, 2_000, 10),
+            None,
+        )],
+        &[&taker.kp],
+    )
+    .await
+    .unwrap();
+
+    // The oldest gets 20% of 10 lots on top, 8 * 8 / 38 rounded down and the lot lost
+    // to rounding; the other 8 * 30 / 38 rounded down.
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    let left: Vec<u64> = asks.orders.iter().map(|o| o.quantity).collect();
+    assert_eq!(left, vec![6, 24]);
+}
+
+#[tokio::test]
+async fn test_tick_schedule_by_price_band() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let trader = new_trader(&mut ctx, &market, BASE, 100 * QUOTE).await;
+
+    let band = |min_price, tick_size| TickBand {
+        min_price,
+        tick_size,
+    };
+    let set_schedule =
+        |bands| instructions::set_tick_schedule(&admin.pubkey(), &market.addresses, bands);
+    let res = ctx
+        .send(
+            &[set_schedule(vec![band(10_000, 100), band(1_000, 10)])],
+            &[&admin],
+        )
+        .await;
+    assert_clob_error(res, ClobError::InvalidTickSchedule);
+    // 10_005 is not on the tick of 10 below it.
+    let res = ctx
+        .send(
+            &[set_schedule(vec![band(1_000, 10), band(10_005, 5)])],
+            &[&admin],
+        )
+        .await;
+    assert_clob_error(res, ClobError::InvalidTickSchedule);
+    ctx.send(
+        &[set_schedule(vec![band(1_000, 10), band(10_000, 100)])],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    let ask = |price| {
+        instructions::place_order(
+            &trader.kp.pubkey(),
+            &market.addresses,
+            &trader.open_orders,
+            &trader.base_account,
+            limit(Side::Ask, price, 1),
+            None,
+        )
+    };
+    // Below the first band the market's tick size of 1 applies.
+    for (price, valid) in [
+        (999, true),
+        (1_005, false),
+        (1_010, true),
+        (10_050, false),
+        (10_100, true),
+    ] {
+        let res = ctx.send(&[ask(price)], &[&trader.kp]).await;
+        if valid {
+            res.unwrap();
+        } else {
+            assert_clob_error(res, ClobError::InvalidTick);
+        }
+    }
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert_eq!(asks.orders.len(), 3);
+}
+
+#[tokio::test]
+async fn test_order_minimums_and_dust_remainders() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let maker = new_trader(&mut ctx, &market, BASE, 0).await;
+    let taker = new_trader(&mut ctx, &market, 0, 100 * QUOTE).await;
+
+    // At least 5 base lots, and 20_000 native quote or 2 lots at 1_000.
+    ctx.send(
+        &[instructions::set_order_minimums(
+            &admin.pubkey(),
+            &market.addresses,
+            5,
+            20_000,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    let ask = |price, size| {
+        instructions::place_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.base_account,
+            limit(Side::Ask, price, size),
+            None,
+        )
+    };
+    for (price, size) in [(1_000, 4), (100, 10)] {
+        let res = ctx.send(&[ask(price, size)], &[&maker.kp]).await;
+        assert_clob_error(res, ClobError::OrderTooSmall);
+    }
+    ctx.send(&[ask(1_000, 10)], &[&maker.kp]).await.unwrap();
+
+    let bid = |size| {
+        instructions::place_order(
+            &taker.kp.pubkey(),
+            &market.addresses,
+            &taker.open_orders,
+            &taker.quote_account,
+            limit(Side::Bid, 1_000, size),
+            None,
+        )
+    };
+    // The maker's remaining 3 lots are cancelled rather than left resting.
+    ctx.send(&[bid(7)], &[&taker.kp]).await.unwrap();
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert!(asks.orders.is_empty());
+    ctx.send(
+        &[instructions::consume_events(
+            &market.addresses,
+            &ctx.context.payer.pubkey(),
+            &[maker.open_orders],
+            10,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let maker_oo = state::get::<OpenOrders>(&mut ctx, maker.open_orders).await;
+    assert_eq!(maker_oo.base_locked, 0);
+    assert_eq!(maker_oo.base_free, 3 * 1_000_000);
+    assert!(maker_oo.orders.is_empty());
+
+    // As is the taker's: 6 of 9 lots fill and nothing rests or stays locked.
+    ctx.send(&[ask(1_000, 6)], &[&maker.kp]).await.unwrap();
+    ctx.send(&[bid(9)], &[&taker.kp]).await.unwrap();
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert!(bids.orders.is_empty());
+    let taker_oo = state::get::<OpenOrders>(&mut ctx, taker.open_orders).await;
+    assert_eq!(taker_oo.quote_locked, 0);
+    assert_eq!(taker_oo.base_free, 13 * 1_000_000);
+}
+
+#[tokio::test]
+async fn test_full_book_evicts_the_worst_order() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let mut makers = Vec::new();
+    for _ in 0..3 {
+        makers.push(new_trader(&mut ctx, &market, BASE, 0).await);
+    }
+    let newcomer = new_trader(&mut ctx, &market, BASE, 0).await;
+
+    // 24, 24 and 16 asks fill the side, the last maker's at 2_048 to 2_063.
+    let mut price = 2_000;
+    for (maker, count) in makers.iter().zip([24, 24, 16]) {
+        for _ in 0..count {
+            ctx.send(
+                &[instructions::place_order(
+                    &maker.kp.pubkey(),
+                    &market.addresses,
+                    &maker.open_orders,
+                    &maker.base_account,
+                    limit(Side::Ask, price, 1),
+                    None,
+                )],
+                &[&maker.kp],
+            )
+            .await
+            .unwrap();
+            price += 1;
+        }
+    }
+
+    let ask = |price| {
+        instructions::place_order(
+            &newcomer.kp.pubkey(),
+            &market.addresses,
+            &newcomer.open_orders,
+            &newcomer.base_account,
+            limit(Side::Ask, price, 1),
+            None,
+        )
+    };
+    for price in [2_100, 2_063] {
+        let res = ctx.send(&[ask(price)], &[&newcomer.kp]).await;
+        assert_clob_error(res, ClobError::BookFull);
+    }
+    ctx.send(&[ask(2_062)], &[&newcomer.kp]).await.unwrap();
+
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert_eq!(asks.orders.len(), BookSide::MAX_ORDERS);
+    assert_eq!(asks.orders.last().unwrap().price, 2_062);
+    assert!(asks.orders.iter().all(|o| o.price != 2_063));
+
+    let evicted = &makers[2];
+    ctx.send(
+        &[instructions::consume_events(
+            &market.addresses,
+            &ctx.context.payer.pubkey(),
+            &[evicted.open_orders],
+            10,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, evicted.open_orders).await;
+    assert_eq!(open_orders.orders.len(), 15);
+    assert_eq!(open_orders.base_locked, 15 * 1_000_000);
+    assert_eq!(open_orders.base_free, 1_000_000);
+}
+
+#[tokio::test]
+async fn test_missed_heartbeat_lets_anyone_cancel() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let maker = new_trader(&mut ctx, &market, BASE, 0).await;
+    for price in [2_000, 2_010] {
+        ctx.send(
+            &[instructions::place_order(
+                &maker.kp.pubkey(),
+                &market.addresses,
+                &maker.open_orders,
+                &maker.base_account,
+                limit(Side::Ask, price, 5),
+                None,
+            )],
+            &[&maker.kp],
+        )
+        .await
+        .unwrap();
+    }
+
+    let cancel_stale = instructions::cancel_stale_orders(&market.addresses, &maker.open_orders);
+    let res = ctx.send(std::slice::from_ref(&cancel_stale), &[]).await;
+    assert_clob_error(res, ClobError::HeartbeatNotMissed);
+
+    ctx.send(
+        &[instructions::set_heartbeat(
+            &maker.kp.pubkey(),
+            &maker.open_orders,
+            3_600,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+    ctx.fast_forward_seconds(1).await;
+    ctx.send(
+        &[instructions::heartbeat(
+            &maker.kp.pubkey(),
+            &maker.open_orders,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+    ctx.fast_forward_seconds(1).await;
+    let res = ctx.send(std::slice::from_ref(&cancel_stale), &[]).await;
+    assert_clob_error(res, ClobError::HeartbeatNotMissed);
+
+    ctx.fast_forward_seconds(3_600).await;
+    ctx.send(&[cancel_stale], &[]).await.unwrap();
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert!(asks.orders.is_empty());
+    let open_orders = state::get::<OpenOrders>(&mut ctx, maker.open_orders).await;
+    assert!(open_orders.orders.is_empty());
+    assert_eq!(open_orders.base_locked, 0);
+    assert_eq!(open_orders.base_free, 10 * 1_000_000);
+}
+
+#[tokio::test]
+async fn test_expected_sequence_orders_updates() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let maker = new_trader(&mut ctx, &market, BASE, 10 * QUOTE).await;
+    let place = |price, expected_sequence| {
+        instructions::place_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.base_account,
+            limit(Side::Ask, price, 5),
+            expected_sequence,
+        )
+    };
+
+    ctx.send(&[place(2_000, None)], &[&maker.kp]).await.unwrap();
+    let res = ctx.send(&[place(2_010, Some(0))], &[&maker.kp]).await;
+    assert_clob_error(res, ClobError::SequenceMismatch);
+    ctx.send(&[place(2_010, Some(1))], &[&maker.kp])
+        .await
+        .unwrap();
+
+    let batch = |expected_sequence| {
+        instructions::batch_orders(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.base_account,
+            &maker.quote_account,
+            vec![],
+            vec![limit(Side::Bid, 1_900, 5)],
+            expected_sequence,
+        )
+    };
+    let res = ctx.send(&[batch(Some(1))], &[&maker.kp]).await;
+    assert_clob_error(res, ClobError::SequenceMismatch);
+    ctx.send(&[batch(Some(2))], &[&maker.kp]).await.unwrap();
+
+    let open_orders = state::get::<OpenOrders>(&mut ctx, maker.open_orders).await;
+    assert_eq!(open_orders.sequence, 3);
+    assert_eq!(open_orders.orders.len(), 3);
+}
@@ -0,0 +1,325 @@
+mod common;
+use anchor_bpf_template::{
+    errors::ClobError,
+    state::{BookSide, OpenOrders, TriggerOrders, TwapOrder},
+    OrderParams, OrderType, RiskLimits, Side, TriggerCondition, TriggerOrderParams, TriggerSource,
+    TwapOrderParams,
+};
+use common::{
+    consts::{BASE, QUOTE},
+    fixtures::{
+        create_program_account, default_market_args, new_trader, setup_market, MarketFixture,
+        Trader,
+    },
+    instructions,
+    runner::{assert_clob_error, state},
+    setup::kp,
+    types::TestContext,
+};
+use solana_program_test::{tokio, BanksClientError};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+
+fn limit(side: Side, price: u64, max_base_lots: u64) -> OrderParams {
+    OrderParams {
+        side,
+        price,
+        max_base_lots,
+        order_type: OrderType::Limit,
+        client_order_id: price,
+        expiry_timestamp: 0,
+    }
+}
+
+async fn place(
+    ctx: &mut TestContext,
+    market: &MarketFixture,
+    trader: &Trader,
+    params: OrderParams,
+) -> Result<(), BanksClientError> {
+    let deposit = match params.side {
+        Side::Bid => trader.quote_account,
+        Side::Ask => trader.base_account,
+    };
+    ctx.send(
+        &[instructions::place_order(
+            &trader.kp.pubkey(),
+            &market.addresses,
+            &trader.open_orders,
+            &deposit,
+            params,
+            None,
+        )],
+        &[&trader.kp],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_admin_or_delegate_sets_risk_limits() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let trader = new_trader(&mut ctx, &market, BASE, 0).await;
+    let delegate = Keypair::new();
+    let set_limits = |signer: &Keypair, max_orders_per_slot| {
+        instructions::set_risk_limits(
+            &signer.pubkey(),
+            &market.addresses,
+            &trader.open_orders,
+            RiskLimits {
+                max_orders_per_slot,
+                ..RiskLimits::default()
+            },
+        )
+    };
+    let set_delegate = |delegate: Pubkey| {
+        instructions::set_delegate(&trader.kp.pubkey(), &trader.open_orders, delegate)
+    };
+
+    let res = ctx.send(&[set_limits(&trader.kp, 1)], &[&trader.kp]).await;
+    assert_clob_error(res, ClobError::Unauthorized);
+    let res = ctx.send(&[set_limits(&delegate, 1)], &[&delegate]).await;
+    assert_clob_error(res, ClobError::Unauthorized);
+
+    ctx.send(&[set_delegate(delegate.pubkey())], &[&trader.kp])
+        .await
+        .unwrap();
+    // A later slot keeps the retry from being deduplicated.
+    ctx.fast_forward_seconds(1).await;
+    ctx.send(&[set_limits(&delegate, 1)], &[&delegate])
+        .await
+        .unwrap();
+
+    // The owner cannot swap in a delegate that would lift them.
+    let res = ctx
+        .send(&[set_delegate(Pubkey::new_unique())], &[&trader.kp])
+        .await;
+    assert_clob_error(res, ClobError::RiskLimitsSet);
+    ctx.send(&[set_limits(&admin, 2)], &[&admin]).await.unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, trader.open_orders).await;
+    assert_eq!(open_orders.risk_limits.max_orders_per_slot, 2);
+
+    // Once they are lifted it can again.
+    ctx.send(&[set_limits(&admin, 0)], &[&admin]).await.unwrap();
+    ctx.send(&[set_delegate(Pubkey::new_unique())], &[&trader.kp])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_risk_limits_cap_order_placement() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let maker = new_trader(&mut ctx, &market, BASE, 10 * QUOTE).await;
+    let taker = new_trader(&mut ctx, &market, 0, 10 * QUOTE).await;
+    let set_limits = |limits| {
+        instructions::set_risk_limits(
+            &admin.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            limits,
+        )
+    };
+    ctx.send(
+        &[set_limits(RiskLimits {
+            max_orders_per_slot: 2,
+            max_open_notional: 200_000,
+            max_net_base_lots: 8,
+        })],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    // Three orders in one transaction are over the per-slot rate.
+    let batch = |orders: Vec<OrderParams>| {
+        instructions::batch_orders(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.base_account,
+            &maker.quote_account,
+            vec![],
+            orders,
+            None,
+        )
+    };
+    let res = ctx
+        .send(&[batch(vec![limit(Side::Ask, 2_100, 1); 3])], &[&maker.kp])
+        .await;
+    assert_clob_error(res, ClobError::OrderRateLimitExceeded);
+
+    // A lot is worth 10 native quote per tick of price.
+    let res = place(&mut ctx, &market, &maker, limit(Side::Bid, 2_600, 8)).await;
+    assert_clob_error(res, ClobError::OpenNotionalLimitExceeded);
+    ctx.fast_forward_seconds(1).await;
+    place(&mut ctx, &market, &maker, limit(Side::Ask, 2_000, 5))
+        .await
+        .unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, maker.open_orders).await;
+    assert_eq!(open_orders.open_ask_lots, 5);
+    assert_eq!(open_orders.open_notional, 100_000);
+
+    // Another 4 lots of asks could leave the maker short 9.
+    let res = place(&mut ctx, &market, &maker, limit(Side::Ask, 2_010, 4)).await;
+    assert_clob_error(res, ClobError::PositionLimitExceeded);
+
+    place(&mut ctx, &market, &taker, limit(Side::Bid, 2_000, 5))
+        .await
+        .unwrap();
+    ctx.send(
+        &[instructions::consume_events(
+            &market.addresses,
+            &admin.pubkey(),
+            &[maker.open_orders],
+            10,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, maker.open_orders).await;
+    assert_eq!(open_orders.net_base_lots, -5);
+    assert_eq!(open_orders.open_ask_lots, 0);
+    assert_eq!(open_orders.open_notional, 0);
+
+    // Below the new cap only orders that reduce the short are allowed.
+    ctx.send(
+        &[set_limits(RiskLimits {
+            max_net_base_lots: 2,
+            ..RiskLimits::default()
+        })],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let res = place(&mut ctx, &market, &maker, limit(Side::Ask, 2_010, 1)).await;
+    assert_clob_error(res, ClobError::PositionLimitExceeded);
+    place(&mut ctx, &market, &maker, limit(Side::Bid, 1_900, 3))
+        .await
+        .unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, maker.open_orders).await;
+    assert_eq!(open_orders.open_bid_lots, 3);
+    assert_eq!(open_orders.exposure().max_short, 5);
+}
+
+#[tokio::test]
+async fn test_risk_limits_cap_trigger_and_twap_orders() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let maker = new_trader(&mut ctx, &market, 0, 10 * QUOTE).await;
+    let seller = new_trader(&mut ctx, &market, BASE, 0).await;
+    ctx.send(
+        &[instructions::set_risk_limits(
+            &admin.pubkey(),
+            &market.addresses,
+            &seller.open_orders,
+            RiskLimits {
+                max_net_base_lots: 3,
+                ..RiskLimits::default()
+            },
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    place(&mut ctx, &market, &maker, limit(Side::Bid, 2_000, 10))
+        .await
+        .unwrap();
+
+    // Both are checked as if resting in full when placed.
+    let trigger_orders = kp();
+    let stop_loss = |max_base_lots| {
+        instructions::place_trigger_order(
+            &seller.kp.pubkey(),
+            &market.addresses,
+            &seller.open_orders,
+            &trigger_orders.pubkey(),
+            &seller.base_account,
+            TriggerOrderParams {
+                side: Side::Ask,
+                order_type: OrderType::ImmediateOrCancel,
+                price: 1_900,
+                max_base_lots,
+                client_order_id: 1,
+                source: TriggerSource::LastTrade,
+                condition: TriggerCondition::Below,
+                trigger_price: 1_500,
+            },
+        )
+    };
+    ctx.send(
+        &[
+            create_program_account(
+                &ctx,
+                &seller.kp.pubkey(),
+                &trigger_orders.pubkey(),
+                8 + TriggerOrders::LEN,
+            ),
+            instructions::init_trigger_orders(
+                &seller.kp.pubkey(),
+                &seller.open_orders,
+                &trigger_orders.pubkey(),
+            ),
+        ],
+        &[&seller.kp, &trigger_orders],
+    )
+    .await
+    .unwrap();
+    let res = ctx.send(&[stop_loss(4)], &[&seller.kp]).await;
+    assert_clob_error(res, ClobError::PositionLimitExceeded);
+    ctx.send(&[stop_loss(3)], &[&seller.kp]).await.unwrap();
+
+    let twap_order = kp();
+    let create_account = create_program_account(
+        &ctx,
+        &seller.kp.pubkey(),
+        &twap_order.pubkey(),
+        8 + TwapOrder::LEN,
+    );
+    let create_twap = |total_base_lots| {
+        [
+            create_account.clone(),
+            instructions::place_twap_order(
+                &seller.kp.pubkey(),
+                &market.addresses,
+                &seller.open_orders,
+                &twap_order.pubkey(),
+                &seller.base_account,
+                TwapOrderParams {
+                    side: Side::Ask,
+                    price: 1_900,
+                    total_base_lots,
+                    slice_base_lots: 1,
+                    interval_seconds: 60,
+                    client_order_id: 2,
+                },
+            ),
+        ]
+    };
+    let res = ctx.send(&create_twap(4), &[&seller.kp, &twap_order]).await;
+    assert_clob_error(res, ClobError::PositionLimitExceeded);
+    ctx.send(&create_twap(3), &[&seller.kp, &twap_order])
+        .await
+        .unwrap();
+
+    // A slice that no longer fits under the limits is skipped.
+    place(&mut ctx, &market, &seller, limit(Side::Ask, 2_100, 3))
+        .await
+        .unwrap();
+    ctx.send(
+        &[instructions::execute_twap_slice(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &seller.open_orders,
+            &twap_order.pubkey(),
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+    let twap = state::get::<TwapOrder>(&mut ctx, twap_order.pubkey()).await;
+    assert_eq!(twap.remaining_base_lots, 2);
+    assert_eq!(twap.base_lots_filled, 0);
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert_eq!(bids.orders[0].quantity, 10);
+}
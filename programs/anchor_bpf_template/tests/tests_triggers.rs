@@ -0,0 +1,542 @@
+mod common;
+use anchor_bpf_template::{
+    errors::ClobError,
+    state::{BookSide, OpenOrders, TriggerOrders},
+    OrderParams, OrderType, Side, TriggerCondition, TriggerOrderParams, TriggerSource,
+};
+use common::{
+    consts::{BASE, BASE_LOT, QUOTE},
+    fixtures::{
+        create_program_account, default_market_args, new_trader, order, setup_market,
+        MarketFixture, Trader, SOL,
+    },
+    instructions,
+    runner::{assert_clob_error, state},
+    setup::kp,
+    types::TestContext,
+};
+use solana_program_test::tokio;
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+
+fn stop_loss(trigger_price: u64, client_order_id: u64) -> TriggerOrderParams {
+    TriggerOrderParams {
+        side: Side::Ask,
+        order_type: OrderType::ImmediateOrCancel,
+        price: 1_900,
+        max_base_lots: 5,
+        client_order_id,
+        source: TriggerSource::LastTrade,
+        condition: TriggerCondition::Below,
+        trigger_price,
+    }
+}
+
+#[tokio::test]
+async fn test_stop_loss_executes_once_last_trade_crosses() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let maker = new_trader(&mut ctx, &market, BASE, 10 * QUOTE).await;
+    let seller = new_trader(&mut ctx, &market, BASE, 0).await;
+    let executor = ctx.new_keypair(SOL::from(1.0)).await;
+
+    let trigger_orders = kp();
+    ctx.send(
+        &[
+            create_program_account(
+                &ctx,
+                &seller.kp.pubkey(),
+                &trigger_orders.pubkey(),
+                8 + TriggerOrders::LEN,
+            ),
+            instructions::init_trigger_orders(
+                &seller.kp.pubkey(),
+                &seller.open_orders,
+                &trigger_orders.pubkey(),
+            ),
+        ],
+        &[&seller.kp, &trigger_orders],
+    )
+    .await
+    .unwrap();
+
+    let place_trigger = |params| {
+        instructions::place_trigger_order(
+            &seller.kp.pubkey(),
+            &market.addresses,
+            &seller.open_orders,
+            &trigger_orders.pubkey(),
+            &seller.base_account,
+            params,
+        )
+    };
+    ctx.send(
+        &[
+            place_trigger(stop_loss(1_950, 1)),
+            place_trigger(stop_loss(1_800, 2)),
+        ],
+        &[&seller.kp],
+    )
+    .await
+    .unwrap();
+
+    // The base is locked off the book until the triggers fire.
+    let open_orders = state::get::<OpenOrders>(&mut ctx, seller.open_orders).await;
+    assert_eq!(open_orders.base_locked, 10 * BASE_LOT);
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert!(asks.orders.is_empty());
+
+    // No trade yet, so nothing fires.
+    let execute = |executor: &Pubkey| {
+        instructions::execute_triggers(
+            executor,
+            &market.addresses,
+            &seller.open_orders,
+            &trigger_orders.pubkey(),
+        )
+    };
+    let res = ctx.send(&[execute(&maker.kp.pubkey())], &[&maker.kp]).await;
+    assert_clob_error(res, ClobError::NoTriggersReady);
+
+    // A trade at 1940 crosses the first stop only.
+    ctx.send(
+        &[
+            instructions::place_order(
+                &maker.kp.pubkey(),
+                &market.addresses,
+                &maker.open_orders,
+                &maker.quote_account,
+                order(Side::Bid, 1_940, 10, 1),
+                None,
+            ),
+            instructions::place_order(
+                &maker.kp.pubkey(),
+                &market.addresses,
+                &maker.open_orders,
+                &maker.base_account,
+                order(Side::Ask, 1_940, 1, 2),
+                None,
+            ),
+        ],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+
+    let lamports_before = ctx
+        .context
+        .banks_client
+        .get_balance(executor.pubkey())
+        .await
+        .unwrap();
+    ctx.send(&[execute(&executor.pubkey())], &[&executor])
+        .await
+        .unwrap();
+    let lamports_after = ctx
+        .context
+        .banks_client
+        .get_balance(executor.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(
+        lamports_after - lamports_before,
+        TriggerOrders::EXECUTION_REWARD
+    );
+
+    // The stop sold 5 lots into the remaining 9 lot bid at 1940.
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert_eq!(bids.orders[0].quantity, 4);
+    let triggers = state::get::<TriggerOrders>(&mut ctx, trigger_orders.pubkey()).await;
+    assert_eq!(triggers.triggers.len(), 1);
+    assert_eq!(triggers.triggers[0].client_order_id, 2);
+    let open_orders = state::get::<OpenOrders>(&mut ctx, seller.open_orders).await;
+    assert_eq!(open_orders.base_locked, 5 * BASE_LOT);
+    assert_eq!(open_orders.base_free, 0);
+
+    // Cancelling the other releases its base.
+    let trigger_id = triggers.triggers[0].trigger_id;
+    ctx.send(
+        &[instructions::cancel_trigger_order(
+            &seller.kp.pubkey(),
+            &seller.open_orders,
+            &trigger_orders.pubkey(),
+            trigger_id,
+        )],
+        &[&seller.kp],
+    )
+    .await
+    .unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, seller.open_orders).await;
+    assert_eq!(open_orders.base_locked, 0);
+    assert_eq!(open_orders.base_free, 5 * BASE_LOT);
+    let triggers = state::get::<TriggerOrders>(&mut ctx, trigger_orders.pubkey()).await;
+    assert!(triggers.triggers.is_empty());
+}
+
+/// Rests a take-profit ask at `take_profit` and places a stop below `stop`, then links
+/// them as `group_id`.
+async fn bracket(
+    ctx: &mut TestContext,
+    market: &MarketFixture,
+    seller: &Trader,
+    trigger_orders: &Pubkey,
+    take_profit: u64,
+    stop: u64,
+    group_id: u64,
+) {
+    ctx.send(
+        &[
+            instructions::place_order(
+                &seller.kp.pubkey(),
+                &market.addresses,
+                &seller.open_orders,
+                &seller.base_account,
+                order(Side::Ask, take_profit, 5, group_id),
+                None,
+            ),
+            instructions::place_trigger_order(
+                &seller.kp.pubkey(),
+                &market.addresses,
+                &seller.open_orders,
+                trigger_orders,
+                &seller.base_account,
+                stop_loss(stop, group_id),
+            ),
+        ],
+        &[&seller.kp],
+    )
+    .await
+    .unwrap();
+    let order_id = state::get::<OpenOrders>(ctx, seller.open_orders)
+        .await
+        .orders
+        .last()
+        .unwrap()
+        .order_id;
+    let trigger_id = state::get::<TriggerOrders>(ctx, *trigger_orders)
+        .await
+        .triggers
+        .last()
+        .unwrap()
+        .trigger_id;
+    let link = |order_ids, trigger_ids| {
+        instructions::link_orders(
+            &seller.kp.pubkey(),
+            &market.addresses,
+            &seller.open_orders,
+            Some(trigger_orders),
+            group_id,
+            order_ids,
+            trigger_ids,
+        )
+    };
+    let res = ctx
+        .send(&[link(vec![order_id], vec![])], &[&seller.kp])
+        .await;
+    assert_clob_error(res, ClobError::InvalidGroup);
+    ctx.send(&[link(vec![order_id], vec![trigger_id])], &[&seller.kp])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_bracket_group_cancels_the_other_leg() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let buyer = new_trader(&mut ctx, &market, BASE, 100 * QUOTE).await;
+    let seller = new_trader(&mut ctx, &market, BASE, 0).await;
+    let executor = ctx.new_keypair(SOL::from(1.0)).await;
+
+    let trigger_orders = kp();
+    ctx.send(
+        &[
+            create_program_account(
+                &ctx,
+                &seller.kp.pubkey(),
+                &trigger_orders.pubkey(),
+                8 + TriggerOrders::LEN,
+            ),
+            instructions::init_trigger_orders(
+                &seller.kp.pubkey(),
+                &seller.open_orders,
+                &trigger_orders.pubkey(),
+            ),
+        ],
+        &[&seller.kp, &trigger_orders],
+    )
+    .await
+    .unwrap();
+
+    let execute = |executor: &Pubkey| {
+        instructions::execute_triggers(
+            executor,
+            &market.addresses,
+            &seller.open_orders,
+            &trigger_orders.pubkey(),
+        )
+    };
+
+    // The stop fires after a trade at 1940 and takes the take-profit off the book.
+    bracket(
+        &mut ctx,
+        &market,
+        &seller,
+        &trigger_orders.pubkey(),
+        2_100,
+        1_950,
+        1,
+    )
+    .await;
+    ctx.send(
+        &[
+            instructions::place_order(
+                &buyer.kp.pubkey(),
+                &market.addresses,
+                &buyer.open_orders,
+                &buyer.quote_account,
+                order(Side::Bid, 1_940, 10, 1),
+                None,
+            ),
+            instructions::place_order(
+                &buyer.kp.pubkey(),
+                &market.addresses,
+                &buyer.open_orders,
+                &buyer.base_account,
+                order(Side::Ask, 1_940, 1, 2),
+                None,
+            ),
+        ],
+        &[&buyer.kp],
+    )
+    .await
+    .unwrap();
+    ctx.send(&[execute(&executor.pubkey())], &[&executor])
+        .await
+        .unwrap();
+
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert!(asks.orders.is_empty());
+    let open_orders = state::get::<OpenOrders>(&mut ctx, seller.open_orders).await;
+    assert!(open_orders.orders.is_empty());
+    assert_eq!(open_orders.base_locked, 0);
+
+    // The take-profit fills first, and the stop is dropped before it could ever fire.
+    bracket(
+        &mut ctx,
+        &market,
+        &seller,
+        &trigger_orders.pubkey(),
+        2_200,
+        1_800,
+        2,
+    )
+    .await;
+    ctx.send(
+        &[instructions::place_order(
+            &buyer.kp.pubkey(),
+            &market.addresses,
+            &buyer.open_orders,
+            &buyer.quote_account,
+            OrderParams {
+                order_type: OrderType::ImmediateOrCancel,
+                ..order(Side::Bid, 2_200, 5, 3)
+            },
+            None,
+        )],
+        &[&buyer.kp],
+    )
+    .await
+    .unwrap();
+    // Executed by someone else, so the transaction differs from the first.
+    ctx.send(&[execute(&buyer.kp.pubkey())], &[&buyer.kp])
+        .await
+        .unwrap();
+
+    let triggers = state::get::<TriggerOrders>(&mut ctx, trigger_orders.pubkey()).await;
+    assert!(triggers.triggers.is_empty());
+    let open_orders = state::get::<OpenOrders>(&mut ctx, seller.open_orders).await;
+    assert_eq!(open_orders.base_free, 5 * BASE_LOT);
+
+    // Two resting asks linked one-cancels-other: lifting one pulls the other in matching.
+    ctx.send(
+        &[
+            instructions::place_order(
+                &seller.kp.pubkey(),
+                &market.addresses,
+                &seller.open_orders,
+                &seller.base_account,
+                order(Side::Ask, 2_300, 5, 5),
+                None,
+            ),
+            instructions::place_order(
+                &seller.kp.pubkey(),
+                &market.addresses,
+                &seller.open_orders,
+                &seller.base_account,
+                order(Side::Ask, 2_400, 5, 6),
+                None,
+            ),
+        ],
+        &[&seller.kp],
+    )
+    .await
+    .unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, seller.open_orders).await;
+    // The filled take-profit stays listed until its fill is consumed.
+    let order_ids = open_orders
+        .orders
+        .iter()
+        .rev()
+        .take(2)
+        .map(|o| o.order_id)
+        .collect();
+    ctx.send(
+        &[instructions::link_orders(
+            &seller.kp.pubkey(),
+            &market.addresses,
+            &seller.open_orders,
+            None,
+            3,
+            order_ids,
+            vec![],
+        )],
+        &[&seller.kp],
+    )
+    .await
+    .unwrap();
+    ctx.send(
+        &[instructions::place_order(
+            &buyer.kp.pubkey(),
+            &market.addresses,
+            &buyer.open_orders,
+            &buyer.quote_account,
+            OrderParams {
+                order_type: OrderType::ImmediateOrCancel,
+                ..order(Side::Bid, 2_300, 2, 7)
+            },
+            None,
+        )],
+        &[&buyer.kp],
+    )
+    .await
+    .unwrap();
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert_eq!(asks.orders.len(), 1);
+    assert_eq!((asks.orders[0].price, asks.orders[0].quantity), (2_300, 3));
+    assert_eq!(asks.orders[0].group_id, 0);
+}
+
+#[tokio::test]
+async fn test_ioc_triggers_wait_for_batch_mode_to_end() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let maker = new_trader(&mut ctx, &market, BASE, 10 * QUOTE).await;
+    let seller = new_trader(&mut ctx, &market, BASE, 0).await;
+    let executor = ctx.new_keypair(SOL::from(1.0)).await;
+
+    let trigger_orders = kp();
+    ctx.send(
+        &[
+            create_program_account(
+                &ctx,
+                &seller.kp.pubkey(),
+                &trigger_orders.pubkey(),
+                8 + TriggerOrders::LEN,
+            ),
+            instructions::init_trigger_orders(
+                &seller.kp.pubkey(),
+                &seller.open_orders,
+                &trigger_orders.pubkey(),
+            ),
+        ],
+        &[&seller.kp, &trigger_orders],
+    )
+    .await
+    .unwrap();
+    let place_trigger = |params| {
+        instructions::place_trigger_order(
+            &seller.kp.pubkey(),
+            &market.addresses,
+            &seller.open_orders,
+            &trigger_orders.pubkey(),
+            &seller.base_account,
+            params,
+        )
+    };
+    let set_batch = |batch_slots| {
+        instructions::set_batch_auction(&admin.pubkey(), &market.addresses, batch_slots)
+    };
+    ctx.send(
+        &[
+            place_trigger(stop_loss(1_950, 1)),
+            place_trigger(TriggerOrderParams {
+                order_type: OrderType::Limit,
+                ..stop_loss(1_950, 2)
+            }),
+        ],
+        &[&seller.kp],
+    )
+    .await
+    .unwrap();
+    ctx.send(&[set_batch(10)], &[&admin]).await.unwrap();
+
+    // A batch cleared at 1940 crosses both stops.
+    ctx.send(
+        &[
+            instructions::place_order(
+                &maker.kp.pubkey(),
+                &market.addresses,
+                &maker.open_orders,
+                &maker.quote_account,
+                order(Side::Bid, 1_940, 10, 1),
+                None,
+            ),
+            instructions::place_order(
+                &maker.kp.pubkey(),
+                &market.addresses,
+                &maker.open_orders,
+                &maker.base_account,
+                order(Side::Ask, 1_940, 1, 2),
+                None,
+            ),
+        ],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+    ctx.fast_forward_seconds(1).await;
+    ctx.send(&[instructions::clear_batch(&market.addresses)], &[])
+        .await
+        .unwrap();
+
+    // Only the limit order joins the batch, the stop market order stays pending.
+    let execute = |executor: &Pubkey| {
+        instructions::execute_triggers(
+            executor,
+            &market.addresses,
+            &seller.open_orders,
+            &trigger_orders.pubkey(),
+        )
+    };
+    ctx.send(&[execute(&executor.pubkey())], &[&executor])
+        .await
+        .unwrap();
+    let triggers = state::get::<TriggerOrders>(&mut ctx, trigger_orders.pubkey()).await;
+    assert_eq!(triggers.triggers.len(), 1);
+    assert_eq!(triggers.triggers[0].client_order_id, 1);
+    let asks = state::get::<BookSide>(&mut ctx, market.addresses.asks).await;
+    assert_eq!((asks.orders[0].price, asks.orders[0].quantity), (1_900, 5));
+    let res = ctx.send(&[execute(&maker.kp.pubkey())], &[&maker.kp]).await;
+    assert_clob_error(res, ClobError::NoTriggersReady);
+
+    // Leaving batch mode fills the limit order, then the stop sells into what is left.
+    ctx.send(&[set_batch(0)], &[&admin]).await.unwrap();
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert_eq!(bids.orders[0].quantity, 4);
+    // A later slot keeps the retry from being deduplicated.
+    ctx.fast_forward_seconds(1).await;
+    ctx.send(&[execute(&executor.pubkey())], &[&executor])
+        .await
+        .unwrap();
+    let triggers = state::get::<TriggerOrders>(&mut ctx, trigger_orders.pubkey()).await;
+    assert!(triggers.triggers.is_empty());
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert!(bids.orders.is_empty());
+}
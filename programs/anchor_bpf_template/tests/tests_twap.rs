@@ -0,0 +1,253 @@
+mod common;
+use anchor_bpf_template::{
+    errors::ClobError,
+    state::{BookSide, OpenOrders, TwapOrder},
+    OrderParams, OrderType, Side, TwapOrderParams,
+};
+use common::{
+    consts::{BASE, BASE_LOT, QUOTE},
+    fixtures::{create_program_account, default_market_args, new_trader, setup_market, SOL},
+    instructions,
+    runner::{assert_clob_error, state},
+    setup::kp,
+};
+use solana_program_test::tokio;
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn test_twap_sells_one_slice_per_interval() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let maker = new_trader(&mut ctx, &market, 0, 10 * QUOTE).await;
+    let seller = new_trader(&mut ctx, &market, BASE, 0).await;
+    let executor = ctx.new_keypair(SOL::from(1.0)).await;
+    let other_executor = ctx.new_keypair(SOL::from(1.0)).await;
+
+    ctx.send(
+        &[instructions::place_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.quote_account,
+            OrderParams {
+                side: Side::Bid,
+                price: 2_000,
+                max_base_lots: 20,
+                order_type: OrderType::Limit,
+                client_order_id: 1,
+                expiry_timestamp: 0,
+            },
+            None,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+
+    let invalid = TwapOrderParams {
+        side: Side::Ask,
+        price: 1_900,
+        total_base_lots: 10,
+        slice_base_lots: 4,
+        interval_seconds: 0,
+        client_order_id: 7,
+    };
+    let twap_order = kp();
+    let create_account = create_program_account(
+        &ctx,
+        &seller.kp.pubkey(),
+        &twap_order.pubkey(),
+        8 + TwapOrder::LEN,
+    );
+    let create_twap = |params| {
+        [
+            create_account.clone(),
+            instructions::place_twap_order(
+                &seller.kp.pubkey(),
+                &market.addresses,
+                &seller.open_orders,
+                &twap_order.pubkey(),
+                &seller.base_account,
+                params,
+            ),
+        ]
+    };
+    let res = ctx
+        .send(&create_twap(invalid.clone()), &[&seller.kp, &twap_order])
+        .await;
+    assert_clob_error(res, ClobError::InvalidTwap);
+    let params = TwapOrderParams {
+        interval_seconds: 60,
+        ..invalid
+    };
+    ctx.send(&create_twap(params), &[&seller.kp, &twap_order])
+        .await
+        .unwrap();
+
+    // All ten lots are locked up front.
+    let open_orders = state::get::<OpenOrders>(&mut ctx, seller.open_orders).await;
+    assert_eq!(open_orders.base_locked, 10 * BASE_LOT);
+
+    let execute = |executor: &solana_sdk::pubkey::Pubkey| {
+        instructions::execute_twap_slice(
+            executor,
+            &market.addresses,
+            &seller.open_orders,
+            &twap_order.pubkey(),
+        )
+    };
+    // The first slice is due right away, the next one only after the interval.
+    ctx.send(&[execute(&executor.pubkey())], &[&executor])
+        .await
+        .unwrap();
+    let res = ctx
+        .send(&[execute(&other_executor.pubkey())], &[&other_executor])
+        .await;
+    assert_clob_error(res, ClobError::TwapNotDue);
+
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert_eq!(bids.orders[0].quantity, 16);
+    let twap = state::get::<TwapOrder>(&mut ctx, twap_order.pubkey()).await;
+    assert_eq!(twap.remaining_base_lots, 6);
+    assert_eq!(twap.base_lots_filled, 4);
+    let open_orders = state::get::<OpenOrders>(&mut ctx, seller.open_orders).await;
+    assert_eq!(open_orders.base_locked, 6 * BASE_LOT);
+
+    ctx.fast_forward_seconds(60).await;
+    ctx.send(&[execute(&other_executor.pubkey())], &[&other_executor])
+        .await
+        .unwrap();
+    ctx.fast_forward_seconds(60).await;
+    let executor_before = ctx
+        .context
+        .banks_client
+        .get_balance(executor.pubkey())
+        .await
+        .unwrap();
+    ctx.send(&[execute(&executor.pubkey())], &[&executor])
+        .await
+        .unwrap();
+    let executor_after = ctx
+        .context
+        .banks_client
+        .get_balance(executor.pubkey())
+        .await
+        .unwrap();
+    assert_eq!(executor_after - executor_before, TwapOrder::SLICE_REWARD);
+
+    // The last slice is the two lots left, after which nothing is due.
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert_eq!(bids.orders[0].quantity, 10);
+    let twap = state::get::<TwapOrder>(&mut ctx, twap_order.pubkey()).await;
+    assert_eq!(twap.remaining_base_lots, 0);
+    assert_eq!(twap.locked, 0);
+    ctx.fast_forward_seconds(60).await;
+    let res = ctx
+        .send(&[execute(&other_executor.pubkey())], &[&other_executor])
+        .await;
+    assert_clob_error(res, ClobError::TwapNotDue);
+
+    // Closing the completed order leaves the base free and nothing locked.
+    ctx.send(
+        &[instructions::cancel_twap_order(
+            &seller.kp.pubkey(),
+            &seller.open_orders,
+            &twap_order.pubkey(),
+        )],
+        &[&seller.kp],
+    )
+    .await
+    .unwrap();
+    let open_orders = state::get::<OpenOrders>(&mut ctx, seller.open_orders).await;
+    assert_eq!(open_orders.base_locked, 0);
+    assert_eq!(open_orders.base_free, 0);
+    assert!(open_orders.quote_free > 0);
+}
+
+#[tokio::test]
+async fn test_twap_slices_are_skipped_in_batch_mode() {
+    let (mut ctx, market) = setup_market(default_market_args()).await;
+    let admin = ctx.initial_market_owner.clone();
+    let maker = new_trader(&mut ctx, &market, 0, 10 * QUOTE).await;
+    let seller = new_trader(&mut ctx, &market, BASE, 0).await;
+    let executor = ctx.new_keypair(SOL::from(1.0)).await;
+    ctx.send(
+        &[instructions::place_order(
+            &maker.kp.pubkey(),
+            &market.addresses,
+            &maker.open_orders,
+            &maker.quote_account,
+            OrderParams {
+                side: Side::Bid,
+                price: 2_000,
+                max_base_lots: 10,
+                order_type: OrderType::Limit,
+                client_order_id: 1,
+                expiry_timestamp: 0,
+            },
+            None,
+        )],
+        &[&maker.kp],
+    )
+    .await
+    .unwrap();
+
+    let twap_order = kp();
+    ctx.send(
+        &[
+            create_program_account(
+                &ctx,
+                &seller.kp.pubkey(),
+                &twap_order.pubkey(),
+                8 + TwapOrder::LEN,
+            ),
+            instructions::place_twap_order(
+                &seller.kp.pubkey(),
+                &market.addresses,
+                &seller.open_orders,
+                &twap_order.pubkey(),
+                &seller.base_account,
+                TwapOrderParams {
+                    side: Side::Ask,
+                    price: 1_900,
+                    total_base_lots: 4,
+                    slice_base_lots: 2,
+                    interval_seconds: 60,
+                    client_order_id: 7,
+                },
+            ),
+        ],
+        &[&seller.kp, &twap_order],
+    )
+    .await
+    .unwrap();
+    let set_batch = |batch_slots| {
+        instructions::set_batch_auction(&admin.pubkey(), &market.addresses, batch_slots)
+    };
+    let execute = instructions::execute_twap_slice(
+        &executor.pubkey(),
+        &market.addresses,
+        &seller.open_orders,
+        &twap_order.pubkey(),
+    );
+
+    // The slice due during the batch is skipped, not placed.
+    ctx.send(&[set_batch(10)], &[&admin]).await.unwrap();
+    ctx.send(std::slice::from_ref(&execute), &[&executor])
+        .await
+        .unwrap();
+    let twap = state::get::<TwapOrder>(&mut ctx, twap_order.pubkey()).await;
+    assert_eq!((twap.remaining_base_lots, twap.base_lots_filled), (2, 0));
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert_eq!(bids.orders[0].quantity, 10);
+    let open_orders = state::get::<OpenOrders>(&mut ctx, seller.open_orders).await;
+    assert_eq!(open_orders.base_locked, 2 * BASE_LOT);
+
+    // Back to continuous matching, the next slice trades.
+    ctx.send(&[set_batch(0)], &[&admin]).await.unwrap();
+    ctx.fast_forward_seconds(60).await;
+    ctx.send(&[execute], &[&executor]).await.unwrap();
+    let twap = state::get::<TwapOrder>(&mut ctx, twap_order.pubkey()).await;
+    assert_eq!((twap.remaining_base_lots, twap.base_lots_filled), (0, 2));
+    let bids = state::get::<BookSide>(&mut ctx, market.addresses.bids).await;
+    assert_eq!(bids.orders[0].quantity, 8);
+}
@@ -0,0 +1,124 @@
+//! Views of the bids and asks accounts.
+//!
+//! Prices are in quote lots per base lot and quantities in base lots, as stored on chain.
+//! Expired orders stay on the book until matching or a cancel removes them, so every view
+//! takes the current unix timestamp and leaves them out. Pegged orders are shown at the
+//! price they currently trade at, and only once the book has an oracle price.
+
+use anchor_lang::prelude::Pubkey;
+
+use crate::{BookSide, Order, Side, TickSizes};
+
+/// Aggregated quantity at one price.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct L2Level {
+    pub price: u64,
+    pub quantity: u64,
+    pub order_count: usize,
+}
+
+/// Both sides of a market's book.
+#[derive(Debug, Default)]
+pub struct Book {
+    pub bids: BookSide,
+    pub asks: BookSide,
+    /// Oracle price in quote lots per base lot that pegged orders are priced at.
+    pub oracle_price: Option<u64>,
+    /// The market's tick sizes, which pegged prices are rounded to.
+    pub tick_sizes: TickSizes,
+}
+
+impl Book {
+    pub fn new(bids: BookSide, asks: BookSide) -> Self {
+        Book {
+            bids,
+            asks,
+            ..Book::default()
+        }
+    }
+
+    /// Prices pegged orders at `oracle_price`, rounded to the tick of their band.
+    pub fn with_oracle_price(mut self, oracle_price: u64, tick_sizes: TickSizes) -> Self {
+        self.oracle_price = Some(oracle_price);
+        self.tick_sizes = tick_sizes;
+        self
+    }
+
+    pub fn side(&self, side: Side) -> &BookSide {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    /// Live orders on `side`, best price first and oldest first within a price. Pegged
+    /// orders have `price` set to their current price rather than their peg limit.
+    pub fn l3(&self, side: Side, now: i64) -> impl Iterator<Item = Order> {
+        let book_side = self.side(side);
+        let pegged = self.oracle_price.into_iter().flat_map(move |oracle_price| {
+            book_side.pegged_orders.iter().filter_map(move |o| {
+                let price = o.price(side, oracle_price, &self.tick_sizes)?;
+                Some(Order {
+                    price,
+                    ..o.order.clone()
+                })
+            })
+        });
+        let mut orders: Vec<Order> = book_side.orders.iter().cloned().chain(pegged).collect();
+        // Stable, so fixed orders keep their book order on equal prices and timestamps.
+        orders.sort_by(|a, b| {
+            let price = match side {
+                Side::Bid => b.price.cmp(&a.price),
+                Side::Ask => a.price.cmp(&b.price),
+            };
+            price.then(a.timestamp.cmp(&b.timestamp))
+        });
+        orders.into_iter().filter(move |o| !o.is_expired(now))
+    }
+
+    /// The best `depth` price levels on `side`.
+    pub fn l2(&self, side: Side, depth: usize, now: i64) -> Vec<L2Level> {
+        let mut levels: Vec<L2Level> = Vec::new();
+        for order in self.l3(side, now) {
+            match levels.last_mut() {
+                Some(level) if level.price == order.price => {
+                    level.quantity += order.quantity;
+                    level.order_count += 1;
+                }
+                _ => {
+                    if levels.len() == depth {
+                        break;
+                    }
+                    levels.push(L2Level {
+                        price: order.price,
+                        quantity: order.quantity,
+                        order_count: 1,
+                    });
+                }
+            }
+        }
+        levels
+    }
+
+    pub fn best_price(&self, side: Side, now: i64) -> Option<u64> {
+        self.l3(side, now).next().map(|o| o.price)
+    }
+
+    /// Best ask minus best bid, `None` unless both sides have live orders.
+    pub fn spread(&self, now: i64) -> Option<u64> {
+        let bid = self.best_price(Side::Bid, now)?;
+        let ask = self.best_price(Side::Ask, now)?;
+        ask.checked_sub(bid)
+    }
+
+    /// Live orders belonging to the `OpenOrders` account `owner`, on both sides.
+    pub fn orders_of<'a>(
+        &'a self,
+        owner: &'a Pubkey,
+        now: i64,
+    ) -> impl Iterator<Item = Order> + 'a {
+        self.l3(Side::Bid, now)
+            .chain(self.l3(Side::Ask, now))
+            .filter(move |o| o.owner == *owner)
+    }
+}
@@ -0,0 +1,481 @@
+//! High-level client that runs the same calls over any [`Transport`].
+//!
+//! `Transport` is implemented for `solana_banks_client::BanksClient` (feature `banks`),
+//! which is what `solana-program-test` hands out, and for the nonblocking
+//! `solana_client` `RpcClient` (feature `rpc`). Code written against
+//! `ClobClient<impl Transport>` runs unchanged in program-test and against a cluster, except
+//! for the calls that return what the program returned, which need a transport that reports
+//! return data.
+
+use std::sync::Arc;
+
+use anchor_bpf_template::state::{Market, OraclePrice};
+use anchor_lang::prelude::{Pubkey, Rent};
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::instruction::{Instruction, InstructionError};
+use anchor_lang::{AccountDeserialize, AnchorDeserialize};
+use async_trait::async_trait;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+use crate::book::Book;
+use crate::{
+    events, instructions, EventQueue, IcebergOrderParams, MarketAddresses, MidpointOrderParams,
+    OpenOrders, OrderParams, OrderSummary, PeggedOrderParams,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[cfg(feature = "banks")]
+    #[error(transparent)]
+    Banks(#[from] solana_banks_client::BanksClientError),
+    #[cfg(feature = "rpc")]
+    #[error(transparent)]
+    Rpc(#[from] solana_client::client_error::ClientError),
+    #[error("account {0} not found")]
+    AccountNotFound(Pubkey),
+    #[error("account {0} could not be decoded")]
+    InvalidAccountData(Pubkey),
+    #[error("the transport does not report return data")]
+    ReturnDataUnavailable,
+    #[error("the transaction did not return an order summary")]
+    InvalidReturnData,
+}
+
+impl ClientError {
+    /// The transaction error, if the transaction reached the runtime and failed there.
+    pub fn transaction_error(&self) -> Option<TransactionError> {
+        match self {
+            #[cfg(feature = "banks")]
+            ClientError::Banks(
+                solana_banks_client::BanksClientError::TransactionError(err)
+                | solana_banks_client::BanksClientError::SimulationError { err, .. },
+            ) => Some(err.clone()),
+            #[cfg(feature = "rpc")]
+            ClientError::Rpc(err) => err.get_transaction_error(),
+            _ => None,
+        }
+    }
+
+    /// The program's custom error code, see [`crate::ClobError`].
+    pub fn error_code(&self) -> Option<u32> {
+        match self.transaction_error()? {
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) => Some(code),
+            _ => None,
+        }
+    }
+}
+
+/// What the client needs from a connection to a bank.
+#[async_trait]
+pub trait Transport: Send {
+    async fn get_account_data(&mut self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError>;
+
+    async fn get_rent(&mut self) -> Result<Rent, ClientError>;
+
+    async fn get_latest_blockhash(&mut self) -> Result<Hash, ClientError>;
+
+    /// Sends a signed transaction and waits until it is processed.
+    async fn process_transaction(&mut self, transaction: Transaction) -> Result<(), ClientError>;
+
+    /// Like [`Transport::process_transaction`], also returning the program and data the
+    /// transaction's return data was last set to, if any. Fails with
+    /// [`ClientError::ReturnDataUnavailable`] without sending the transaction unless the
+    /// transport overrides it, which the `BanksClient` of this Solana version cannot.
+    async fn process_transaction_with_return_data(
+        &mut self,
+        _transaction: Transaction,
+    ) -> Result<Option<(Pubkey, Vec<u8>)>, ClientError> {
+        Err(ClientError::ReturnDataUnavailable)
+    }
+}
+
+#[cfg(feature = "banks")]
+#[async_trait]
+impl Transport for solana_banks_client::BanksClient {
+    async fn get_account_data(&mut self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+        Ok(self.get_account(*address).await?.map(|a| a.data))
+    }
+
+    async fn get_rent(&mut self) -> Result<Rent, ClientError> {
+        Ok(solana_banks_client::BanksClient::get_rent(self).await?)
+    }
+
+    async fn get_latest_blockhash(&mut self) -> Result<Hash, ClientError> {
+        Ok(solana_banks_client::BanksClient::get_latest_blockhash(self).await?)
+    }
+
+    async fn process_transaction(&mut self, transaction: Transaction) -> Result<(), ClientError> {
+        Ok(solana_banks_client::BanksClient::process_transaction(self, transaction).await?)
+    }
+}
+
+#[cfg(feature = "rpc")]
+#[async_trait]
+impl Transport for solana_client::nonblocking::rpc_client::RpcClient {
+    async fn get_account_data(&mut self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+        let account = self
+            .get_account_with_commitment(address, self.commitment())
+            .await?
+            .value;
+        Ok(account.map(|a| a.data))
+    }
+
+    async fn get_rent(&mut self) -> Result<Rent, ClientError> {
+        let address = anchor_lang::solana_program::sysvar::rent::ID;
+        let account = self.get_account(&address).await?;
+        solana_sdk::account::from_account(&account).ok_or(ClientError::InvalidAccountData(address))
+    }
+
+    async fn get_latest_blockhash(&mut self) -> Result<Hash, ClientError> {
+        Ok(solana_client::nonblocking::rpc_client::RpcClient::get_latest_blockhash(self).await?)
+    }
+
+    async fn process_transaction(&mut self, transaction: Transaction) -> Result<(), ClientError> {
+        self.send_and_confirm_transaction(&transaction).await?;
+        Ok(())
+    }
+
+    /// Reads the return data from the `Program return:` line of the transaction's logs.
+    async fn process_transaction_with_return_data(
+        &mut self,
+        transaction: Transaction,
+    ) -> Result<Option<(Pubkey, Vec<u8>)>, ClientError> {
+        use solana_client::rpc_config::RpcTransactionConfig;
+        use solana_sdk::commitment_config::CommitmentConfig;
+        use solana_transaction_status::UiTransactionEncoding;
+
+        let signature = self.send_and_confirm_transaction(&transaction).await?;
+        // Only confirmed transactions can be fetched.
+        let commitment = if self.commitment().is_at_least_confirmed() {
+            self.commitment()
+        } else {
+            CommitmentConfig::confirmed()
+        };
+        self.poll_for_signature_with_commitment(&signature, commitment)
+            .await?;
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(commitment),
+            max_supported_transaction_version: Some(0),
+        };
+        let logs = self
+            .get_transaction_with_config(&signature, config)
+            .await?
+            .transaction
+            .meta
+            .and_then(|meta| meta.log_messages)
+            .ok_or(ClientError::ReturnDataUnavailable)?;
+        Ok(events::parse_return_data(&logs))
+    }
+}
+
+/// One market, traded through `transport` with `payer` paying transaction fees.
+pub struct ClobClient<T> {
+    pub transport: T,
+    pub payer: Arc<Keypair>,
+    pub market: MarketAddresses,
+}
+
+impl<T: Transport> ClobClient<T> {
+    /// Reads the market at `market_key` to resolve the rest of its accounts.
+    pub async fn load(
+        mut transport: T,
+        payer: Arc<Keypair>,
+        market_key: Pubkey,
+    ) -> Result<Self, ClientError> {
+        let market: Market = fetch(&mut transport, &market_key).await?;
+        Ok(ClobClient {
+            transport,
+            payer,
+            market: MarketAddresses::from_market(market_key, &market),
+        })
+    }
+
+    pub async fn fetch<A: AccountDeserialize>(
+        &mut self,
+        address: &Pubkey,
+    ) -> Result<A, ClientError> {
+        fetch(&mut self.transport, address).await
+    }
+
+    pub async fn fetch_market(&mut self) -> Result<Market, ClientError> {
+        let address = self.market.market;
+        self.fetch(&address).await
+    }
+
+    /// The bids and asks, with pegged orders priced at the oracle's current price when the
+    /// market has an oracle.
+    pub async fn fetch_book(&mut self) -> Result<Book, ClientError> {
+        let (bids, asks) = (self.market.bids, self.market.asks);
+        let book = Book::new(self.fetch(&bids).await?, self.fetch(&asks).await?);
+        let oracle = match self.market.oracle {
+            Some(oracle) => oracle,
+            None => return Ok(book),
+        };
+        let market = self.fetch_market().await?;
+        let data = self
+            .transport
+            .get_account_data(&oracle)
+            .await?
+            .ok_or(ClientError::AccountNotFound(oracle))?;
+        let oracle_price = OraclePrice::from_data(market.oracle_kind, &data)
+            .and_then(|price| price.to_lots(&market))
+            .map_err(|_| ClientError::InvalidAccountData(oracle))?;
+        Ok(book.with_oracle_price(oracle_price, market.tick_sizes()))
+    }
+
+    pub async fn fetch_event_queue(&mut self) -> Result<EventQueue, ClientError> {
+        let address = self.market.event_queue;
+        self.fetch(&address).await
+    }
+
+    pub async fn fetch_open_orders(&mut self, address: &Pubkey) -> Result<OpenOrders, ClientError> {
+        self.fetch(address).await
+    }
+
+    /// Signs `instructions` with the payer and `signers` and sends them in one transaction.
+    pub async fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), ClientError> {
+        let transaction = self.sign(instructions, signers).await?;
+        self.transport.process_transaction(transaction).await
+    }
+
+    /// Sends `instruction` as [`ClobClient::send`] does and decodes the [`OrderSummary`] it
+    /// returns. Transports that do not report return data fail without sending it.
+    pub async fn send_for_summary(
+        &mut self,
+        instruction: Instruction,
+        signers: &[&Keypair],
+    ) -> Result<OrderSummary, ClientError> {
+        let transaction = self.sign(&[instruction], signers).await?;
+        match self
+            .transport
+            .process_transaction_with_return_data(transaction)
+            .await?
+        {
+            Some((program_id, data)) if program_id == anchor_bpf_template::ID => {
+                OrderSummary::try_from_slice(&data).map_err(|_| ClientError::InvalidReturnData)
+            }
+            _ => Err(ClientError::InvalidReturnData),
+        }
+    }
+
+    async fn sign(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<Transaction, ClientError> {
+        let payer = self.payer.clone();
+        let mut all_signers: Vec<&Keypair> = vec![&payer];
+        all_signers.extend(signers.iter().filter(|s| s.pubkey() != payer.pubkey()));
+        let blockhash = self.transport.get_latest_blockhash().await?;
+        Ok(Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &all_signers,
+            blockhash,
+        ))
+    }
+
+    /// Creates an `OpenOrders` account for `owner` on this market and returns its address.
+    pub async fn create_open_orders(&mut self, owner: &Keypair) -> Result<Pubkey, ClientError> {
+        let open_orders = Keypair::new();
+        let rent = self.transport.get_rent().await?;
+        let ixs = instructions::create_open_orders(
+            &self.payer.pubkey(),
+            &owner.pubkey(),
+            &self.market,
+            &open_orders.pubkey(),
+            &rent,
+        );
+        self.send(&ixs, &[owner, &open_orders]).await?;
+        Ok(open_orders.pubkey())
+    }
+
+    /// `signer` is the owner or delegate of `open_orders`. Returns what the program returned
+    /// about the order, see [`ClobClient::send_for_summary`].
+    pub async fn place_order(
+        &mut self,
+        signer: &Keypair,
+        open_orders: &Pubkey,
+        token_deposit_account: &Pubkey,
+        params: OrderParams,
+        expected_sequence: Option<u64>,
+    ) -> Result<OrderSummary, ClientError> {
+        let ix = instructions::place_order(
+            &signer.pubkey(),
+            &self.market,
+            open_orders,
+            token_deposit_account,
+            params,
+            expected_sequence,
+        );
+        self.send_for_summary(ix, &[signer]).await
+    }
+
+    /// Places an order that follows the market's oracle, `signer`, `expected_sequence` and
+    /// the summary returned as in [`ClobClient::place_order`].
+    pub async fn place_pegged_order(
+        &mut self,
+        signer: &Keypair,
+        open_orders: &Pubkey,
+        token_deposit_account: &Pubkey,
+        params: PeggedOrderParams,
+        expected_sequence: Option<u64>,
+    ) -> Result<OrderSummary, ClientError> {
+        let ix = instructions::place_pegged_order(
+            &signer.pubkey(),
+            &self.market,
+            open_orders,
+            token_deposit_account,
+            params,
+            expected_sequence,
+        );
+        self.send_for_summary(ix, &[signer]).await
+    }
+
+    /// Places a hidden order at the midpoint, `signer`, `expected_sequence` and the summary
+    /// returned as in [`ClobClient::place_order`].
+    pub async fn place_midpoint_order(
+        &mut self,
+        signer: &Keypair,
+        open_orders: &Pubkey,
+        token_deposit_account: &Pubkey,
+        params: MidpointOrderParams,
+        expected_sequence: Option<u64>,
+    ) -> Result<OrderSummary, ClientError> {
+        let ix = instructions::place_midpoint_order(
+            &signer.pubkey(),
+            &self.market,
+            open_orders,
+            token_deposit_account,
+            params,
+            expected_sequence,
+        );
+        self.send_for_summary(ix, &[signer]).await
+    }
+
+    /// Places an iceberg order showing part of its size, `signer`, `expected_sequence` and
+    /// the summary returned as in [`ClobClient::place_order`].
+    pub async fn place_iceberg_order(
+        &mut self,
+        signer: &Keypair,
+        open_orders: &Pubkey,
+        token_deposit_account: &Pubkey,
+        params: IcebergOrderParams,
+        expected_sequence: Option<u64>,
+    ) -> Result<OrderSummary, ClientError> {
+        let ix = instructions::place_iceberg_order(
+            &signer.pubkey(),
+            &self.market,
+            open_orders,
+            token_deposit_account,
+            params,
+            expected_sequence,
+        );
+        self.send_for_summary(ix, &[signer]).await
+    }
+
+    pub async fn cancel_order(
+        &mut self,
+        signer: &Keypair,
+        open_orders: &Pubkey,
+        order_id: u64,
+    ) -> Result<(), ClientError> {
+        let ix = instructions::cancel_order(&signer.pubkey(), &self.market, open_orders, order_id);
+        self.send(&[ix], &[signer]).await
+    }
+
+    pub async fn cancel_all(
+        &mut self,
+        signer: &Keypair,
+        open_orders: &Pubkey,
+    ) -> Result<(), ClientError> {
+        let ix = instructions::cancel_all_orders(&signer.pubkey(), &self.market, open_orders);
+        self.send(&[ix], &[signer]).await
+    }
+
+    /// Places the fired trigger orders in `trigger_orders`, the payer collecting the
+    /// execution rewards.
+    pub async fn execute_triggers(
+        &mut self,
+        open_orders: &Pubkey,
+        trigger_orders: &Pubkey,
+    ) -> Result<(), ClientError> {
+        let ix = instructions::execute_triggers(
+            &self.payer.pubkey(),
+            &self.market,
+            open_orders,
+            trigger_orders,
+        );
+        self.send(&[ix], &[]).await
+    }
+
+    /// Executes the next slice of `twap_order`, the payer collecting the slice reward.
+    pub async fn execute_twap_slice(
+        &mut self,
+        open_orders: &Pubkey,
+        twap_order: &Pubkey,
+    ) -> Result<(), ClientError> {
+        let ix = instructions::execute_twap_slice(
+            &self.payer.pubkey(),
+            &self.market,
+            open_orders,
+            twap_order,
+        );
+        self.send(&[ix], &[]).await
+    }
+
+    /// Clears the market's batch once its slots have passed.
+    pub async fn clear_batch(&mut self) -> Result<(), ClientError> {
+        let ix = instructions::clear_batch(&self.market);
+        self.send(&[ix], &[]).await
+    }
+
+    pub async fn settle_funds(
+        &mut self,
+        owner: &Keypair,
+        open_orders: &Pubkey,
+        user_base_account: &Pubkey,
+        user_quote_account: &Pubkey,
+    ) -> Result<(), ClientError> {
+        let ix = instructions::settle_funds(
+            &owner.pubkey(),
+            &self.market,
+            open_orders,
+            user_base_account,
+            user_quote_account,
+        );
+        self.send(&[ix], &[owner]).await
+    }
+
+    /// Consumes up to `limit` events, passing the `OpenOrders` accounts they need, with the
+    /// payer collecting forfeited order bonds. Returns the number of events that were
+    /// queued, 0 meaning there was nothing to do.
+    pub async fn consume_events(&mut self, limit: u16) -> Result<usize, ClientError> {
+        let event_queue = self.fetch_event_queue().await?;
+        let pending = event_queue.events.len().min(limit as usize);
+        if pending == 0 {
+            return Ok(0);
+        }
+        let makers = events::makers(&event_queue, pending);
+        let ix = instructions::consume_events(&self.market, &self.payer.pubkey(), &makers, limit);
+        self.send(&[ix], &[]).await?;
+        Ok(pending)
+    }
+}
+
+async fn fetch<A: AccountDeserialize, T: Transport>(
+    transport: &mut T,
+    address: &Pubkey,
+) -> Result<A, ClientError> {
+    let data = transport
+        .get_account_data(address)
+        .await?
+        .ok_or(ClientError::AccountNotFound(*address))?;
+    crate::decode(&data).map_err(|_| ClientError::InvalidAccountData(*address))
+}
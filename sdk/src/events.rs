@@ -0,0 +1,139 @@
+//! Iteration over the event queue, and over the events and return data of the program in
+//! transaction logs.
+
+use std::collections::BTreeSet;
+
+use anchor_bpf_template::events::{
+    AuctionUncrossed, BatchCleared, CircuitBreakerTripped, FeesSwept, FundsSettled, MarketCreated,
+    OrderCancelled, OrderEvicted, OrderExpired, OrderFilled, OrderPlaced, TriggerCancelled,
+    TriggerExecuted, TriggerPlaced, TwapCancelled, TwapPlaced, TwapSliceExecuted,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use crate::{Event, EventQueue};
+
+/// Events still waiting for `consume_events`, with their sequence numbers.
+pub fn queued(event_queue: &EventQueue) -> impl Iterator<Item = (u64, &Event)> {
+    let first = event_queue.seq_num - event_queue.events.len() as u64;
+    (first..).zip(event_queue.events.iter())
+}
+
+/// The distinct `OpenOrders` accounts `consume_events` needs to process the first
+/// `limit` queued events.
+pub fn makers(event_queue: &EventQueue, limit: usize) -> Vec<Pubkey> {
+    let owners: BTreeSet<Pubkey> = event_queue
+        .events
+        .iter()
+        .take(limit)
+        .map(Event::owner)
+        .collect();
+    owners.into_iter().collect()
+}
+
+/// An event emitted by the program with `emit!`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProgramEvent {
+    MarketCreated(MarketCreated),
+    OrderPlaced(OrderPlaced),
+    OrderFilled(OrderFilled),
+    OrderCancelled(OrderCancelled),
+    OrderExpired(OrderExpired),
+    OrderEvicted(OrderEvicted),
+    FundsSettled(FundsSettled),
+    FeesSwept(FeesSwept),
+    TriggerPlaced(TriggerPlaced),
+    TriggerCancelled(TriggerCancelled),
+    TriggerExecuted(TriggerExecuted),
+    TwapPlaced(TwapPlaced),
+    TwapSliceExecuted(TwapSliceExecuted),
+    TwapCancelled(TwapCancelled),
+    CircuitBreakerTripped(CircuitBreakerTripped),
+    AuctionUncrossed(AuctionUncrossed),
+    BatchCleared(BatchCleared),
+}
+
+impl ProgramEvent {
+    /// Decodes the payload of one `Program data:` log line, `None` if it is not one of
+    /// this program's events.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 {
+            return None;
+        }
+        let (discriminator, mut body) = data.split_at(8);
+        macro_rules! try_decode {
+            ($($event:ident),*) => {
+                $(
+                    if discriminator == $event::discriminator() {
+                        return $event::deserialize(&mut body).ok().map(ProgramEvent::$event);
+                    }
+                )*
+            };
+        }
+        try_decode!(
+            MarketCreated,
+            OrderPlaced,
+            OrderFilled,
+            OrderCancelled,
+            OrderExpired,
+            OrderEvicted,
+            FundsSettled,
+            FeesSwept,
+            TriggerPlaced,
+            TriggerCancelled,
+            TriggerExecuted,
+            TwapPlaced,
+            TwapSliceExecuted,
+            TwapCancelled,
+            CircuitBreakerTripped,
+            AuctionUncrossed,
+            BatchCleared
+        );
+        None
+    }
+}
+
+/// The program's events in a transaction's log messages, in emission order.
+///
+/// Log lines are attributed to the program that was executing when they were written, so
+/// data logged by other programs in the same transaction, including ones this program
+/// calls, is skipped.
+pub fn parse_logs<S: AsRef<str>>(logs: &[S]) -> impl Iterator<Item = ProgramEvent> + '_ {
+    let program_id = anchor_bpf_template::ID.to_string();
+    let mut stack: Vec<bool> = Vec::new();
+    logs.iter().filter_map(move |line| {
+        let line = line.as_ref();
+        if let Some(rest) = line.strip_prefix("Program ") {
+            if let Some(data) = rest.strip_prefix("data: ") {
+                if stack.last() != Some(&true) {
+                    return None;
+                }
+                let bytes = base64::decode(data).ok()?;
+                return ProgramEvent::decode(&bytes);
+            }
+            let mut words = rest.split(' ');
+            let id = words.next()?;
+            match words.next() {
+                Some("invoke") => stack.push(id == program_id),
+                Some("success") | Some("failed:") => {
+                    stack.pop();
+                }
+                _ => {}
+            }
+        }
+        None
+    })
+}
+
+/// The program and data of the last `Program return:` line of the program in a
+/// transaction's log messages, which the runtime writes when a program it ran left return
+/// data set.
+pub fn parse_return_data<S: AsRef<str>>(logs: &[S]) -> Option<(Pubkey, Vec<u8>)> {
+    let program_id = anchor_bpf_template::ID;
+    let prefix = format!("Program return: {} ", program_id);
+    let data = logs
+        .iter()
+        .rev()
+        .find_map(|line| line.as_ref().strip_prefix(prefix.as_str()))?;
+    Some((program_id, base64::decode(data).ok()?))
+}
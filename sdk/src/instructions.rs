@@ -0,0 +1,909 @@
+//! Instruction builders, one per program instruction.
+//!
+//! The market, its books and event queue, and `OpenOrders` accounts are created by the
+//! caller, zeroed and owned by the program, in the same transaction as the instruction
+//! that initialises them. [`create_market`] and [`create_open_orders`] return the whole
+//! sequence.
+
+use anchor_bpf_template::state::{
+    BookSide, EventQueue, InternalOracle, Market, MidpointBook, OpenOrders, TriggerOrders,
+    TwapOrder,
+};
+use anchor_bpf_template::{accounts, instruction};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+
+use crate::{
+    CircuitBreakerArgs, IcebergOrderParams, InitializeArgs, MarketAddresses, MarketStatus,
+    MatchingMode, MidpointOrderParams, OracleConfigArgs, OrderParams, PeggedOrderParams,
+    RiskLimits, SwapArgs, TickBand, TriggerOrderParams, TwapOrderParams,
+};
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: anchor_bpf_template::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Appends the market's oracle and midpoint book, those it has, as remaining accounts.
+fn with_market_accounts(mut ix: Instruction, market: &MarketAddresses) -> Instruction {
+    if let Some(oracle) = market.oracle {
+        ix.accounts.push(AccountMeta::new_readonly(oracle, false));
+    }
+    if let Some(midpoint_book) = market.midpoint_book {
+        ix.accounts.push(AccountMeta::new(midpoint_book, false));
+    }
+    ix
+}
+
+/// Allocates a zeroed, rent-exempt account of `space` bytes owned by the program.
+pub fn create_program_account(
+    payer: &Pubkey,
+    account: &Pubkey,
+    space: usize,
+    rent: &Rent,
+) -> Instruction {
+    system_instruction::create_account(
+        payer,
+        account,
+        rent.minimum_balance(space),
+        space as u64,
+        &anchor_bpf_template::ID,
+    )
+}
+
+/// Creates and initialises the market, books and event queue in `addresses`. The
+/// vaults must already exist and be owned by `addresses.market_authority`; the new
+/// accounts must sign alongside `payer` and `admin`.
+pub fn create_market(
+    payer: &Pubkey,
+    admin: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    addresses: &MarketAddresses,
+    args: InitializeArgs,
+    rent: &Rent,
+) -> Vec<Instruction> {
+    vec![
+        create_program_account(payer, &addresses.market, 8 + Market::LEN, rent),
+        create_program_account(payer, &addresses.bids, 8 + BookSide::LEN, rent),
+        create_program_account(payer, &addresses.asks, 8 + BookSide::LEN, rent),
+        create_program_account(payer, &addresses.event_queue, 8 + EventQueue::LEN, rent),
+        initialize(admin, base_mint, quote_mint, addresses, args),
+    ]
+}
+
+/// Creates and initialises `open_orders` for `owner`, which must sign with `payer`.
+pub fn create_open_orders(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    rent: &Rent,
+) -> Vec<Instruction> {
+    vec![
+        create_program_account(payer, open_orders, 8 + OpenOrders::LEN, rent),
+        init_open_orders(owner, market, open_orders),
+    ]
+}
+
+pub fn initialize(
+    admin: &Pubkey,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+    market: &MarketAddresses,
+    args: InitializeArgs,
+) -> Instruction {
+    build(
+        accounts::Initialize {
+            admin: *admin,
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+            event_queue: market.event_queue,
+            market_authority: market.market_authority,
+            base_mint: *base_mint,
+            quote_mint: *quote_mint,
+            base_vault: market.base_vault,
+            quote_vault: market.quote_vault,
+        },
+        instruction::Initialize { args },
+    )
+}
+
+pub fn init_open_orders(
+    owner: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::InitOpenOrders {
+            owner: *owner,
+            market: market.market,
+            open_orders: *open_orders,
+        },
+        instruction::InitOpenOrders {},
+    )
+}
+
+pub fn set_delegate(owner: &Pubkey, open_orders: &Pubkey, delegate: Pubkey) -> Instruction {
+    build(
+        accounts::SetDelegate {
+            owner: *owner,
+            open_orders: *open_orders,
+        },
+        instruction::SetDelegate { delegate },
+    )
+}
+
+pub fn set_heartbeat(owner: &Pubkey, open_orders: &Pubkey, interval_seconds: u32) -> Instruction {
+    build(
+        accounts::SetHeartbeat {
+            owner: *owner,
+            open_orders: *open_orders,
+        },
+        instruction::SetHeartbeat { interval_seconds },
+    )
+}
+
+/// `signer` is the market admin or the `OpenOrders` delegate.
+pub fn set_risk_limits(
+    signer: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    limits: RiskLimits,
+) -> Instruction {
+    build(
+        accounts::SetRiskLimits {
+            signer: *signer,
+            open_orders: *open_orders,
+            market: market.market,
+        },
+        instruction::SetRiskLimits { limits },
+    )
+}
+
+pub fn heartbeat(signer: &Pubkey, open_orders: &Pubkey) -> Instruction {
+    build(
+        accounts::Heartbeat {
+            signer: *signer,
+            open_orders: *open_orders,
+        },
+        instruction::Heartbeat {},
+    )
+}
+
+pub fn set_market_status(
+    admin: &Pubkey,
+    market: &MarketAddresses,
+    status: MarketStatus,
+) -> Instruction {
+    build(
+        accounts::SetMarketStatus {
+            admin: *admin,
+            market: market.market,
+        },
+        instruction::SetMarketStatus { status },
+    )
+}
+
+pub fn set_matching_mode(
+    admin: &Pubkey,
+    market: &MarketAddresses,
+    mode: MatchingMode,
+    top_share_bps: u16,
+) -> Instruction {
+    build(
+        accounts::SetMatchingMode {
+            admin: *admin,
+            market: market.market,
+        },
+        instruction::SetMatchingMode {
+            mode,
+            top_share_bps,
+        },
+    )
+}
+
+pub fn set_circuit_breaker(
+    admin: &Pubkey,
+    market: &MarketAddresses,
+    args: CircuitBreakerArgs,
+) -> Instruction {
+    build(
+        accounts::SetCircuitBreaker {
+            admin: *admin,
+            market: market.market,
+        },
+        instruction::SetCircuitBreaker { args },
+    )
+}
+
+/// `admin` ends the auction, the fills are settled by [`consume_events`].
+pub fn uncross_auction(admin: &Pubkey, market: &MarketAddresses) -> Instruction {
+    let ix = build(
+        accounts::UncrossAuction {
+            admin: *admin,
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+            event_queue: market.event_queue,
+        },
+        instruction::UncrossAuction {},
+    );
+    with_market_accounts(ix, market)
+}
+
+/// Leaving batch mode clears the pending batch, whose fills are settled by
+/// [`consume_events`].
+pub fn set_batch_auction(
+    admin: &Pubkey,
+    market: &MarketAddresses,
+    batch_slots: u64,
+) -> Instruction {
+    let ix = build(
+        accounts::SetBatchAuction {
+            admin: *admin,
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+            event_queue: market.event_queue,
+        },
+        instruction::SetBatchAuction { batch_slots },
+    );
+    with_market_accounts(ix, market)
+}
+
+/// Permissionless.
+pub fn clear_batch(market: &MarketAddresses) -> Instruction {
+    let ix = build(
+        accounts::ClearBatch {
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+            event_queue: market.event_queue,
+        },
+        instruction::ClearBatch {},
+    );
+    with_market_accounts(ix, market)
+}
+
+/// Creates and initialises `midpoint_book` for the market, signed by its admin and by
+/// the new account alongside `payer`.
+pub fn create_midpoint_book(
+    payer: &Pubkey,
+    admin: &Pubkey,
+    market: &MarketAddresses,
+    midpoint_book: &Pubkey,
+    rent: &Rent,
+) -> Vec<Instruction> {
+    vec![
+        create_program_account(payer, midpoint_book, 8 + MidpointBook::LEN, rent),
+        init_midpoint_book(admin, market, midpoint_book),
+    ]
+}
+
+pub fn init_midpoint_book(
+    admin: &Pubkey,
+    market: &MarketAddresses,
+    midpoint_book: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::InitMidpointBook {
+            admin: *admin,
+            market: market.market,
+            midpoint_book: *midpoint_book,
+        },
+        instruction::InitMidpointBook {},
+    )
+}
+
+/// `oracle` is ignored when `args.kind` is `OracleKind::None`.
+pub fn set_oracle_config(
+    admin: &Pubkey,
+    market: &MarketAddresses,
+    oracle: &Pubkey,
+    args: OracleConfigArgs,
+) -> Instruction {
+    build(
+        accounts::SetOracleConfig {
+            admin: *admin,
+            market: market.market,
+            oracle: *oracle,
+        },
+        instruction::SetOracleConfig { args },
+    )
+}
+
+pub fn set_order_bond(
+    admin: &Pubkey,
+    market: &MarketAddresses,
+    bond: u64,
+    forfeit_bps: u16,
+) -> Instruction {
+    build(
+        accounts::SetOrderBond {
+            admin: *admin,
+            market: market.market,
+        },
+        instruction::SetOrderBond { bond, forfeit_bps },
+    )
+}
+
+pub fn set_order_minimums(
+    admin: &Pubkey,
+    market: &MarketAddresses,
+    min_base_lots: u64,
+    min_notional: u64,
+) -> Instruction {
+    build(
+        accounts::SetOrderMinimums {
+            admin: *admin,
+            market: market.market,
+        },
+        instruction::SetOrderMinimums {
+            min_base_lots,
+            min_notional,
+        },
+    )
+}
+
+pub fn set_tick_schedule(
+    admin: &Pubkey,
+    market: &MarketAddresses,
+    bands: Vec<TickBand>,
+) -> Instruction {
+    build(
+        accounts::SetTickSchedule {
+            admin: *admin,
+            market: market.market,
+        },
+        instruction::SetTickSchedule { bands },
+    )
+}
+
+/// Creates and initialises an internal oracle at `oracle`, which must sign with `payer`
+/// and `authority`.
+pub fn create_internal_oracle(
+    payer: &Pubkey,
+    authority: &Pubkey,
+    oracle: &Pubkey,
+    price: i64,
+    expo: i32,
+    rent: &Rent,
+) -> Vec<Instruction> {
+    vec![
+        create_program_account(payer, oracle, 8 + InternalOracle::LEN, rent),
+        init_internal_oracle(authority, oracle, price, expo),
+    ]
+}
+
+pub fn init_internal_oracle(
+    authority: &Pubkey,
+    oracle: &Pubkey,
+    price: i64,
+    expo: i32,
+) -> Instruction {
+    build(
+        accounts::InitInternalOracle {
+            authority: *authority,
+            oracle: *oracle,
+        },
+        instruction::InitInternalOracle { price, expo },
+    )
+}
+
+pub fn set_oracle_price(authority: &Pubkey, oracle: &Pubkey, price: i64, expo: i32) -> Instruction {
+    build(
+        accounts::SetOraclePrice {
+            authority: *authority,
+            oracle: *oracle,
+        },
+        instruction::SetOraclePrice { price, expo },
+    )
+}
+
+/// `token_deposit_account` holds quote tokens for bids and base tokens for asks. The
+/// order is rejected unless `expected_sequence`, if given, is the sequence number of
+/// `open_orders`.
+pub fn place_order(
+    signer: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    token_deposit_account: &Pubkey,
+    params: OrderParams,
+    expected_sequence: Option<u64>,
+) -> Instruction {
+    let ix = build(
+        accounts::PlaceOrder {
+            signer: *signer,
+            open_orders: *open_orders,
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+            event_queue: market.event_queue,
+            token_deposit_account: *token_deposit_account,
+            market_vault: market.deposit_vault(params.side),
+            token_program: spl_token::ID,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::PlaceOrder {
+            params,
+            expected_sequence,
+        },
+    );
+    with_market_accounts(ix, market)
+}
+
+/// `market.oracle` must be set, pegged orders are priced off it. `expected_sequence` as
+/// for [`place_order`].
+pub fn place_pegged_order(
+    signer: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    token_deposit_account: &Pubkey,
+    params: PeggedOrderParams,
+    expected_sequence: Option<u64>,
+) -> Instruction {
+    let ix = build(
+        accounts::PlaceOrder {
+            signer: *signer,
+            open_orders: *open_orders,
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+            event_queue: market.event_queue,
+            token_deposit_account: *token_deposit_account,
+            market_vault: market.deposit_vault(params.side),
+            token_program: spl_token::ID,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::PlacePeggedOrder {
+            params,
+            expected_sequence,
+        },
+    );
+    with_market_accounts(ix, market)
+}
+
+/// `market.midpoint_book` must be set. `expected_sequence` as for [`place_order`].
+pub fn place_midpoint_order(
+    signer: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    token_deposit_account: &Pubkey,
+    params: MidpointOrderParams,
+    expected_sequence: Option<u64>,
+) -> Instruction {
+    let ix = build(
+        accounts::PlaceOrder {
+            signer: *signer,
+            open_orders: *open_orders,
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+            event_queue: market.event_queue,
+            token_deposit_account: *token_deposit_account,
+            market_vault: market.deposit_vault(params.side),
+            token_program: spl_token::ID,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::PlaceMidpointOrder {
+            params,
+            expected_sequence,
+        },
+    );
+    with_market_accounts(ix, market)
+}
+
+/// `expected_sequence` as for [`place_order`].
+pub fn place_iceberg_order(
+    signer: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    token_deposit_account: &Pubkey,
+    params: IcebergOrderParams,
+    expected_sequence: Option<u64>,
+) -> Instruction {
+    let ix = build(
+        accounts::PlaceOrder {
+            signer: *signer,
+            open_orders: *open_orders,
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+            event_queue: market.event_queue,
+            token_deposit_account: *token_deposit_account,
+            market_vault: market.deposit_vault(params.side),
+            token_program: spl_token::ID,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::PlaceIcebergOrder {
+            params,
+            expected_sequence,
+        },
+    );
+    with_market_accounts(ix, market)
+}
+
+pub fn cancel_order(
+    signer: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    order_id: u64,
+) -> Instruction {
+    let ix = build(
+        accounts::CancelOrder {
+            signer: *signer,
+            open_orders: *open_orders,
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+        },
+        instruction::CancelOrder { order_id },
+    );
+    with_market_accounts(ix, market)
+}
+
+pub fn cancel_all_orders(
+    signer: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+) -> Instruction {
+    let ix = build(
+        accounts::CancelAllOrders {
+            signer: *signer,
+            open_orders: *open_orders,
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+        },
+        instruction::CancelAllOrders {},
+    );
+    with_market_accounts(ix, market)
+}
+
+/// Permissionless once the owner of `open_orders` has missed a heartbeat.
+pub fn cancel_stale_orders(market: &MarketAddresses, open_orders: &Pubkey) -> Instruction {
+    let ix = build(
+        accounts::CancelStaleOrders {
+            open_orders: *open_orders,
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+        },
+        instruction::CancelStaleOrders {},
+    );
+    with_market_accounts(ix, market)
+}
+
+#[allow(clippy::too_many_arguments)]
+/// `trigger_orders` is needed when `trigger_ids` is not empty.
+pub fn link_orders(
+    signer: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    trigger_orders: Option<&Pubkey>,
+    group_id: u64,
+    order_ids: Vec<u64>,
+    trigger_ids: Vec<u64>,
+) -> Instruction {
+    let mut ix = build(
+        accounts::LinkOrders {
+            signer: *signer,
+            open_orders: *open_orders,
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+        },
+        instruction::LinkOrders {
+            group_id,
+            order_ids,
+            trigger_ids,
+        },
+    );
+    if let Some(trigger_orders) = trigger_orders {
+        ix.accounts.push(AccountMeta::new(*trigger_orders, false));
+    }
+    ix
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn batch_orders(
+    signer: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    user_base_account: &Pubkey,
+    user_quote_account: &Pubkey,
+    cancel_order_ids: Vec<u64>,
+    orders: Vec<OrderParams>,
+    expected_sequence: Option<u64>,
+) -> Instruction {
+    let ix = build(
+        accounts::BatchOrders {
+            signer: *signer,
+            open_orders: *open_orders,
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+            event_queue: market.event_queue,
+            base_vault: market.base_vault,
+            quote_vault: market.quote_vault,
+            user_base_account: *user_base_account,
+            user_quote_account: *user_quote_account,
+            token_program: spl_token::ID,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::BatchOrders {
+            cancel_order_ids,
+            orders,
+            expected_sequence,
+        },
+    );
+    with_market_accounts(ix, market)
+}
+
+pub fn swap(
+    owner: &Pubkey,
+    market: &MarketAddresses,
+    user_base_account: &Pubkey,
+    user_quote_account: &Pubkey,
+    args: SwapArgs,
+) -> Instruction {
+    let ix = build(
+        accounts::Swap {
+            owner: *owner,
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+            event_queue: market.event_queue,
+            base_vault: market.base_vault,
+            quote_vault: market.quote_vault,
+            market_authority: market.market_authority,
+            user_base_account: *user_base_account,
+            user_quote_account: *user_quote_account,
+            token_program: spl_token::ID,
+        },
+        instruction::Swap { args },
+    );
+    with_market_accounts(ix, market)
+}
+
+/// Creates and initialises `trigger_orders` for the `open_orders` of `owner`, which must
+/// sign with `payer`.
+pub fn create_trigger_orders(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    open_orders: &Pubkey,
+    trigger_orders: &Pubkey,
+    rent: &Rent,
+) -> Vec<Instruction> {
+    vec![
+        create_program_account(payer, trigger_orders, 8 + TriggerOrders::LEN, rent),
+        init_trigger_orders(owner, open_orders, trigger_orders),
+    ]
+}
+
+pub fn init_trigger_orders(
+    owner: &Pubkey,
+    open_orders: &Pubkey,
+    trigger_orders: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::InitTriggerOrders {
+            owner: *owner,
+            open_orders: *open_orders,
+            trigger_orders: *trigger_orders,
+        },
+        instruction::InitTriggerOrders {},
+    )
+}
+
+/// `owner` also pays the execution reward, [`crate::TriggerOrders::EXECUTION_REWARD`].
+pub fn place_trigger_order(
+    owner: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    trigger_orders: &Pubkey,
+    token_deposit_account: &Pubkey,
+    params: TriggerOrderParams,
+) -> Instruction {
+    let ix = build(
+        accounts::PlaceTriggerOrder {
+            owner: *owner,
+            open_orders: *open_orders,
+            trigger_orders: *trigger_orders,
+            market: market.market,
+            token_deposit_account: *token_deposit_account,
+            market_vault: market.deposit_vault(params.side),
+            token_program: spl_token::ID,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::PlaceTriggerOrder { params },
+    );
+    with_market_accounts(ix, market)
+}
+
+pub fn cancel_trigger_order(
+    owner: &Pubkey,
+    open_orders: &Pubkey,
+    trigger_orders: &Pubkey,
+    trigger_id: u64,
+) -> Instruction {
+    build(
+        accounts::CancelTriggerOrder {
+            owner: *owner,
+            open_orders: *open_orders,
+            trigger_orders: *trigger_orders,
+        },
+        instruction::CancelTriggerOrder { trigger_id },
+    )
+}
+
+/// Permissionless, `executor` collects the rewards of the trigger orders it places.
+pub fn execute_triggers(
+    executor: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    trigger_orders: &Pubkey,
+) -> Instruction {
+    let ix = build(
+        accounts::ExecuteTriggers {
+            executor: *executor,
+            open_orders: *open_orders,
+            trigger_orders: *trigger_orders,
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+            event_queue: market.event_queue,
+        },
+        instruction::ExecuteTriggers {},
+    );
+    with_market_accounts(ix, market)
+}
+
+/// Creates `twap_order` and places the TWAP order in it, `twap_order` signing.
+#[allow(clippy::too_many_arguments)]
+pub fn create_twap_order(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    twap_order: &Pubkey,
+    token_deposit_account: &Pubkey,
+    params: TwapOrderParams,
+    rent: &Rent,
+) -> Vec<Instruction> {
+    vec![
+        create_program_account(payer, twap_order, 8 + TwapOrder::LEN, rent),
+        place_twap_order(
+            owner,
+            market,
+            open_orders,
+            twap_order,
+            token_deposit_account,
+            params,
+        ),
+    ]
+}
+
+/// `owner` also pays the slice rewards, [`crate::TwapOrder::SLICE_REWARD`] per slice.
+pub fn place_twap_order(
+    owner: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    twap_order: &Pubkey,
+    token_deposit_account: &Pubkey,
+    params: TwapOrderParams,
+) -> Instruction {
+    let ix = build(
+        accounts::PlaceTwapOrder {
+            owner: *owner,
+            open_orders: *open_orders,
+            twap_order: *twap_order,
+            market: market.market,
+            token_deposit_account: *token_deposit_account,
+            market_vault: market.deposit_vault(params.side),
+            token_program: spl_token::ID,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::PlaceTwapOrder { params },
+    );
+    with_market_accounts(ix, market)
+}
+
+pub fn cancel_twap_order(owner: &Pubkey, open_orders: &Pubkey, twap_order: &Pubkey) -> Instruction {
+    build(
+        accounts::CancelTwapOrder {
+            owner: *owner,
+            open_orders: *open_orders,
+            twap_order: *twap_order,
+        },
+        instruction::CancelTwapOrder {},
+    )
+}
+
+/// Permissionless, `executor` collects the slice reward.
+pub fn execute_twap_slice(
+    executor: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    twap_order: &Pubkey,
+) -> Instruction {
+    let ix = build(
+        accounts::ExecuteTwapSlice {
+            executor: *executor,
+            open_orders: *open_orders,
+            twap_order: *twap_order,
+            market: market.market,
+            bids: market.bids,
+            asks: market.asks,
+            event_queue: market.event_queue,
+        },
+        instruction::ExecuteTwapSlice {},
+    );
+    with_market_accounts(ix, market)
+}
+
+/// `makers` are the `OpenOrders` accounts owning the events to consume, see
+/// [`crate::events::makers`]. `cranker` collects the bonds forfeited by expired and
+/// evicted orders.
+pub fn consume_events(
+    market: &MarketAddresses,
+    cranker: &Pubkey,
+    makers: &[Pubkey],
+    limit: u16,
+) -> Instruction {
+    let mut ix = build(
+        accounts::ConsumeEvents {
+            market: market.market,
+            event_queue: market.event_queue,
+            cranker: *cranker,
+        },
+        instruction::ConsumeEvents { limit },
+    );
+    ix.accounts
+        .extend(makers.iter().map(|maker| AccountMeta::new(*maker, false)));
+    ix
+}
+
+pub fn settle_funds(
+    owner: &Pubkey,
+    market: &MarketAddresses,
+    open_orders: &Pubkey,
+    user_base_account: &Pubkey,
+    user_quote_account: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::SettleFunds {
+            owner: *owner,
+            open_orders: *open_orders,
+            market: market.market,
+            market_authority: market.market_authority,
+            base_vault: market.base_vault,
+            quote_vault: market.quote_vault,
+            user_base_account: *user_base_account,
+            user_quote_account: *user_quote_account,
+            token_program: spl_token::ID,
+        },
+        instruction::SettleFunds {},
+    )
+}
+
+pub fn sweep_fees(admin: &Pubkey, market: &MarketAddresses, destination: &Pubkey) -> Instruction {
+    build(
+        accounts::SweepFees {
+            admin: *admin,
+            market: market.market,
+            market_authority: market.market_authority,
+            quote_vault: market.quote_vault,
+            destination: *destination,
+            token_program: spl_token::ID,
+        },
+        instruction::SweepFees {},
+    )
+}
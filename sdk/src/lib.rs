@@ -0,0 +1,82 @@
+//! Off-chain companion to the order book program.
+//!
+//! - [`MarketAddresses`]: every account of a market, resolved from its `Market` state.
+//! - [`instructions`]: one builder per program instruction, plus the account creation
+//!   the program expects its callers to do.
+//! - [`book`]: L2 and L3 views of the bids and asks accounts.
+//! - [`events`]: iteration over the event queue and over events emitted in transaction logs.
+//! - [`math`]: exact conversions between UI amounts and native units, lots and ticks, as
+//!   the program does them.
+//! - [`decode`]: deserialises any of the program's accounts from raw account data.
+//! - [`client`]: [`client::ClobClient`], the same high-level calls over BanksClient or RPC.
+#![allow(clippy::result_large_err)]
+
+pub mod book;
+pub mod client;
+pub mod events;
+pub mod instructions;
+
+use anchor_bpf_template::state::Market;
+use anchor_lang::prelude::*;
+use anchor_lang::AccountDeserialize;
+
+pub use anchor_bpf_template::math;
+pub use anchor_bpf_template::pda::{find_market_authority_address, MARKET_AUTHORITY_SEED};
+pub use anchor_bpf_template::{
+    errors::ClobError,
+    state::{
+        BookSide, Event, EventQueue, FillEvent, IcebergReserve, InternalOracle, MidpointBook,
+        OpenOrders, Order, OutEvent, OutReason, PeggedOrder, TickSizes, TriggerOrder,
+        TriggerOrders, TwapOrder,
+    },
+    CircuitBreakerArgs, IcebergOrderParams, InitializeArgs, MarketStatus, MatchingMode,
+    MidpointOrderParams, OracleConfigArgs, OracleKind, OrderParams, OrderSummary, OrderType,
+    PeggedOrderParams, RiskLimits, Side, SwapArgs, TickBand, TriggerCondition, TriggerOrderParams,
+    TriggerSource, TwapOrderParams,
+};
+
+/// Checks the account discriminator and deserialises `data` as `T`.
+pub fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+/// The accounts that make up a market.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MarketAddresses {
+    pub market: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub event_queue: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub market_authority: Pubkey,
+    /// Passed to order entry when the market bands prices around an oracle.
+    pub oracle: Option<Pubkey>,
+    /// Passed to order entry and cancels when the market takes midpoint orders.
+    pub midpoint_book: Option<Pubkey>,
+}
+
+impl MarketAddresses {
+    pub fn from_market(market_key: Pubkey, market: &Market) -> Self {
+        MarketAddresses {
+            market: market_key,
+            bids: market.bids,
+            asks: market.asks,
+            event_queue: market.event_queue,
+            base_vault: market.base_vault,
+            quote_vault: market.quote_vault,
+            market_authority: find_market_authority_address(&market_key).0,
+            oracle: (market.oracle_kind != OracleKind::None).then_some(market.oracle),
+            midpoint_book: (market.midpoint_book != Pubkey::default())
+                .then_some(market.midpoint_book),
+        }
+    }
+
+    /// The vault an order on `side` is paid into.
+    pub fn deposit_vault(&self, side: Side) -> Pubkey {
+        match side {
+            Side::Bid => self.quote_vault,
+            Side::Ask => self.base_vault,
+        }
+    }
+}
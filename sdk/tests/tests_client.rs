@@ -0,0 +1,508 @@
+use std::sync::{Arc, Mutex};
+
+use anchor_bpf_template::state::Market;
+use anchor_lang::prelude::{Pubkey, Rent};
+use anchor_lang::solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    hash::Hash,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+};
+use async_trait::async_trait;
+use clob_sdk::client::{ClientError, ClobClient, Transport};
+use clob_sdk::{
+    find_market_authority_address, instructions, ClobError, IcebergOrderParams, InitializeArgs,
+    MarketAddresses, MidpointOrderParams, OrderParams, OrderSummary, OrderType, Side,
+};
+use solana_program_test::{processor, tokio, BanksClient, ProgramTest};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+
+const BASE_LOT: u64 = 1_000_000;
+
+/// What the program last set with `sol_set_return_data`. The stubs are global, so this
+/// binary holds no other test.
+static RETURN_DATA: Mutex<Option<(Pubkey, Vec<u8>)>> = Mutex::new(None);
+
+/// Forwards every syscall to the stubs of `solana-program-test`, recording return data.
+struct RecordReturnData(Box<dyn SyscallStubs>);
+
+impl SyscallStubs for RecordReturnData {
+    fn sol_log(&self, message: &str) {
+        self.0.sol_log(message)
+    }
+    fn sol_log_compute_units(&self) {
+        self.0.sol_log_compute_units()
+    }
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.0
+            .sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_clock_sysvar(var_addr)
+    }
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_fees_sysvar(var_addr)
+    }
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_rent_sysvar(var_addr)
+    }
+    unsafe fn sol_memcpy(&self, dst: *mut u8, src: *const u8, n: usize) {
+        self.0.sol_memcpy(dst, src, n)
+    }
+    unsafe fn sol_memmove(&self, dst: *mut u8, src: *const u8, n: usize) {
+        self.0.sol_memmove(dst, src, n)
+    }
+    unsafe fn sol_memcmp(&self, s1: *const u8, s2: *const u8, n: usize, result: *mut i32) {
+        self.0.sol_memcmp(s1, s2, n, result)
+    }
+    unsafe fn sol_memset(&self, s: *mut u8, c: u8, n: usize) {
+        self.0.sol_memset(s, c, n)
+    }
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.0.sol_get_return_data()
+    }
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.0.sol_set_return_data(data);
+        *RETURN_DATA.lock().unwrap() = self.0.sol_get_return_data();
+    }
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        self.0.sol_log_data(fields)
+    }
+    fn sol_get_processed_sibling_instruction(&self, index: usize) -> Option<Instruction> {
+        self.0.sol_get_processed_sibling_instruction(index)
+    }
+    fn sol_get_stack_height(&self) -> u64 {
+        self.0.sol_get_stack_height()
+    }
+}
+
+/// Wraps the stubs `solana-program-test` installs when it starts.
+fn record_return_data() {
+    struct Placeholder;
+    impl SyscallStubs for Placeholder {}
+
+    let program_test_stubs = set_syscall_stubs(Box::new(Placeholder));
+    set_syscall_stubs(Box::new(RecordReturnData(program_test_stubs)));
+}
+
+/// A `BanksClient` that reports the return data its programs set, as an RPC node does.
+struct RecordingBanks(BanksClient);
+
+#[async_trait]
+impl Transport for RecordingBanks {
+    async fn get_account_data(&mut self, address: &Pubkey) -> Result<Option<Vec<u8>>, ClientError> {
+        self.0.get_account_data(address).await
+    }
+
+    async fn get_rent(&mut self) -> Result<Rent, ClientError> {
+        Transport::get_rent(&mut self.0).await
+    }
+
+    async fn get_latest_blockhash(&mut self) -> Result<Hash, ClientError> {
+        Transport::get_latest_blockhash(&mut self.0).await
+    }
+
+    async fn process_transaction(&mut self, transaction: Transaction) -> Result<(), ClientError> {
+        Transport::process_transaction(&mut self.0, transaction).await
+    }
+
+    async fn process_transaction_with_return_data(
+        &mut self,
+        transaction: Transaction,
+    ) -> Result<Option<(Pubkey, Vec<u8>)>, ClientError> {
+        RETURN_DATA.lock().unwrap().take();
+        Transport::process_transaction(&mut self.0, transaction).await?;
+        Ok(RETURN_DATA.lock().unwrap().take())
+    }
+}
+
+/// What a market-making bot would run, written once against any transport.
+async fn quote_around<T: Transport>(
+    client: &mut ClobClient<T>,
+    owner: &Keypair,
+    open_orders: &Pubkey,
+    base_account: &Pubkey,
+    quote_account: &Pubkey,
+    mid: u64,
+) -> Result<(), ClientError> {
+    if !client
+        .fetch_open_orders(open_orders)
+        .await?
+        .orders
+        .is_empty()
+    {
+        client.cancel_all(owner, open_orders).await?;
+    }
+    for (side, price, deposit, client_order_id) in [
+        (Side::Bid, mid - 10, quote_account, 1),
+        (Side::Bid, mid - 20, quote_account, 2),
+        (Side::Ask, mid + 10, base_account, 3),
+    ] {
+        let params = OrderParams {
+            side,
+            price,
+            max_base_lots: 5,
+            order_type: OrderType::PostOnly,
+            client_order_id,
+            expiry_timestamp: 0,
+        };
+        let summary = client
+            .place_order(owner, open_orders, deposit, params, None)
+            .await?;
+        assert!(summary.rested && summary.base_filled == 0);
+    }
+    Ok(())
+}
+
+struct Setup {
+    banks: BanksClient,
+    payer: Arc<Keypair>,
+    market: Pubkey,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+}
+
+async fn send(banks: &mut BanksClient, payer: &Keypair, ixs: &[Instruction], signers: &[&Keypair]) {
+    let blockhash = banks.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![payer];
+    all_signers.extend(signers);
+    let tx = solana_sdk::transaction::Transaction::new_signed_with_payer(
+        ixs,
+        Some(&payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    banks.process_transaction(tx).await.unwrap();
+}
+
+async fn create_token_account(
+    banks: &mut BanksClient,
+    payer: &Keypair,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let account = Keypair::new();
+    let rent = banks.get_rent().await.unwrap();
+    let len = spl_token::state::Account::LEN;
+    let mut ixs = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &account.pubkey(),
+            rent.minimum_balance(len),
+            len as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_account(&spl_token::ID, &account.pubkey(), mint, owner)
+            .unwrap(),
+    ];
+    if amount > 0 {
+        ixs.push(
+            spl_token::instruction::mint_to(
+                &spl_token::ID,
+                mint,
+                &account.pubkey(),
+                &payer.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap(),
+        );
+    }
+    send(banks, payer, &ixs, &[&account]).await;
+    account.pubkey()
+}
+
+async fn setup() -> Setup {
+    let mut program_test = ProgramTest::new(
+        "anchor_bpf_template",
+        anchor_bpf_template::ID,
+        processor!(anchor_bpf_template::entry),
+    );
+    program_test.add_program(
+        "spl_token",
+        spl_token::ID,
+        processor!(spl_token::processor::Processor::process),
+    );
+    let (mut banks, payer, _) = program_test.start().await;
+    let rent = banks.get_rent().await.unwrap();
+
+    let mut mints = Vec::new();
+    for decimals in [9, 6] {
+        let mint = Keypair::new();
+        let len = spl_token::state::Mint::LEN;
+        let ixs = [
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(len),
+                len as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::ID,
+                &mint.pubkey(),
+                &payer.pubkey(),
+                None,
+                decimals,
+            )
+            .unwrap(),
+        ];
+        send(&mut banks, &payer, &ixs, &[&mint]).await;
+        mints.push(mint.pubkey());
+    }
+    let (base_mint, quote_mint) = (mints[0], mints[1]);
+
+    let [market, bids, asks, event_queue] = [(); 4].map(|_| Keypair::new());
+    let (market_authority, _) = find_market_authority_address(&market.pubkey());
+    let base_vault =
+        create_token_account(&mut banks, &payer, &base_mint, &market_authority, 0).await;
+    let quote_vault =
+        create_token_account(&mut banks, &payer, &quote_mint, &market_authority, 0).await;
+    let addresses = MarketAddresses {
+        market: market.pubkey(),
+        bids: bids.pubkey(),
+        asks: asks.pubkey(),
+        event_queue: event_queue.pubkey(),
+        base_vault,
+        quote_vault,
+        market_authority,
+        oracle: None,
+        midpoint_book: None,
+    };
+    let ixs = instructions::create_market(
+        &payer.pubkey(),
+        &payer.pubkey(),
+        &base_mint,
+        &quote_mint,
+        &addresses,
+        InitializeArgs {
+            base_lot_size: BASE_LOT,
+            quote_lot_size: 10,
+            tick_size: 1,
+            taker_fee_bps: 10,
+        },
+        &rent,
+    );
+    send(
+        &mut banks,
+        &payer,
+        &ixs,
+        &[&market, &bids, &asks, &event_queue],
+    )
+    .await;
+
+    Setup {
+        banks,
+        payer: Arc::new(payer),
+        market: market.pubkey(),
+        base_mint,
+        quote_mint,
+    }
+}
+
+#[tokio::test]
+async fn test_bot_runs_over_banks_client() {
+    let setup = setup().await;
+    let mut banks = setup.banks.clone();
+    let payer = setup.payer.clone();
+
+    record_return_data();
+
+    let mut client = ClobClient::load(RecordingBanks(setup.banks), setup.payer, setup.market)
+        .await
+        .unwrap();
+    let market: Market = client.fetch_market().await.unwrap();
+    assert_eq!(market.base_lot_size, BASE_LOT);
+
+    let owner = Keypair::new();
+    let base_account = create_token_account(
+        &mut banks,
+        &payer,
+        &setup.base_mint,
+        &owner.pubkey(),
+        1_000 * BASE_LOT,
+    )
+    .await;
+    let quote_account = create_token_account(
+        &mut banks,
+        &payer,
+        &setup.quote_mint,
+        &owner.pubkey(),
+        1_000_000_000,
+    )
+    .await;
+    let open_orders = client.create_open_orders(&owner).await.unwrap();
+
+    // Without return data the client cannot tell what became of an order, so it does not
+    // place one.
+    let mut plain_client = ClobClient::load(banks.clone(), payer.clone(), setup.market)
+        .await
+        .unwrap();
+    let err = quote_around(
+        &mut plain_client,
+        &owner,
+        &open_orders,
+        &base_account,
+        &quote_account,
+        2_000,
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, ClientError::ReturnDataUnavailable));
+    assert!(client
+        .fetch_open_orders(&open_orders)
+        .await
+        .unwrap()
+        .orders
+        .is_empty());
+
+    quote_around(
+        &mut client,
+        &owner,
+        &open_orders,
+        &base_account,
+        &quote_account,
+        2_000,
+    )
+    .await
+    .unwrap();
+
+    let book = client.fetch_book().await.unwrap();
+    let bids = book.l2(Side::Bid, 10, 0);
+    assert_eq!(bids.len(), 2);
+    assert_eq!((bids[0].price, bids[0].quantity), (1_990, 5));
+    assert_eq!(book.best_price(Side::Ask, 0), Some(2_010));
+    assert_eq!(book.orders_of(&open_orders, 0).count(), 3);
+    assert_eq!(
+        client
+            .fetch_open_orders(&open_orders)
+            .await
+            .unwrap()
+            .orders
+            .len(),
+        3
+    );
+
+    // Requoting replaces the orders instead of stacking them.
+    quote_around(
+        &mut client,
+        &owner,
+        &open_orders,
+        &base_account,
+        &quote_account,
+        2_100,
+    )
+    .await
+    .unwrap();
+    let book = client.fetch_book().await.unwrap();
+    assert_eq!(book.orders_of(&open_orders, 0).count(), 3);
+    assert_eq!(book.best_price(Side::Bid, 0), Some(2_090));
+
+    // A taker lifts 3 lots of the 2_110 ask, worth 10 native quote per tick.
+    let taker = Keypair::new();
+    let taker_quote = create_token_account(
+        &mut banks,
+        &payer,
+        &setup.quote_mint,
+        &taker.pubkey(),
+        1_000_000_000,
+    )
+    .await;
+    let taker_open_orders = client.create_open_orders(&taker).await.unwrap();
+    let params = OrderParams {
+        side: Side::Bid,
+        price: 2_110,
+        max_base_lots: 3,
+        order_type: OrderType::ImmediateOrCancel,
+        client_order_id: 9,
+        expiry_timestamp: 0,
+    };
+    let summary = client
+        .place_order(&taker, &taker_open_orders, &taker_quote, params, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        summary,
+        OrderSummary {
+            order_id: 7,
+            base_filled: 3 * BASE_LOT,
+            quote_filled: 63_300,
+            fee_paid: 64,
+            rested: false,
+            base_cancelled: 0,
+        }
+    );
+
+    let resting: Vec<u64> = book
+        .orders_of(&open_orders, 0)
+        .map(|o| o.order_id)
+        .collect();
+    for order_id in resting {
+        client
+            .cancel_order(&owner, &open_orders, order_id)
+            .await
+            .unwrap();
+    }
+    let book = client.fetch_book().await.unwrap();
+    assert!(book.bids.orders.is_empty() && book.asks.orders.is_empty());
+
+    // Order 1 went with the requote's cancel_all.
+
+    let err = client
+        .cancel_order(&owner, &open_orders, 1)
+        .await
+        .unwrap_err();
+    assert_eq!(err.error_code(), Some(u32::from(ClobError::OrderNotFound)));
+
+    // Iceberg and midpoint orders report what became of them the same way.
+    let midpoint_book = Keypair::new();
+    let rent = banks.get_rent().await.unwrap();
+    let ixs = instructions::create_midpoint_book(
+        &payer.pubkey(),
+        &payer.pubkey(),
+        &client.market,
+        &midpoint_book.pubkey(),
+        &rent,
+    );
+    send(&mut banks, &payer, &ixs, &[&midpoint_book]).await;
+    client.market.midpoint_book = Some(midpoint_book.pubkey());
+    let params = IcebergOrderParams {
+        side: Side::Ask,
+        price: 2_200,
+        max_base_lots: 10,
+        display_base_lots: 2,
+        order_type: OrderType::Limit,
+        client_order_id: 4,
+        expiry_timestamp: 0,
+    };
+    let summary = client
+        .place_iceberg_order(&owner, &open_orders, &base_account, params, None)
+        .await
+        .unwrap();
+    assert_eq!((summary.order_id, summary.rested), (8, true));
+    let params = MidpointOrderParams {
+        side: Side::Bid,
+        limit_price: 2_100,
+        max_base_lots: 2,
+        client_order_id: 5,
+        expiry_timestamp: 0,
+    };
+    let summary = client
+        .place_midpoint_order(&owner, &open_orders, &quote_account, params, None)
+        .await
+        .unwrap();
+    assert_eq!((summary.order_id, summary.rested), (9, true));
+}
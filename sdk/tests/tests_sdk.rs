@@ -0,0 +1,169 @@
+use anchor_bpf_template::events::{OrderFilled, OrderPlaced};
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::Event as _;
+use clob_sdk::book::{Book, L2Level};
+use clob_sdk::events::{parse_logs, parse_return_data, ProgramEvent};
+use clob_sdk::{BookSide, Order, OrderType, PeggedOrder, Side, TickSizes};
+
+fn order(order_id: u64, owner: Pubkey, price: u64, quantity: u64, expiry: i64) -> Order {
+    Order {
+        order_id,
+        owner,
+        price,
+        quantity,
+        expiry_timestamp: expiry,
+        ..Order::default()
+    }
+}
+
+fn book() -> (Book, Pubkey, Pubkey) {
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+    let bids = BookSide {
+        side: Side::Bid,
+        orders: vec![
+            order(1, alice, 100, 5, 0),
+            order(2, bob, 100, 7, 0),
+            order(3, alice, 99, 1, 50),
+            order(4, bob, 98, 2, 0),
+        ],
+        ..BookSide::default()
+    };
+    let asks = BookSide {
+        side: Side::Ask,
+        orders: vec![order(5, bob, 101, 4, 10), order(6, alice, 103, 3, 0)],
+        ..BookSide::default()
+    };
+    (Book::new(bids, asks), alice, bob)
+}
+
+#[test]
+fn test_l2_aggregates_and_skips_expired() {
+    let (book, _, _) = book();
+    assert_eq!(
+        book.l2(Side::Bid, 2, 0),
+        vec![
+            L2Level {
+                price: 100,
+                quantity: 12,
+                order_count: 2
+            },
+            L2Level {
+                price: 99,
+                quantity: 1,
+                order_count: 1
+            },
+        ]
+    );
+    // Order 3 has expired by now, so the second level is 98.
+    assert_eq!(book.l2(Side::Bid, 2, 60)[1].price, 98);
+    assert_eq!(book.best_price(Side::Ask, 0), Some(101));
+    assert_eq!(book.spread(0), Some(1));
+    assert_eq!(book.spread(10), Some(3));
+}
+
+#[test]
+fn test_orders_of_owner() {
+    let (book, alice, _) = book();
+    let ids: Vec<u64> = book.orders_of(&alice, 0).map(|o| o.order_id).collect();
+    assert_eq!(ids, vec![1, 3, 6]);
+}
+
+#[test]
+fn test_pegged_orders_at_oracle_price() {
+    let (mut book, alice, _) = book();
+    let pegged = |order_id, price_offset, peg_limit| PeggedOrder {
+        order: order(order_id, alice, peg_limit, 2, 0),
+        price_offset,
+    };
+    book.bids.pegged_orders = vec![pegged(7, -1, 105)];
+    book.asks.pegged_orders = vec![pegged(8, 2, 0)];
+
+    // Without an oracle price they are left out, as matching skips them.
+    assert_eq!(book.l3(Side::Bid, 0).count(), 4);
+    let book = book.with_oracle_price(100, TickSizes::flat(1));
+    assert_eq!(
+        book.l2(Side::Bid, 2, 0)[1],
+        L2Level {
+            price: 99,
+            quantity: 3,
+            order_count: 2
+        }
+    );
+    // Order 5 has expired, the pegged ask at the oracle price plus 2 is the best.
+    assert_eq!(book.best_price(Side::Ask, 10), Some(102));
+    assert_eq!(book.spread(10), Some(2));
+    let ids: Vec<u64> = book.orders_of(&alice, 0).map(|o| o.order_id).collect();
+    assert_eq!(ids, vec![1, 3, 7, 8, 6]);
+}
+
+#[test]
+fn test_parse_logs_keeps_only_program_events() {
+    let program = anchor_bpf_template::ID;
+    let other = Pubkey::new_unique();
+    let placed = OrderPlaced {
+        market: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        order_id: 7,
+        client_order_id: 1,
+        side: Side::Bid,
+        order_type: OrderType::Limit,
+        price: 100,
+        max_base_lots: 3,
+        base_lots_filled: 0,
+        quote_lots_filled: 0,
+        taker_fee: 0,
+        posted_base_lots: 3,
+    };
+    let filled = OrderFilled {
+        market: placed.market,
+        maker: Pubkey::new_unique(),
+        maker_order_id: 2,
+        maker_client_order_id: 0,
+        maker_out: true,
+        taker: placed.owner,
+        taker_order_id: 7,
+        taker_side: Side::Bid,
+        price: 100,
+        quantity: 1,
+        auction: false,
+    };
+    let data = |bytes: Vec<u8>| format!("Program data: {}", base64::encode(bytes));
+    let logs = vec![
+        format!("Program {} invoke [1]", program),
+        format!("Program {} invoke [2]", other),
+        data(filled.data()),
+        format!("Program {} success", other),
+        data(filled.data()),
+        data(placed.data()),
+        data(vec![0; 4]),
+        format!("Program {} success", program),
+        data(placed.data()),
+    ];
+
+    let events: Vec<ProgramEvent> = parse_logs(&logs).collect();
+    assert_eq!(
+        events,
+        vec![
+            ProgramEvent::OrderFilled(filled),
+            ProgramEvent::OrderPlaced(placed)
+        ]
+    );
+}
+
+#[test]
+fn test_parse_return_data_takes_the_last_of_the_program() {
+    let program = anchor_bpf_template::ID;
+    let other = Pubkey::new_unique();
+    let logs = vec![
+        format!("Program {} invoke [1]", program),
+        format!("Program return: {} {}", program, base64::encode([1, 2])),
+        format!("Program {} success", program),
+        format!("Program {} invoke [1]", program),
+        format!("Program return: {} {}", program, base64::encode([3])),
+        format!("Program {} success", program),
+        format!("Program return: {} {}", other, base64::encode([4])),
+    ];
+    assert_eq!(parse_return_data(&logs), Some((program, vec![3])));
+    assert_eq!(parse_return_data(&logs[..1]), None);
+}